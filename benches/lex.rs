@@ -0,0 +1,58 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use copager::lang::Lang;
+use copager::lex::{BaseLexer, DfaLexer, RegexLexer};
+
+use example_lang_json::syntax::Json;
+
+// JSON のテストケース (正しい入力のみ) を連結したもの
+fn json_corpus() -> String {
+    let mut paths = fs::read_dir("tests/json/success")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let cases = paths
+        .into_iter()
+        .map(|path| fs::read_to_string(path).unwrap())
+        .collect::<Vec<_>>();
+    format!("[{}]", cases.join(","))
+}
+
+fn lex_all<L, Lx>(lexer: &Lx, input: &str) -> usize
+where
+    L: Lang,
+    Lx: BaseLexer<L>,
+{
+    lexer.run(input).count()
+}
+
+fn bench_lexers(c: &mut Criterion) {
+    let corpus = json_corpus();
+    let inputs = [
+        ("corpus", corpus.clone()),
+        ("corpus_x64", format!("[{}]", vec![corpus; 64].join(","))),
+    ];
+
+    let regex_lexer = <RegexLexer<Json> as BaseLexer<Json>>::init().unwrap();
+    let dfa_lexer = <DfaLexer<Json> as BaseLexer<Json>>::init().unwrap();
+
+    let mut group = c.benchmark_group("lex_json");
+    for (name, input) in &inputs {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("RegexLexer", name), input, |b, input| {
+            b.iter(|| lex_all::<Json, _>(&regex_lexer, input))
+        });
+        group.bench_with_input(BenchmarkId::new("DfaLexer", name), input, |b, input| {
+            b.iter(|| lex_all::<Json, _>(&dfa_lexer, input))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lexers);
+criterion_main!(benches);
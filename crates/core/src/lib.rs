@@ -1,5 +1,10 @@
 mod generator;
 mod processor;
+mod session;
 
 pub use generator::Generator;
-pub use processor::Processor;
+pub use processor::{Processor, BuiltProcessor, Unbuilt};
+pub use session::{ParserSession, SessionSnapshot};
+pub use copager_utils::cache::CacheError;
+pub use copager_utils::error::{Error, GrammarError, TokenInfo, PrettyError};
+pub use copager_utils::error::{Diagnostic, Label, Severity};
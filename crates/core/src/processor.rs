@@ -1,124 +1,396 @@
+use std::cell::RefCell;
+use std::io::Read;
 use std::marker::PhantomData;
+use std::rc::Rc;
 
 use serde::{Serialize, Deserialize};
 use serde_cbor::ser::to_vec_packed;
 use serde_cbor::de::from_slice;
 
-use copager_lex::BaseLexer;
-use copager_parse::{BaseParser, ParseEvent};
-use copager_ir::{IR, IRBuilder};
-use copager_utils::cache::Cacheable;
+use copager_lang::token::{TokenOwned, TokenSource};
+use copager_lang::Lang;
+use copager_lex::mode::LexState;
+use copager_lex::{BaseLexer, ContextualLexer, StreamLexer, TokenFilter, TokenStream, FilterChain};
+use copager_parse::{BaseParser, IncrementalParser, ParseEvent};
+use copager_ir::{IR, IRBuilder, IROwned, IROwnedBuilder};
+use copager_utils::cache::{fingerprint, Cacheable, CacheError};
+use copager_utils::error::Error;
 
 use crate::generator::GeneratorDesign;
+use crate::session::ParserSession;
+
+// 字句解析器・構文解析器が未構築であることを表すマーカー
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Unbuilt;
+
+type TokenTagOf<Gen> = <<Gen as GeneratorDesign>::Lang as Lang>::TokenTag;
+
+// 字句解析器・構文解析器が共に構築済みの `Processor`
+pub type BuiltProcessor<Gen> = Processor<
+    Gen,
+    <Gen as GeneratorDesign>::Lexer,
+    <Gen as GeneratorDesign>::Parser,
+>;
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Processor<Gen: GeneratorDesign> {
+#[serde(bound(serialize = "", deserialize = "Lexer: Default, Parser: Default"))]
+pub struct Processor<Gen, Lexer = Unbuilt, Parser = Unbuilt>
+where
+    Gen: GeneratorDesign,
+{
     // Cache
-    cache_lex: Option<Vec<u8>>,
-    cache_parse: Option<Vec<u8>>,
+    cache_lex: Option<CacheEntry>,
+    cache_parse: Option<CacheEntry>,
 
     // Driver
-    #[serde(skip, default="Option::default")]
-    lexer: Option<Gen::Lexer>,
-    #[serde(skip, default="Option::default")]
-    parser: Option<Gen::Parser>,
+    #[serde(skip)]
+    lexer: Lexer,
+    #[serde(skip)]
+    parser: Parser,
+    #[serde(skip)]
+    filters: FilterChain<Gen::Lang>,
 
     // Phantom
     #[serde(skip)]
-    _phantom_lang: PhantomData<Gen::Lang>,
-    #[serde(skip)]
     _phantom_gen: PhantomData<Gen>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: u64,
+    body: Vec<u8>,
+}
+
 impl<Gen: GeneratorDesign> Processor<Gen> {
     pub fn new() -> Self {
         Processor {
             cache_lex: None,
             cache_parse: None,
-            lexer: None,
-            parser: None,
-            _phantom_lang: PhantomData,
+            lexer: Unbuilt,
+            parser: Unbuilt,
+            filters: FilterChain::default(),
             _phantom_gen: PhantomData,
         }
     }
 
-    pub fn build(self) -> anyhow::Result<Self> {
+    pub fn build(self) -> Result<BuiltProcessor<Gen>, Error> {
         self.build_lexer()?
             .build_parser()
     }
+}
+
+impl<Gen: GeneratorDesign> Default for Processor<Gen> {
+    fn default() -> Self {
+        Processor::new()
+    }
+}
 
-    pub fn build_lexer(mut self) -> anyhow::Result<Self> {
+impl<Gen, Parser> Processor<Gen, Unbuilt, Parser>
+where
+    Gen: GeneratorDesign,
+{
+    pub fn build_lexer(self) -> Result<Processor<Gen, Gen::Lexer, Parser>, Error> {
         let lexer = Gen::Lexer::init()?;
-        self.lexer = Some(lexer);
+        Ok(self.with_lexer(lexer))
+    }
 
-        Ok(self)
+    fn with_lexer(self, lexer: Gen::Lexer) -> Processor<Gen, Gen::Lexer, Parser> {
+        Processor {
+            cache_lex: self.cache_lex,
+            cache_parse: self.cache_parse,
+            lexer,
+            parser: self.parser,
+            filters: self.filters,
+            _phantom_gen: PhantomData,
+        }
     }
+}
 
-    pub fn build_parser(mut self) -> anyhow::Result<Self> {
+impl<Gen, Lexer> Processor<Gen, Lexer, Unbuilt>
+where
+    Gen: GeneratorDesign,
+{
+    pub fn build_parser(self) -> Result<Processor<Gen, Lexer, Gen::Parser>, Error> {
         let parser = Gen::Parser::init()?;
-        self.parser = Some(parser);
+        Ok(self.with_parser(parser))
+    }
 
-        Ok(self)
+    fn with_parser(self, parser: Gen::Parser) -> Processor<Gen, Lexer, Gen::Parser> {
+        Processor {
+            cache_lex: self.cache_lex,
+            cache_parse: self.cache_parse,
+            lexer: self.lexer,
+            parser,
+            filters: self.filters,
+            _phantom_gen: PhantomData,
+        }
     }
+}
 
-    pub fn process<'input, I>(&self, input: &'input str) -> anyhow::Result<I>
+impl<Gen, Lexer, Parser> Processor<Gen, Lexer, Parser>
+where
+    Gen: GeneratorDesign,
+{
+    // 字句解析器と構文解析器の間で字句列を書き換えるフィルタを登録する (登録した順に適用される)
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: TokenFilter<Gen::Lang> + Send + Sync + 'static,
+    {
+        self.filters.push(filter);
+        self
+    }
+}
+
+impl<Gen: GeneratorDesign> BuiltProcessor<Gen> {
+    fn reject_filters(&self, method: &'static str) -> Result<(), Error> {
+        match self.filters.is_empty() {
+            true => Ok(()),
+            false => Err(Error::UnsupportedFilter { method }),
+        }
+    }
+
+    pub fn process<'input, I>(&self, input: &'input str) -> Result<I, Error>
     where
         I: IR<'input, Gen::Lang>,
     {
-        let lexer = self.lexer.as_ref().unwrap();
-        let parser = self.parser.as_ref().unwrap();
+        self.process_source(input, |tokens| self.filters.apply(tokens))
+    }
+
+    fn process_source<'a, 'input: 'a, S, I, F>(&'a self, input: &'input S, filter: F) -> Result<I, Error>
+    where
+        S: TokenSource + ?Sized,
+        Gen::Lexer: BaseLexer<Gen::Lang, S>,
+        I: IR<'input, Gen::Lang, S>,
+        F: FnOnce(TokenStream<'a, 'input, TokenTagOf<Gen>, S>) -> TokenStream<'a, 'input, TokenTagOf<Gen>, S>,
+    {
+        // 字句解析が停止した位置とモードを記録する (フィルタによる書き換えの前に記録する)
+        let lexed = Rc::new(RefCell::new(LexState::new()));
+        let lexer = BaseLexer::<Gen::Lang, S>::run_tracked(&self.lexer, input, Rc::clone(&lexed));
+        let tokens = filter(Box::new(lexer));
 
         let mut ir_builder = I::Builder::new();
-        for result in parser.run(lexer.run(input)) {
+        for result in self.parser.run(tokens) {
             match result {
                 ParseEvent::Read(token) => ir_builder.on_read(token)?,
-                ParseEvent::Parse{ rule,len } => ir_builder.on_parse(rule, len)?,
+                ParseEvent::Parse{ rule, alt, len } => ir_builder.on_reduce(rule, alt, len)?,
+                ParseEvent::Err(err @ Error::Syntax { found: None, .. }) => {
+                    BaseLexer::<Gen::Lang, S>::check_rest(&self.lexer, input, &lexed.borrow())?;
+                    return Err(err);
+                }
                 ParseEvent::Err(err) => return Err(err),
             }
         }
+        BaseLexer::<Gen::Lang, S>::check_rest(&self.lexer, input, &lexed.borrow())?;
+
+        ir_builder.build()
+    }
+}
+
+impl<Gen> BuiltProcessor<Gen>
+where
+    Gen: GeneratorDesign<Lexer: BaseLexer<Gen::Lang, [u8]>>,
+{
+    // バイト列を入力として解析する (字句解析器がバイト列に対応している必要がある)
+    // フィルタは文字列の字句列のみを対象とするため，登録されている場合はエラーとなる
+    pub fn process_bytes<'input, I>(&self, input: &'input [u8]) -> Result<I, Error>
+    where
+        I: IR<'input, Gen::Lang, [u8]>,
+    {
+        self.reject_filters("process_bytes")?;
+        self.process_source(input, |tokens| tokens)
+    }
+}
+
+impl<Gen> BuiltProcessor<Gen>
+where
+    Gen: GeneratorDesign<
+        Lexer: ContextualLexer<Gen::Lang>,
+        Parser: IncrementalParser<Gen::Lang>,
+    >,
+{
+    // 入力全体を保持せずに io::Read から読み込みながら解析する (モードは読み込みの単位をまたいで引き継がれる)
+    // フィルタは字句列全体を 1 つの入力として扱うため，登録されている場合はエラーとなる
+    pub fn process_reader<I, R>(&self, reader: R) -> Result<I, Error>
+    where
+        I: IROwned<Gen::Lang>,
+        R: Read,
+    {
+        self.reject_filters("process_reader")?;
+
+        let mut stream = StreamLexer::new(&self.lexer, reader);
+        let mut state = self.parser.start();
+        let mut ir_builder = I::Builder::new();
+
+        // 字句の位置は窓の先頭からの相対位置となるため，最後に字句を読んだ窓の位置を記録する
+        let mut offset = 0;
+        while let Some(window) = stream.next_window()? {
+            if !window.tokens.is_empty() {
+                offset = window.offset;
+            }
+            for token in window.tokens {
+                for event in Gen::Parser::feed(&mut state, Some(token)) {
+                    match event {
+                        ParseEvent::Read(token) => {
+                            ir_builder.on_read(TokenOwned::from_token(&token, offset))?
+                        }
+                        ParseEvent::Parse{ rule, alt, len } => ir_builder.on_reduce(rule, alt, len)?,
+                        ParseEvent::Err(err) => return Err(err.shift(offset)),
+                    }
+                }
+            }
+        }
+        for event in Gen::Parser::feed::<str>(&mut state, None) {
+            match event {
+                ParseEvent::Parse{ rule, alt, len } => ir_builder.on_reduce(rule, alt, len)?,
+                ParseEvent::Err(err) => return Err(err.shift(offset)),
+                ParseEvent::Read(_) => unreachable!(),
+            }
+        }
+
+        ir_builder.build()
+    }
+}
+
+impl<Gen> BuiltProcessor<Gen>
+where
+    Gen: GeneratorDesign<
+        Lexer: ContextualLexer<Gen::Lang>,
+        Parser: IncrementalParser<Gen::Lang>,
+    >,
+{
+    // 構文解析器の状態から受理可能な字句のみを試しながら解析する
+    // フィルタは先読みした字句を書き換えうるため，登録されている場合はエラーとなる
+    pub fn process_contextual<'input, I>(&self, input: &'input str) -> Result<I, Error>
+    where
+        I: IR<'input, Gen::Lang>,
+    {
+        self.reject_filters("process_contextual")?;
+
+        let mut lex_state = self.lexer.start();
+        let mut lexed = LexState::new();
+        let mut state = self.parser.start();
+        let mut ir_builder = I::Builder::new();
+
+        loop {
+            // 受理可能な字句が見つからない場合は全ての字句を試し，構文エラーとして報告させる
+            let acceptable = Gen::Parser::acceptable(&state);
+            let token = self.lexer
+                .next_token(&mut lex_state, input, Some(&acceptable))
+                .or_else(|| self.lexer.next_token(&mut lex_state, input, None));
+            if let Some(token) = &token {
+                lexed.advance(token);
+            }
+            let is_eof = token.is_none();
+
+            for event in Gen::Parser::feed(&mut state, token) {
+                match event {
+                    ParseEvent::Read(token) => ir_builder.on_read(token)?,
+                    ParseEvent::Parse{ rule, alt, len } => ir_builder.on_reduce(rule, alt, len)?,
+                    ParseEvent::Err(err @ Error::Syntax { found: None, .. }) => {
+                        self.lexer.check_rest(input, &lexed)?;
+                        return Err(err);
+                    }
+                    ParseEvent::Err(err) => return Err(err),
+                }
+            }
+            if is_eof {
+                break;
+            }
+        }
+        self.lexer.check_rest(input, &lexed)?;
 
         ir_builder.build()
     }
 }
 
-impl<Gen> Processor<Gen>
+impl<Gen, Lexer> Processor<Gen, Lexer, Gen::Parser>
+where
+    Gen: GeneratorDesign<Parser: IncrementalParser<Gen::Lang>>,
+{
+    pub fn session<'input, I>(&self) -> ParserSession<'_, 'input, Gen, I>
+    where
+        I: IR<'input, Gen::Lang>,
+    {
+        ParserSession::new(&self.parser)
+    }
+}
+
+impl<Gen, Lexer, Parser> Processor<Gen, Lexer, Parser>
 where
     Gen: GeneratorDesign<Lexer: Cacheable<()>>,
 {
-    pub fn prebuild_lexer(mut self) -> anyhow::Result<Self> {
+    pub fn prebuild_lexer(mut self) -> Result<Self, Error> {
         let cache_lex = Gen::Lexer::cache(())?;
-        self.cache_lex = Some(to_vec_packed(&cache_lex)?);
+        self.cache_lex = Some(CacheEntry::new::<Gen, _>(&cache_lex, "lexer")?);
 
         Ok(self)
     }
+}
 
-    pub fn restore_lexer_by_cache(mut self) -> Self {
-        let cache_lex = self.cache_lex.as_ref().unwrap();
-        let cache_lex = from_slice(cache_lex).unwrap();
+impl<Gen, Parser> Processor<Gen, Unbuilt, Parser>
+where
+    Gen: GeneratorDesign<Lexer: Cacheable<()>>,
+{
+    pub fn restore_lexer_by_cache(self) -> Result<Processor<Gen, Gen::Lexer, Parser>, CacheError> {
+        let cache_lex = CacheEntry::restore::<Gen, _>(self.cache_lex.as_ref(), "lexer")?;
         let lexer = Gen::Lexer::restore(cache_lex);
-        self.lexer = Some(lexer);
-
-        self
+        Ok(self.with_lexer(lexer))
     }
 }
 
-impl<Gen> Processor<Gen>
+impl<Gen, Lexer, Parser> Processor<Gen, Lexer, Parser>
 where
-    Gen: GeneratorDesign< Parser: Cacheable<()>>,
+    Gen: GeneratorDesign<Parser: Cacheable<()>>,
 {
-    pub fn prebuild_parser(mut self) -> anyhow::Result<Self> {
+    pub fn prebuild_parser(mut self) -> Result<Self, Error> {
         let cache_parse = Gen::Parser::cache(())?;
-        self.cache_parse = Some(to_vec_packed(&cache_parse)?);
+        self.cache_parse = Some(CacheEntry::new::<Gen, _>(&cache_parse, "parser")?);
 
         Ok(self)
     }
+}
 
-    pub fn restore_parser_by_cache(mut self) -> Self {
-        let cache_parse = self.cache_parse.as_ref().unwrap();
-        let cache_parse = from_slice(cache_parse).unwrap();
+impl<Gen, Lexer> Processor<Gen, Lexer, Unbuilt>
+where
+    Gen: GeneratorDesign<Parser: Cacheable<()>>,
+{
+    pub fn restore_parser_by_cache(self) -> Result<Processor<Gen, Lexer, Gen::Parser>, CacheError> {
+        let cache_parse = CacheEntry::restore::<Gen, _>(self.cache_parse.as_ref(), "parser")?;
         let parser = Gen::Parser::restore(cache_parse);
-        self.parser = Some(parser);
+        Ok(self.with_parser(parser))
+    }
+}
 
-        self
+impl CacheEntry {
+    fn new<Gen, C>(cache: &C, target: &'static str) -> Result<Self, CacheError>
+    where
+        Gen: GeneratorDesign,
+        C: Serialize,
+    {
+        let body = to_vec_packed(cache)
+            .map_err(|err| CacheError::Unserializable(target, err.to_string()))?;
+        Ok(CacheEntry {
+            fingerprint: fingerprint::<Gen::Lang>(),
+            body,
+        })
+    }
+
+    fn restore<Gen, C>(entry: Option<&CacheEntry>, target: &'static str) -> Result<C, CacheError>
+    where
+        Gen: GeneratorDesign,
+        C: for<'de> Deserialize<'de>,
+    {
+        let entry = entry.ok_or(CacheError::NotFound(target))?;
+
+        let expected = fingerprint::<Gen::Lang>();
+        if entry.fingerprint != expected {
+            return Err(CacheError::GrammarMismatch {
+                target,
+                expected,
+                found: entry.fingerprint,
+            });
+        }
+
+        from_slice(&entry.body).map_err(|err| CacheError::Corrupted(target, err.to_string()))
     }
 }
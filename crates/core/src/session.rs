@@ -0,0 +1,162 @@
+use copager_lang::token::Token;
+use copager_lang::Lang;
+use copager_parse::{IncrementalParser, ParseEvent};
+use copager_ir::{IR, IRBuilder};
+use copager_utils::error::Error;
+
+use crate::generator::GeneratorDesign;
+
+type TokenTag<Gen> = <<Gen as GeneratorDesign>::Lang as Lang>::TokenTag;
+type ParserState<'parser, Gen> = <<Gen as GeneratorDesign>::Parser as IncrementalParser<
+    <Gen as GeneratorDesign>::Lang,
+>>::State<'parser>;
+
+// トークンを 1 つずつ受け取りながら構文解析を進めるセッション
+pub struct ParserSession<'parser, 'input, Gen, I>
+where
+    Gen: GeneratorDesign<Parser: IncrementalParser<Gen::Lang>> + 'parser,
+    I: IR<'input, Gen::Lang>,
+{
+    state: ParserState<'parser, Gen>,
+    builder: I::Builder,
+    poisoned: bool,  // IRBuilder がエラーを返した (構文解析器の状態と IRBuilder が対応しない)
+}
+
+// ParserSession::restore により巻き戻すための状態
+pub struct SessionSnapshot<'parser, 'input, Gen, I>
+where
+    Gen: GeneratorDesign<Parser: IncrementalParser<Gen::Lang>> + 'parser,
+    I: IR<'input, Gen::Lang>,
+{
+    state: ParserState<'parser, Gen>,
+    builder: I::Builder,
+    poisoned: bool,
+}
+
+impl<'parser, 'input, Gen, I> SessionSnapshot<'parser, 'input, Gen, I>
+where
+    Gen: GeneratorDesign<Parser: IncrementalParser<Gen::Lang>> + 'parser,
+    I: IR<'input, Gen::Lang>,
+{
+    pub fn state(&self) -> &ParserState<'parser, Gen> {
+        &self.state
+    }
+}
+
+impl<'parser, 'input, Gen, I> ParserSession<'parser, 'input, Gen, I>
+where
+    Gen: GeneratorDesign<Parser: IncrementalParser<Gen::Lang>> + 'parser,
+    I: IR<'input, Gen::Lang>,
+{
+    pub(crate) fn new(parser: &'parser Gen::Parser) -> Self {
+        ParserSession {
+            state: parser.start(),
+            builder: I::Builder::new(),
+            poisoned: false,
+        }
+    }
+
+    // 構文エラーとなるトークンは受理せず，セッションは直前の状態のまま残る
+    // IRBuilder がエラーを返した場合，以降のトークンは受け付けない
+    pub fn feed(&mut self, token: Token<'input, TokenTag<Gen>>) -> Result<(), Error> {
+        self.check_poisoned()?;
+
+        // 構文エラーの場合のみ，エラーを得るために複製した状態へ読み込む
+        if !Gen::Parser::accepts(&self.state, &token.kind) {
+            let mut state = self.state.clone();
+            return match Gen::Parser::feed(&mut state, Some(token)).pop() {
+                Some(ParseEvent::Err(err)) => Err(err),
+                _ => unreachable!(),
+            };
+        }
+
+        let events = Gen::Parser::feed(&mut self.state, Some(token));
+        self.apply(events)
+    }
+
+    pub fn finish(mut self) -> Result<I, Error> {
+        self.check_poisoned()?;
+
+        let events = Gen::Parser::feed(&mut self.state, None);
+        self.apply(events)?;
+
+        self.builder.build()
+    }
+
+    // ここで入力が終了した場合に受理されるか
+    pub fn is_accepting_prefix(&self) -> bool {
+        Gen::Parser::accepts_eof(&self.state)
+    }
+
+    fn check_poisoned(&self) -> Result<(), Error> {
+        if self.poisoned {
+            return Err(Error::IR {
+                message: "ParserSession cannot continue after an IR error".to_string(),
+                span: None,
+            });
+        }
+        Ok(())
+    }
+
+    fn apply(
+        &mut self,
+        mut events: Vec<ParseEvent<'input, TokenTag<Gen>, <Gen::Lang as Lang>::RuleTag>>,
+    ) -> Result<(), Error> {
+        // エラーで終わる場合は IRBuilder にイベントを渡さない
+        match events.pop() {
+            Some(ParseEvent::Err(err)) => return Err(err),
+            Some(event) => events.push(event),
+            None => {}
+        }
+
+        for event in events {
+            let result = match event {
+                ParseEvent::Read(token) => self.builder.on_read(token),
+                ParseEvent::Parse { rule, alt, len } => self.builder.on_reduce(rule, alt, len),
+                ParseEvent::Err(err) => Err(err),
+            };
+            if result.is_err() {
+                self.poisoned = true;
+                return result;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// スナップショットは IRBuilder の複製を含むため，IRBuilder が Clone を実装する場合のみ用いることができる
+impl<'parser, 'input, Gen, I> ParserSession<'parser, 'input, Gen, I>
+where
+    Gen: GeneratorDesign<Parser: IncrementalParser<Gen::Lang>> + 'parser,
+    I: IR<'input, Gen::Lang, Builder: Clone>,
+{
+    pub fn snapshot(&self) -> SessionSnapshot<'parser, 'input, Gen, I> {
+        SessionSnapshot {
+            state: self.state.clone(),
+            builder: self.builder.clone(),
+            poisoned: self.poisoned,
+        }
+    }
+
+    // snapshot を取得した時点の状態に巻き戻す
+    pub fn restore(&mut self, snapshot: SessionSnapshot<'parser, 'input, Gen, I>) {
+        self.state = snapshot.state;
+        self.builder = snapshot.builder;
+        self.poisoned = snapshot.poisoned;
+    }
+}
+
+impl<'parser, 'input, Gen, I> Clone for ParserSession<'parser, 'input, Gen, I>
+where
+    Gen: GeneratorDesign<Parser: IncrementalParser<Gen::Lang>> + 'parser,
+    I: IR<'input, Gen::Lang, Builder: Clone>,
+{
+    fn clone(&self) -> Self {
+        ParserSession {
+            state: self.state.clone(),
+            builder: self.builder.clone(),
+            poisoned: self.poisoned,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Serialize, Deserialize};
+
+use copager_core::{Error, Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexBytesLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+use copager_ir_sexp::SExpOwned;
+
+#[derive(Lang, Serialize, Deserialize)]
+struct ConfigLang (
+    #[tokenset] ConfigToken,
+    #[ruleset]  ConfigRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum ConfigToken {
+    #[token(r"=")]
+    Assign,
+    #[token(r"[a-z]+")]
+    Key,
+    #[token(r#""(?-u:[^"\n])*""#)]
+    Value,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum ConfigRule {
+    #[tokenset(ConfigToken)]
+    #[rule("<entries> ::= <entries> <entry>")]
+    #[rule("<entries> ::= <entry>")]
+    Entries,
+    #[rule("<entry> ::= Key Assign Value")]
+    Entry,
+}
+
+type MyGenerator<T> = Generator<T, RegexBytesLexer<T>, LR1<T>>;
+type MyProcessor = Processor<MyGenerator<ConfigLang>>;
+
+#[test]
+fn process_bytes() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    // Latin-1 の "é" (0xE9) は UTF-8 として不正なバイト
+    let input = b"name = \"caf\xE9\"\nsize = \"10\"";
+    processor.process_bytes::<Void>(input)?;
+
+    let sexp = processor.process_bytes::<SExpOwned<_>>(input)?;
+    assert_eq!(
+        sexp.to_string(),
+        "(Entries (Entries (Entry \"name\" \"=\" \"\\\"caf\u{FFFD}\\\"\")) (Entry \"size\" \"=\" \"\\\"10\\\"\"))",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn process_bytes_error() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    let err = processor.process_bytes::<Void>(b"name = \xE9").unwrap_err();
+    assert!(matches!(err, Error::Lex { span: (7, 8), .. }));
+
+    let err = processor.process_bytes::<Void>(b"name = \"\xE9\" =").unwrap_err();
+    assert!(matches!(err, Error::Syntax { span: (11, 12), .. }));
+
+    Ok(())
+}
+
+#[test]
+fn process_str() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+    processor.process::<Void>("name = \"café\"")?;
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use serde::{Serialize, Deserialize};
+
+use copager_core::{Error, Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+use copager_ir_sexp::SExp;
+
+#[derive(Lang, Serialize, Deserialize)]
+struct LetLang (
+    #[tokenset] LetToken,
+    #[ruleset]  LetRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum LetToken {
+    #[token(r"let")]
+    Let,
+    #[token(r"=")]
+    Assign,
+    #[token(r">>")]
+    Shr,
+    #[token(r"<")]
+    AngleL,
+    #[token(r">")]
+    AngleR,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[a-z]+")]
+    Ident,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum LetRule {
+    #[tokenset(LetToken)]
+    #[rule("<stmt> ::= Let Ident Assign <expr>")]
+    Stmt,
+    #[rule("<expr> ::= <expr> Shr Num")]
+    #[rule("<expr> ::= <type>")]
+    Expr,
+    #[rule("<type> ::= Ident AngleL <type> AngleR")]
+    #[rule("<type> ::= Ident")]
+    Type,
+}
+
+type MyGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type MyProcessor = Processor<MyGenerator<LetLang>>;
+
+#[test]
+fn keyword_as_ident() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    // 通常の字句解析では 2 つ目の let がキーワードとして扱われる
+    assert!(processor.process::<Void>("let let = a").is_err());
+
+    let sexp = processor.process_contextual::<SExp<_>>("let let = a")?;
+    assert_eq!(sexp.to_string(), r#"(Stmt "let" "let" "=" (Expr (Type "a")))"#);
+
+    Ok(())
+}
+
+#[test]
+fn nested_angle_brackets() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    assert!(processor.process::<Void>("let x = a<b<c>>").is_err());
+    processor.process_contextual::<Void>("let x = a<b<c>>")?;
+    processor.process_contextual::<Void>("let x = a<b> >> 1")?;
+
+    let sexp = processor.process_contextual::<SExp<_>>("let x = a >> 2")?;
+    assert_eq!(sexp.to_string(), r#"(Stmt "let" "x" "=" (Expr (Expr (Type "a")) ">>" "2"))"#);
+
+    Ok(())
+}
+
+#[test]
+fn contextual_error() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    let err = processor.process_contextual::<Void>("let x = = a").unwrap_err();
+    assert!(matches!(err, Error::Syntax { found: Some(_), span: (8, 9), .. }));
+
+    let err = processor.process_contextual::<Void>("let x = a ?").unwrap_err();
+    assert!(matches!(err, Error::Lex { span: (10, 11), .. }));
+
+    let err = processor.process_contextual::<Void>("let x =").unwrap_err();
+    assert!(matches!(err, Error::Syntax { found: None, .. }));
+
+    Ok(())
+}
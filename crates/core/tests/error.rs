@@ -0,0 +1,131 @@
+use serde::{Serialize, Deserialize};
+
+use copager_core::{Error, Generator, GrammarError, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+
+#[derive(Lang, Serialize, Deserialize)]
+struct ExprLang (
+    #[tokenset] ExprToken,
+    #[ruleset]  ExprRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum ExprToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"-")]
+    Minus,
+    #[token(r"\*")]
+    Mul,
+    #[token(r"/")]
+    Div,
+    #[token(r"\(")]
+    BracketL,
+    #[token(r"\)")]
+    BracketR,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum ExprRule {
+    #[tokenset(ExprToken)]
+    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= <expr> Minus <term>")]
+    #[rule("<expr> ::= <term>")]
+    Expr,
+    #[rule("<term> ::= <term> Mul <num>")]
+    #[rule("<term> ::= <term> Div <num>")]
+    #[rule("<term> ::= <num>")]
+    Term,
+    #[rule("<num> ::= BracketL <expr> BracketR")]
+    #[rule("<num> ::= Num")]
+    Num,
+}
+
+type MyGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type MyProcessor = Processor<MyGenerator<ExprLang>>;
+
+#[test]
+fn error_lex() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    let err = processor.process::<Void>("1 + $").unwrap_err();
+    assert!(matches!(err, Error::Lex { span: (4, 5), .. }));
+
+    let err = processor.process::<Void>("1 + 2 $").unwrap_err();
+    assert!(matches!(err, Error::Lex { span: (6, 7), .. }));
+
+    Ok(())
+}
+
+#[test]
+fn error_syntax() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    match processor.process::<Void>("1 + * 2").unwrap_err() {
+        Error::Syntax { found: Some(found), expected, span } => {
+            assert_eq!(found.kind, "Mul");
+            assert_eq!(found.text, "*");
+            assert_eq!(expected, vec!["BracketL", "Num"]);
+            assert_eq!(span, (4, 5));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+
+    match processor.process::<Void>("1 +").unwrap_err() {
+        Error::Syntax { found: None, span, .. } => assert_eq!(span, (3, 3)),
+        err => panic!("unexpected error: {}", err),
+    }
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Lang)]
+struct AmbiguousLang (
+    #[tokenset] ExprToken,
+    #[ruleset]  AmbiguousRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum AmbiguousRule {
+    #[tokenset(ExprToken)]
+    #[rule("<expr> ::= <expr> Plus <expr>")]
+    #[rule("<expr> ::= Num")]
+    Expr,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Lang)]
+struct UndefinedLang (
+    #[tokenset] ExprToken,
+    #[ruleset]  UndefinedRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum UndefinedRule {
+    #[tokenset(ExprToken)]
+    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= Num")]
+    Expr,
+}
+
+#[test]
+fn error_grammar() {
+    let result = Processor::<MyGenerator<AmbiguousLang>>::new().build();
+    assert!(matches!(result, Err(Error::Grammar(GrammarError::Conflict { .. }))));
+
+    let result = Processor::<MyGenerator<UndefinedLang>>::new().build();
+    match result {
+        Err(Error::Grammar(GrammarError::UndefinedSymbol { symbol, .. })) => assert_eq!(symbol, "term"),
+        _ => panic!("undefined symbol is not detected"),
+    }
+}
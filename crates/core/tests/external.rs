@@ -0,0 +1,205 @@
+use std::ops::Range;
+
+use logos::Logos;
+use serde::{Serialize, Deserialize};
+
+use copager_core::{Error, Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::{logos_tokens, BaseLexer, ExternalLexer, LogosLexer, Tokenizer};
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+use copager_ir_sexp::SExp;
+
+#[derive(Lang, Serialize, Deserialize)]
+struct ExprLang (
+    #[tokenset] ExprToken,
+    #[ruleset]  ExprRule,
+);
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum ExprToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(r"#[^\n]*", trivia, comment)]
+    Comment,
+    #[token(r";[^\n]*", post_trivia, comment)]
+    Note,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum ExprRule {
+    #[tokenset(ExprToken)]
+    #[rule("<expr> ::= <expr> Plus Num")]
+    #[rule("<expr> ::= Num")]
+    Expr,
+}
+
+// 手書きの字句解析器 (空白は読み飛ばす)
+struct HandTokenizer;
+
+impl Tokenizer<ExprLang> for HandTokenizer {
+    fn init() -> copager_utils::error::Result<Self> {
+        Ok(HandTokenizer)
+    }
+
+    fn tokenize(&self, input: &str)
+        -> impl Iterator<Item = (ExprToken, Range<usize>)>
+    {
+        let mut pos = 0;
+        std::iter::from_fn(move || {
+            let rest = &input[pos..];
+            let begin = pos + rest.len() - rest.trim_start().len();
+            let rest = &input[begin..];
+            let len = match rest.chars().next()? {
+                '+' => 1,
+                '#' | ';' => rest.find('\n').unwrap_or(rest.len()),
+                c if c.is_ascii_digit() => rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len()),
+                _ => return None,
+            };
+            let kind = match rest.as_bytes()[0] {
+                b'+' => ExprToken::Plus,
+                b'#' => ExprToken::Comment,
+                b';' => ExprToken::Note,
+                _ => ExprToken::Num,
+            };
+            pos = begin + len;
+            Some((kind, begin..pos))
+        })
+    }
+}
+
+// logos による字句解析器 (列挙子名により ExprToken と対応付ける)
+logos_tokens! {
+    #[tokenset(ExprToken)]
+    #[derive(Debug, Logos)]
+    #[logos(skip r"[ \t\n]+")]
+    enum LogosToken {
+        #[token("+")]
+        Plus,
+        #[regex("[0-9]+", |lex| lex.slice().parse::<u32>().ok())]
+        Num(#[allow(dead_code)] u32),
+        #[regex("#[^\n]*")]
+        Comment,
+        #[regex(";[^\n]*")]
+        Note,
+    }
+}
+
+// ExprToken に対応しない字句を含む logos の字句解析器
+#[derive(Debug, Logos)]
+#[logos(skip r"[ \t\n]+")]
+enum PartialToken {
+    #[token("+")]
+    Plus,
+    #[regex("[0-9]+")]
+    Num,
+    #[token("**")]
+    Pow,
+}
+
+impl TryFrom<PartialToken> for ExprToken {
+    type Error = ();
+
+    fn try_from(token: PartialToken) -> Result<Self, ()> {
+        match token {
+            PartialToken::Plus => Ok(ExprToken::Plus),
+            PartialToken::Num => Ok(ExprToken::Num),
+            PartialToken::Pow => Err(()),
+        }
+    }
+}
+
+type HandLexer = ExternalLexer<ExprLang, HandTokenizer>;
+type HandProcessor = Processor<Generator<ExprLang, HandLexer, LR1<ExprLang>>>;
+type MyLogosLexer = LogosLexer<ExprLang, LogosToken>;
+type LogosProcessor = Processor<Generator<ExprLang, MyLogosLexer, LR1<ExprLang>>>;
+type PartialProcessor = Processor<Generator<ExprLang, LogosLexer<ExprLang, PartialToken>, LR1<ExprLang>>>;
+
+#[test]
+fn external_span() -> anyhow::Result<()> {
+    let lexer = HandLexer::init()?;
+
+    let input = "# head\n1 + 2 ; note\n+ 3";
+    let tokens = lexer.run(input).collect::<Vec<_>>();
+    let kinds = tokens.iter().map(|token| token.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, [ExprToken::Num, ExprToken::Plus, ExprToken::Num, ExprToken::Plus, ExprToken::Num]);
+
+    // 読み飛ばされた空白と Trivia は full に含まれる
+    assert_eq!(tokens[0].body, (7, 8));
+    assert_eq!(tokens[0].full, (0, 8));
+    assert_eq!(tokens[0].leading_trivia().next().unwrap().span, (0, 6));
+    assert_eq!(tokens[1].full, (8, 10));
+    assert_eq!(tokens[2].full, (10, 19));
+    assert_eq!(tokens[2].trailing_trivia().next().unwrap().span, (13, 19));
+    assert_eq!(tokens[3].full, (19, 21));
+    assert_eq!(tokens[4].full, (21, 23));
+
+    Ok(())
+}
+
+#[test]
+fn external_success() -> anyhow::Result<()> {
+    let processor = HandProcessor::new().build()?;
+
+    let sexp = processor.process::<SExp<_>>("1 + 2 # comment\n+ 3")?;
+    assert_eq!(sexp.to_string(), r#"(Expr (Expr (Expr "1") "+" "2") "+" "3")"#);
+
+    Ok(())
+}
+
+#[test]
+fn external_failed() -> anyhow::Result<()> {
+    let processor = HandProcessor::new().build()?;
+
+    let err = processor.process::<Void>("1 + 2 ?").unwrap_err();
+    assert!(matches!(err, Error::Lex { span: (6, 7), .. }));
+
+    let err = processor.process::<Void>("1 + + 2").unwrap_err();
+    assert!(matches!(err, Error::Syntax { .. }));
+
+    Ok(())
+}
+
+#[test]
+fn logos_success() -> anyhow::Result<()> {
+    let processor = LogosProcessor::new().build()?;
+
+    let sexp = processor.process::<SExp<_>>("10 + 20 ; note\n+ 30")?;
+    assert_eq!(sexp.to_string(), r#"(Expr (Expr (Expr "10") "+" "20") "+" "30")"#);
+
+    // Trivia の扱いは手書きの字句解析器と同じ
+    let input = "# head\n1 + 2 ; note\n+ 3";
+    let hand = HandLexer::init()?
+        .run(input)
+        .map(|token| (token.kind, token.body, token.full, token.trivia))
+        .collect::<Vec<_>>();
+    let logos = MyLogosLexer::init()?
+        .run(input)
+        .map(|token| (token.kind, token.body, token.full, token.trivia))
+        .collect::<Vec<_>>();
+    assert_eq!(hand, logos);
+
+    Ok(())
+}
+
+#[test]
+fn logos_failed() -> anyhow::Result<()> {
+    let processor = LogosProcessor::new().build()?;
+
+    let err = processor.process::<Void>("1 + 2 ? 3").unwrap_err();
+    assert!(matches!(err, Error::Lex { span: (6, 7), .. }));
+
+    // TokenTag に変換できない字句はその字句全体を報告する
+    let processor = PartialProcessor::new().build()?;
+    let err = processor.process::<Void>("1 + 2 ** 3").unwrap_err();
+    match err {
+        Error::Lex { found, span } => assert_eq!((found.as_str(), span), ("**", (6, 8))),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    Ok(())
+}
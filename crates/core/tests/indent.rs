@@ -0,0 +1,115 @@
+use serde::{Serialize, Deserialize};
+
+use copager_core::{Error, Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::IndentLexer;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+use copager_ir_sexp::SExp;
+
+#[derive(Lang, Serialize, Deserialize)]
+struct BlockLang (
+    #[tokenset] BlockToken,
+    #[ruleset]  BlockRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum BlockToken {
+    #[token(r":")]
+    Colon,
+    #[token(r"[a-z]+")]
+    Ident,
+    #[token(indent)]
+    Indent,
+    #[token(dedent)]
+    Dedent,
+    #[token(newline)]
+    Newline,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum BlockRule {
+    #[tokenset(BlockToken)]
+    #[rule("<block> ::= <block> <stmt>")]
+    #[rule("<block> ::= <stmt>")]
+    Block,
+    #[rule("<stmt> ::= Ident Newline")]
+    #[rule("<stmt> ::= Ident Colon Newline Indent <block> Dedent")]
+    Stmt,
+}
+
+type MyLexer<T> = IndentLexer<T, RegexLexer<T>>;
+type MyGenerator<T> = Generator<T, MyLexer<T>, LR1<T>>;
+type MyProcessor = Processor<MyGenerator<BlockLang>>;
+
+#[test]
+fn indent_success() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    let input = "a\nb:\n  c\n\n  d:\n\te\nf\n";
+    let sexp = processor.process::<SExp<_>>(input)?;
+    assert_eq!(
+        sexp.to_string(),
+        concat!(
+            r#"(Block (Block (Block (Stmt "a" "")) "#,
+            r#"(Stmt "b" ":" "" "" (Block (Block (Stmt "c" "")) "#,
+            r#"(Stmt "d" ":" "" "" (Block (Stmt "e" "")) "")) "")) "#,
+            r#"(Stmt "f" ""))"#,
+        ),
+    );
+
+    // 入力の終端で開いている字下げは全て閉じられる
+    processor.process::<Void>("a:\n  b:\n    c")?;
+
+    Ok(())
+}
+
+#[test]
+fn indent_failed() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    let err = processor.process::<Void>("a:\n    b\n  c\n").unwrap_err();
+    assert!(matches!(err, Error::Indent { span: (9, 11) }));
+
+    // 誤りのある行に対する Dedent は構文解析器に渡さない
+    let err = processor.process::<Void>("a:\n    b:\n c\n").unwrap_err();
+    assert!(matches!(err, Error::Indent { span: (10, 11) }));
+
+    let err = processor.process::<Void>("a\n  b\n").unwrap_err();
+    assert!(matches!(err, Error::Syntax { .. }));
+
+    let err = processor.process::<Void>("a:\n  b\n  ?\n").unwrap_err();
+    assert!(matches!(err, Error::Lex { span: (9, 10), .. }));
+
+    Ok(())
+}
+
+#[test]
+fn indent_failed_in_threads() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+
+    // 字下げの誤りは解析ごとに記録されるため，他の解析の結果に影響しない
+    std::thread::scope(|scope| {
+        for idx in 0..8 {
+            let processor = &processor;
+            scope.spawn(move || {
+                for _ in 0..50 {
+                    match idx % 2 {
+                        0 => assert!(processor.process::<Void>("a:\n  b\n").is_ok()),
+                        _ => {
+                            let err = processor.process::<Void>("a:\n    b\n  c\n").unwrap_err();
+                            assert!(matches!(err, Error::Indent { span: (9, 11) }));
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
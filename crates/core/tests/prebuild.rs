@@ -1,8 +1,10 @@
 use serde::{Serialize, Deserialize};
 use serde_cbor::ser::to_vec_packed;
 use serde_cbor::de::from_slice;
+use serde_cbor::value::{from_value, to_value, Value};
 
 use copager_core::{Generator, Processor};
+use copager_utils::cache::CacheError;
 use copager_lang::token::{TokenSet, TokenTag};
 use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
 use copager_lang::Lang;
@@ -69,14 +71,57 @@ fn prebuild() -> anyhow::Result<()> {
 }
 
 fn build_rs() -> anyhow::Result<MyProcessor> {
-    MyProcessor::new().prebuild_parser()
+    Ok(MyProcessor::new().prebuild_parser()?)
 }
 
 fn main_rs(processor: MyProcessor) -> anyhow::Result<()> {
     processor
         .build_lexer()?
-        .restore_parser_by_cache()
+        .restore_parser_by_cache()?
         .process::<Void>("1 + 2 * 3")?;
 
     Ok(())
 }
+
+#[test]
+fn prebuild_not_found() {
+    let processor = MyProcessor::new();
+    let result = processor.restore_parser_by_cache();
+    assert!(matches!(result, Err(CacheError::NotFound(_))));
+}
+
+#[test]
+fn prebuild_corrupted() -> anyhow::Result<()> {
+    let processor = tamper(build_rs()?, |cache| {
+        cache.insert(Value::Text("body".to_string()), Value::Array(vec![]));
+    })?;
+    let result = processor.restore_parser_by_cache();
+    assert!(matches!(result, Err(CacheError::Corrupted(_, _))));
+
+    Ok(())
+}
+
+#[test]
+fn prebuild_grammar_mismatch() -> anyhow::Result<()> {
+    let processor = tamper(build_rs()?, |cache| {
+        cache.insert(Value::Text("fingerprint".to_string()), Value::Integer(0));
+    })?;
+    let result = processor.restore_parser_by_cache();
+    assert!(matches!(result, Err(CacheError::GrammarMismatch { .. })));
+
+    Ok(())
+}
+
+// キャッシュの中身を書き換えた Processor を作る
+fn tamper<F>(processor: MyProcessor, f: F) -> anyhow::Result<MyProcessor>
+where
+    F: FnOnce(&mut std::collections::BTreeMap<Value, Value>),
+{
+    let mut value = to_value(&processor)?;
+    if let Value::Map(fields) = &mut value {
+        if let Some(Value::Map(cache)) = fields.get_mut(&Value::Text("cache_parse".to_string())) {
+            f(cache);
+        }
+    }
+    Ok(from_value(value)?)
+}
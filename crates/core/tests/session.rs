@@ -0,0 +1,144 @@
+use serde::{Serialize, Deserialize};
+
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::BaseLexer;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+
+#[derive(Lang, Serialize, Deserialize)]
+struct ExprLang (
+    #[tokenset] ExprToken,
+    #[ruleset]  ExprRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum ExprToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"-")]
+    Minus,
+    #[token(r"\*")]
+    Mul,
+    #[token(r"/")]
+    Div,
+    #[token(r"\(")]
+    BracketL,
+    #[token(r"\)")]
+    BracketR,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum ExprRule {
+    #[tokenset(ExprToken)]
+    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= <expr> Minus <term>")]
+    #[rule("<expr> ::= <term>")]
+    Expr,
+    #[rule("<term> ::= <term> Mul <num>")]
+    #[rule("<term> ::= <term> Div <num>")]
+    #[rule("<term> ::= <num>")]
+    Term,
+    #[rule("<num> ::= BracketL <expr> BracketR")]
+    #[rule("<num> ::= Num")]
+    Num,
+}
+
+type MyGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type MyProcessor = Processor<MyGenerator<ExprLang>>;
+
+#[test]
+fn session_feed() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+    let lexer = RegexLexer::<ExprLang>::init()?;
+
+    let mut session = processor.session::<Void>();
+    for token in lexer.run("1 + 2 *") {
+        session.feed(token)?;
+    }
+    assert!(!session.is_accepting_prefix());
+
+    for token in lexer.run("3") {
+        session.feed(token)?;
+    }
+    assert!(session.is_accepting_prefix());
+    session.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn session_rejected_token() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+    let lexer = RegexLexer::<ExprLang>::init()?;
+    let mut tokens = lexer.run("1 + * 2");
+
+    let mut session = processor.session::<Void>();
+    session.feed(tokens.next().unwrap())?;
+    session.feed(tokens.next().unwrap())?;
+    let snapshot = session.snapshot();
+
+    // 不正なトークンは受理されず，状態も変化しない
+    assert!(session.feed(tokens.next().unwrap()).is_err());
+    assert_eq!(session.snapshot().state().stack(), snapshot.state().stack());
+
+    session.feed(tokens.next().unwrap())?;
+    session.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn session_clone() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+    let lexer = RegexLexer::<ExprLang>::init()?;
+
+    let mut session = processor.session::<Void>();
+    for token in lexer.run("1 +") {
+        session.feed(token)?;
+    }
+
+    let forked = session.clone();
+    assert!(forked.finish().is_err());
+
+    for token in lexer.run("2") {
+        session.feed(token)?;
+    }
+    session.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn session_restore() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+    let lexer = RegexLexer::<ExprLang>::init()?;
+
+    let mut session = processor.session::<Void>();
+    for token in lexer.run("1 +") {
+        session.feed(token)?;
+    }
+    let snapshot = session.snapshot();
+
+    for token in lexer.run("2") {
+        session.feed(token)?;
+    }
+    assert!(session.is_accepting_prefix());
+
+    // 巻き戻した後は別のトークン列を続けられる
+    session.restore(snapshot);
+    assert!(!session.is_accepting_prefix());
+    for token in lexer.run("(3 * 4)") {
+        session.feed(token)?;
+    }
+    session.finish()?;
+
+    Ok(())
+}
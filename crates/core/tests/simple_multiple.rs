@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 
-use copager_core::{Generator, Processor};
+use copager_core::{Generator, Processor, BuiltProcessor};
 use copager_lang::token::{TokenSet, TokenTag};
 use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
 use copager_lang::Lang;
@@ -106,7 +106,7 @@ fn simple_multiple_mix_success_and_failure() {
     }
 }
 
-fn gen_processor() -> MyProcessor {
+fn gen_processor() -> BuiltProcessor<MyGenerator<ExprLang>> {
     MyProcessor::new()
         .build()
         .unwrap()
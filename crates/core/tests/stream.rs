@@ -0,0 +1,110 @@
+use serde::{Serialize, Deserialize};
+
+use copager_core::{Error, Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::{BaseLexer, StreamLexer};
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+
+#[derive(Lang, Serialize, Deserialize)]
+struct ExprLang (
+    #[tokenset] ExprToken,
+    #[ruleset]  ExprRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum ExprToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"-")]
+    Minus,
+    #[token(r"\*")]
+    Mul,
+    #[token(r"/")]
+    Div,
+    #[token(r"\(")]
+    BracketL,
+    #[token(r"\)")]
+    BracketR,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum ExprRule {
+    #[tokenset(ExprToken)]
+    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= <expr> Minus <term>")]
+    #[rule("<expr> ::= <term>")]
+    Expr,
+    #[rule("<term> ::= <term> Mul <num>")]
+    #[rule("<term> ::= <term> Div <num>")]
+    #[rule("<term> ::= <num>")]
+    Term,
+    #[rule("<num> ::= BracketL <expr> BracketR")]
+    #[rule("<num> ::= Num")]
+    Num,
+}
+
+type MyGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type MyProcessor = Processor<MyGenerator<ExprLang>>;
+
+const INPUT: &str = "(10 + 200) * 3\n  - 4000 / (5 + 60)";
+
+#[test]
+fn stream_tokens_across_chunks() -> anyhow::Result<()> {
+    let lexer = RegexLexer::<ExprLang>::init()?;
+    let expected = lexer
+        .run(INPUT)
+        .map(|token| (token.kind.clone(), token.as_str().to_string(), token.body))
+        .collect::<Vec<_>>();
+
+    for chunk_size in 1..8 {
+        let tokens = StreamLexer::new(&lexer, INPUT.as_bytes())
+            .with_chunk_size(chunk_size)
+            .owned_tokens()
+            .map(|token| token.map(|token| (token.kind.clone(), token.as_str().to_string(), token.body)))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(tokens, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn stream_lex_error_across_chunks() -> anyhow::Result<()> {
+    let lexer = RegexLexer::<ExprLang>::init()?;
+    let input = "1 + あ";
+
+    for chunk_size in 1..4 {
+        let result = StreamLexer::new(&lexer, input.as_bytes())
+            .with_chunk_size(chunk_size)
+            .owned_tokens()
+            .collect::<Result<Vec<_>, _>>();
+        match result {
+            Err(Error::Lex { found, span }) => {
+                assert_eq!(found, "あ");
+                assert_eq!(span, (4, 7));
+            }
+            _ => panic!("lex error is not detected"),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn process_reader() -> anyhow::Result<()> {
+    let processor = MyProcessor::new().build()?;
+    processor.process_reader::<Void, _>(INPUT.as_bytes())?;
+
+    let err = processor.process_reader::<Void, _>("1 + 2 * (3 +".as_bytes()).unwrap_err();
+    assert!(matches!(err, Error::Syntax { found: None, span: (12, 12), .. }));
+
+    Ok(())
+}
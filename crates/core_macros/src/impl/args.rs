@@ -0,0 +1,58 @@
+use proc_macro2::{Span, TokenStream};
+use syn::parse::Parser;
+use syn::{LitStr, ReturnType, Type};
+
+pub struct CacheArgs {
+    pub name: Option<LitStr>,
+}
+
+impl CacheArgs {
+    // #[copager::prebuild(name = "...")] / #[copager::load(name = "...")]
+    pub fn parse(args: TokenStream) -> syn::Result<CacheArgs> {
+        let mut name = None;
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported argument (expected `name = \"...\"`)"))
+            }
+        });
+        parser.parse2(args)?;
+
+        if let Some(name) = &name {
+            let value = name.value();
+            if value.is_empty() || value.contains(['/', '\\']) {
+                return Err(syn::Error::new(name.span(), "cache name must be a non-empty file name"));
+            }
+        }
+
+        Ok(CacheArgs { name })
+    }
+
+    // 名前が指定されていない場合は Processor の型名をキャッシュ名とする
+    pub fn name_or(&self, ty: Option<&Type>, span: Span) -> syn::Result<String> {
+        if let Some(name) = &self.name {
+            return Ok(name.value());
+        }
+
+        match ty {
+            Some(Type::Path(type_path)) => {
+                let ident = &type_path.path.segments.last().unwrap().ident;
+                Ok(ident.to_string())
+            }
+            _ => Err(syn::Error::new(span, "cannot infer the cache name, specify it by `name = \"...\"`")),
+        }
+    }
+}
+
+pub fn cache_file_name(name: &str) -> String {
+    format!("{}.cache", name)
+}
+
+pub fn return_type(ret: &ReturnType) -> Option<&Type> {
+    match ret {
+        ReturnType::Type(_, ty) => Some(ty),
+        ReturnType::Default => None,
+    }
+}
@@ -1,15 +1,47 @@
+use std::path::Path;
+
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{ItemFn, FnArg};
+use syn::{ItemFn, FnArg, ReturnType, Type};
+
+use crate::r#impl::args::{cache_file_name, CacheArgs};
+
+pub fn proc_macro_impl_load(args: TokenStream, ast: ItemFn) -> TokenStream {
+    let args = match CacheArgs::parse(args) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error(),
+    };
 
-pub fn proc_macro_impl_load(_args: TokenStream, ast: ItemFn) -> TokenStream {
     let fn_visibility = ast.vis;
     let fn_ident = ast.sig.ident;
     let fn_ret_type = ast.sig.output;
     let fn_body = ast.block;
 
+    // キャッシュが破損している場合は CacheError::Corrupted を ? で返すため，
+    // 関数の戻り値は From<CacheError> を実装するエラー型の Result とする
+    if !returns_result(&fn_ret_type) {
+        let msg = "#[copager::load] requires the function to return a Result whose error type implements From<CacheError>";
+        return syn::Error::new(fn_ident.span(), msg).to_compile_error();
+    }
+
     let fn_args = ast.sig.inputs;
-    let fn_arg_processor = &fn_args[0];
+    let fn_arg_processor = match fn_args.first() {
+        Some(FnArg::Typed(pat_type)) => pat_type,
+        _ => {
+            let msg = "#[copager::load] requires the processor as the first argument";
+            return syn::Error::new(fn_ident.span(), msg).to_compile_error();
+        }
+    };
+    let cache_name = match args.name_or(Some(&fn_arg_processor.ty), fn_ident.span()) {
+        Ok(name) => name,
+        Err(err) => return err.to_compile_error(),
+    };
+    if let Err(msg) = check_cache_exists(&cache_name) {
+        let span = args.name.as_ref().map(|name| name.span()).unwrap_or(fn_ident.span());
+        return syn::Error::new(span, msg).to_compile_error();
+    }
+    let cache_path = format!("/{}", cache_file_name(&cache_name));
+
     let fn_args_orig_def = fn_args
         .iter()
         .skip(1)
@@ -37,9 +69,42 @@ pub fn proc_macro_impl_load(_args: TokenStream, ast: ItemFn) -> TokenStream {
                 #fn_body
             }
 
-            let cache_body = include_str!(concat!(env!("OUT_DIR"), "/MyProcessor.cache"));
-            let deserialized = copager::prebuild::__deserialize(&cache_body).unwrap();
+            let cache_body = include_str!(concat!(env!("OUT_DIR"), #cache_path));
+            let deserialized = copager::prebuild::__deserialize(&cache_body)
+                .map_err(|err| copager::prebuild::__corrupted(#cache_name, err))?;
             __inner(deserialized, #fn_args_orig_uses)
         }
     }
 }
+
+// 戻り値の型が Result (anyhow::Result なども含む) であるかを確認する
+fn returns_result(ret_type: &ReturnType) -> bool {
+    match ret_type {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(path) => path.path.segments.last().is_some_and(|seg| seg.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+// ビルドスクリプトがキャッシュを生成済みであるかを確認する
+fn check_cache_exists(name: &str) -> Result<(), String> {
+    let out_dir = match std::env::var_os("OUT_DIR") {
+        Some(out_dir) => out_dir,
+        None => return Err(format!(
+            "OUT_DIR is not set, the prebuilt cache \"{}\" requires a build script using #[copager::prebuild]",
+            name,
+        )),
+    };
+
+    let cache_path = Path::new(&out_dir).join(cache_file_name(name));
+    if cache_path.is_file() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Prebuilt cache \"{}\" is not found in OUT_DIR, add #[copager::prebuild(name = \"{}\")] to build.rs",
+            name, name,
+        ))
+    }
+}
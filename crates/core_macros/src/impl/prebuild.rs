@@ -2,7 +2,18 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::ItemFn;
 
-pub fn proc_macro_impl_prebuild(_args: TokenStream, ast: ItemFn) -> TokenStream {
+use crate::r#impl::args::{cache_file_name, return_type, CacheArgs};
+
+pub fn proc_macro_impl_prebuild(args: TokenStream, ast: ItemFn) -> TokenStream {
+    let args = match CacheArgs::parse(args) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error(),
+    };
+    let cache_name = match args.name_or(return_type(&ast.sig.output), ast.sig.ident.span()) {
+        Ok(name) => cache_file_name(&name),
+        Err(err) => return err.to_compile_error(),
+    };
+
     let fn_visibility = ast.vis;
     let fn_ident = ast.sig.ident;
     let fn_args = ast.sig.inputs;
@@ -17,7 +28,7 @@ pub fn proc_macro_impl_prebuild(_args: TokenStream, ast: ItemFn) -> TokenStream
 
             let serialized = copager::prebuild::__serialize(&__inner()).unwrap();
             let out_dir = std::env::var_os("OUT_DIR").unwrap();
-            let cache_path = std::path::Path::new(&out_dir).join("MyProcessor.cache");
+            let cache_path = std::path::Path::new(&out_dir).join(#cache_name);
             std::fs::write(cache_path, serialized).unwrap();
         }
     }
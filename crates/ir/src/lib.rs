@@ -1,27 +1,166 @@
-use copager_lang::token::Token;
+use std::marker::PhantomData;
+
+use copager_lang::token::{Token, TokenOwned, TokenTag};
+pub use copager_lang::token::TokenSource;
 use copager_lang::Lang;
+use copager_utils::error::Result;
+pub use copager_utils::error::Error;
 #[cfg(feature = "derive")]
 pub use copager_ir_derive::{IR, IRBuilder};
 
-pub trait IR<'input, L: Lang> {
-    type Builder: IRBuilder<'input, L, Output = Self>;
+pub mod visit;
+
+// S は字句の参照先となる入力の型 (バイト列の解析時は [u8])
+pub trait IR<'input, L, S = str>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
+    type Builder: IRBuilder<'input, L, S, Output = Self>;
 }
 
-pub trait IRBuilder<'input, L: Lang> {
-    type Output: IR<'input, L>;
+pub trait IRBuilder<'input, L, S = str>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
+    type Output: IR<'input, L, S>;
 
     fn new() -> Self;
-    fn on_read(&mut self, token: Token<'input, L::TokenTag>) -> anyhow::Result<()>;
-    fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> anyhow::Result<()>;
-    fn build(self) -> anyhow::Result<Self::Output>;
+    fn on_read(&mut self, token: Token<'input, L::TokenTag, S>) -> Result<()>;
+    fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> Result<()>;
+    fn build(self) -> Result<Self::Output>;
+
+    // 還元に用いた規則 (rule.as_rules() における alt 番目の規則) を区別する場合に実装する
+    fn on_reduce(&mut self, rule: L::RuleTag, alt: usize, len: usize) -> Result<()> {
+        let _ = alt;
+        self.on_parse(rule, len)
+    }
+}
+
+// 入力を借用しない IR (ストリーム入力の解析に用いる)
+pub trait IROwned<L: Lang> {
+    type Builder: IROwnedBuilder<L, Output = Self>;
+}
+
+pub trait IROwnedBuilder<L: Lang> {
+    type Output: IROwned<L>;
+
+    fn new() -> Self;
+    fn on_read(&mut self, token: TokenOwned<L::TokenTag>) -> Result<()>;
+    fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> Result<()>;
+    fn build(self) -> Result<Self::Output>;
+
+    // 還元に用いた規則 (rule.as_rules() における alt 番目の規則) を区別する場合に実装する
+    fn on_reduce(&mut self, rule: L::RuleTag, alt: usize, len: usize) -> Result<()> {
+        let _ = alt;
+        self.on_parse(rule, len)
+    }
+}
+
+#[cfg(feature = "derive")]
+#[derive(Debug)]
+pub enum RawIR<'input, L, S = str>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
+    Atom(Token<'input, L::TokenTag, S>),
+    List {
+        rule: L::RuleTag,
+        alt: Option<usize>,  // 還元に用いた規則の位置 (on_parse により構築された場合は None)
+        elems: Vec<RawIR<'input, L, S>>
+    },
 }
 
 #[cfg(feature = "derive")]
+impl<'input, L, S> Clone for RawIR<'input, L, S>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
+    fn clone(&self) -> Self {
+        match self {
+            RawIR::Atom(token) => RawIR::Atom(token.clone()),
+            RawIR::List { rule, alt, elems } => RawIR::List {
+                rule: rule.clone(),
+                alt: *alt,
+                elems: elems.clone(),
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
-pub enum RawIR<'input, L: Lang> {
-    Atom(Token<'input, L::TokenTag>),
+pub enum RawIROwned<L: Lang> {
+    Atom(TokenOwned<L::TokenTag>),
     List {
         rule: L::RuleTag,
-        elems: Vec<RawIR<'input, L>>
+        alt: Option<usize>,  // 還元に用いた規則の位置 (on_parse により構築された場合は None)
+        elems: Vec<RawIROwned<L>>
     },
 }
+
+pub struct RawIROwnedBuilder<L: Lang, O> {
+    stack: Vec<RawIROwned<L>>,
+    _phantom: PhantomData<O>,
+}
+
+impl<L, O> IROwnedBuilder<L> for RawIROwnedBuilder<L, O>
+where
+    L: Lang,
+    O: IROwned<L, Builder = Self> + From<RawIROwned<L>>,
+{
+    type Output = O;
+
+    fn new() -> Self {
+        RawIROwnedBuilder {
+            stack: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    fn on_read(&mut self, token: TokenOwned<L::TokenTag>) -> Result<()> {
+        self.stack.push(RawIROwned::Atom(token));
+        Ok(())
+    }
+
+    fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> Result<()> {
+        self.reduce(rule, None, len)
+    }
+
+    fn on_reduce(&mut self, rule: L::RuleTag, alt: usize, len: usize) -> Result<()> {
+        self.reduce(rule, Some(alt), len)
+    }
+
+    fn build(mut self) -> Result<O> {
+        if self.stack.len() != 1 {
+            return Err(Error::IR {
+                message: format!("Expected a single root, but {} elements remain", self.stack.len()),
+                span: None,
+            });
+        }
+        Ok(O::from(self.stack.pop().unwrap()))
+    }
+}
+
+impl<L: Lang, O> RawIROwnedBuilder<L, O> {
+    fn reduce(&mut self, rule: L::RuleTag, alt: Option<usize>, len: usize) -> Result<()> {
+        if self.stack.len() < len {
+            return Err(Error::IR {
+                message: format!("Cannot reduce {} elements from a stack of {}", len, self.stack.len()),
+                span: None,
+            });
+        }
+        let elems = self.stack.split_off(self.stack.len() - len);
+        let elems = elems
+            .into_iter()
+            .filter(|elem| match elem {
+                RawIROwned::Atom(token) => !token.kind.as_option_list().contains(&"ir_omit"),
+                _ => true,
+            })
+            .collect();
+        self.stack.push(RawIROwned::List { rule, alt, elems });
+        Ok(())
+    }
+}
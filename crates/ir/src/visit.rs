@@ -0,0 +1,286 @@
+use std::collections::VecDeque;
+use std::iter::successors;
+use std::mem::take;
+use std::ptr;
+
+// 走査の継続方法
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    #[default]
+    Continue,
+    SkipChildren,  // 子を走査せずに exit へ進む
+    Break,         // 走査を打ち切る
+}
+
+// 節と葉からなる木構造の IR (CSTree や SExp など)
+pub trait Tree: Sized {
+    type Rule;
+    type Token: ?Sized;
+
+    fn rule(&self) -> Option<&Self::Rule>;
+    fn token(&self) -> Option<&Self::Token>;
+    fn children(&self) -> Option<&VecDeque<Self>>;
+    fn children_mut(&mut self) -> Option<&mut VecDeque<Self>>;
+
+    // 走査が打ち切られた場合は Visit::Break を返す
+    fn visit<V: Visitor<Self> + ?Sized>(&self, visitor: &mut V) -> Visit {
+        walk(self, visitor)
+    }
+
+    fn visit_mut<V: VisitorMut<Self> + ?Sized>(&mut self, visitor: &mut V) -> Visit {
+        walk_mut(self, visitor)
+    }
+
+    fn fold<F: Fold<Self> + ?Sized>(self, folder: &mut F) -> Self {
+        folder.fold(self)
+    }
+
+    fn pre_order(&self) -> PreOrder<'_, Self> {
+        PreOrder { stack: vec![self] }
+    }
+
+    fn post_order(&self) -> PostOrder<'_, Self> {
+        PostOrder { stack: vec![(self, 0)] }
+    }
+
+    // 自身を含まない子孫 (行きがけ順)
+    fn descendants(&self) -> impl Iterator<Item = &Self> {
+        self.pre_order().skip(1)
+    }
+
+    // 子を持たない節は含まない
+    fn leaves(&self) -> impl Iterator<Item = &Self> {
+        self.pre_order().filter(|tree| tree.children().is_none())
+    }
+
+    fn parent_view(&self) -> ParentView<'_, Self> {
+        ParentView::new(self)
+    }
+}
+
+// 要素ごとに enter -> visit_rule (節) または visit_token (葉) -> 子 -> exit の順に呼ばれる
+pub trait Visitor<T: Tree> {
+    fn enter(&mut self, _tree: &T) -> Visit {
+        Visit::Continue
+    }
+
+    fn exit(&mut self, _tree: &T) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_rule(&mut self, _rule: &T::Rule, _tree: &T) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_token(&mut self, _token: &T::Token, _tree: &T) -> Visit {
+        Visit::Continue
+    }
+}
+
+// 規則や字句は書き換えられる要素自身から参照する
+pub trait VisitorMut<T: Tree> {
+    fn enter(&mut self, _tree: &mut T) -> Visit {
+        Visit::Continue
+    }
+
+    fn exit(&mut self, _tree: &mut T) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_node(&mut self, _tree: &mut T) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_leaf(&mut self, _tree: &mut T) -> Visit {
+        Visit::Continue
+    }
+}
+
+// 木を再構築する (既定では子を再帰的に畳み込み，葉はそのまま返す)
+pub trait Fold<T: Tree> {
+    fn fold(&mut self, tree: T) -> T {
+        match tree.children() {
+            Some(_) => self.fold_node(tree),
+            None => self.fold_leaf(tree),
+        }
+    }
+
+    fn fold_node(&mut self, mut tree: T) -> T {
+        if let Some(children) = tree.children_mut() {
+            *children = take(children)
+                .into_iter()
+                .map(|child| self.fold(child))
+                .collect();
+        }
+        tree
+    }
+
+    fn fold_leaf(&mut self, tree: T) -> T {
+        tree
+    }
+}
+
+fn walk<T: Tree, V: Visitor<T> + ?Sized>(tree: &T, visitor: &mut V) -> Visit {
+    let mut flow = visitor.enter(tree);
+    if flow == Visit::Continue {
+        if let Some(rule) = tree.rule() {
+            flow = visitor.visit_rule(rule, tree);
+        } else if let Some(token) = tree.token() {
+            flow = visitor.visit_token(token, tree);
+        }
+    }
+
+    match flow {
+        Visit::Break => return Visit::Break,
+        Visit::Continue => {
+            for child in tree.children().into_iter().flatten() {
+                if walk(child, visitor) == Visit::Break {
+                    return Visit::Break;
+                }
+            }
+        }
+        Visit::SkipChildren => {}
+    }
+
+    match visitor.exit(tree) {
+        Visit::Break => Visit::Break,
+        _ => Visit::Continue,
+    }
+}
+
+fn walk_mut<T: Tree, V: VisitorMut<T> + ?Sized>(tree: &mut T, visitor: &mut V) -> Visit {
+    let mut flow = visitor.enter(tree);
+    if flow == Visit::Continue {
+        flow = match tree.children() {
+            Some(_) => visitor.visit_node(tree),
+            None => visitor.visit_leaf(tree),
+        };
+    }
+
+    match flow {
+        Visit::Break => return Visit::Break,
+        Visit::Continue => {
+            for child in tree.children_mut().into_iter().flatten() {
+                if walk_mut(child, visitor) == Visit::Break {
+                    return Visit::Break;
+                }
+            }
+        }
+        Visit::SkipChildren => {}
+    }
+
+    match visitor.exit(tree) {
+        Visit::Break => Visit::Break,
+        _ => Visit::Continue,
+    }
+}
+
+// 行きがけ順
+pub struct PreOrder<'a, T> {
+    stack: Vec<&'a T>,
+}
+
+impl<'a, T: Tree> Iterator for PreOrder<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tree = self.stack.pop()?;
+        if let Some(children) = tree.children() {
+            self.stack.extend(children.iter().rev());
+        }
+        Some(tree)
+    }
+}
+
+// 帰りがけ順
+pub struct PostOrder<'a, T> {
+    stack: Vec<(&'a T, usize)>,  // (要素, 次に訪れる子の位置)
+}
+
+impl<'a, T: Tree> Iterator for PostOrder<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (tree, next_idx) = self.stack.last_mut()?;
+            match tree.children().and_then(|children| children.get(*next_idx)) {
+                Some(child) => {
+                    *next_idx += 1;
+                    self.stack.push((child, 0));
+                }
+                None => return self.stack.pop().map(|(tree, _)| tree),
+            }
+        }
+    }
+}
+
+// 各要素に親への参照を持たせた木 (祖先の列挙に用いる)
+pub struct ParentView<'a, T> {
+    nodes: Vec<(&'a T, Option<usize>)>,  // 行きがけ順に並べた (要素, 親の位置)
+}
+
+impl<'a, T: Tree> ParentView<'a, T> {
+    pub fn new(root: &'a T) -> Self {
+        let mut nodes = vec![];
+        let mut stack = vec![(root, None)];
+        while let Some((tree, parent)) = stack.pop() {
+            let idx = nodes.len();
+            nodes.push((tree, parent));
+            if let Some(children) = tree.children() {
+                stack.extend(children.iter().rev().map(|child| (child, Some(idx))));
+            }
+        }
+        ParentView { nodes }
+    }
+
+    pub fn root(&self) -> ViewNode<'_, 'a, T> {
+        ViewNode { view: self, idx: 0 }
+    }
+
+    // 行きがけ順
+    pub fn iter(&self) -> impl Iterator<Item = ViewNode<'_, 'a, T>> {
+        (0..self.nodes.len()).map(|idx| ViewNode { view: self, idx })
+    }
+
+    // 木に含まれる要素を参照の同一性により探す
+    pub fn find(&self, tree: &T) -> Option<ViewNode<'_, 'a, T>> {
+        self.nodes
+            .iter()
+            .position(|(node, _)| ptr::eq(*node, tree))
+            .map(|idx| ViewNode { view: self, idx })
+    }
+}
+
+pub struct ViewNode<'v, 'a, T> {
+    view: &'v ParentView<'a, T>,
+    idx: usize,
+}
+
+impl<T> Clone for ViewNode<'_, '_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ViewNode<'_, '_, T> {}
+
+impl<'v, 'a, T: Tree> ViewNode<'v, 'a, T> {
+    pub fn get(&self) -> &'a T {
+        self.view.nodes[self.idx].0
+    }
+
+    pub fn parent(&self) -> Option<ViewNode<'v, 'a, T>> {
+        self.view.nodes[self.idx]
+            .1
+            .map(|idx| ViewNode { view: self.view, idx })
+    }
+
+    // 親から根までを順に返す
+    pub fn ancestors(&self) -> impl Iterator<Item = ViewNode<'v, 'a, T>> {
+        successors(self.parent(), |node| node.parent())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.ancestors().count()
+    }
+}
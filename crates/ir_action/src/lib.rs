@@ -0,0 +1,157 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use copager_lang::token::{Token, TokenOwned, TokenTag};
+use copager_lang::Lang;
+use copager_ir::{IR, IRBuilder, IROwned, IROwnedBuilder, TokenSource, Error};
+
+// 還元ごとに子の値から親の値を計算する意味動作 (yacc の $$ に相当する)
+// ir_omit が指定された字句は値を持たず，子の値に含まれない
+// 意味動作は Default により生成されるため，クロージャや外部の状態は登録できない
+pub trait Actions<L: Lang>: Default {
+    type Value;
+
+    fn on_token(&mut self, tag: L::TokenTag, text: &str) -> Self::Value;
+    fn on_rule(&mut self, rule: L::RuleTag, children: Vec<Self::Value>) -> Self::Value;
+}
+
+// 木を構築せずに意味動作の結果のみを保持する IR
+pub struct ActionIR<L: Lang, A: Actions<L>> {
+    pub value: A::Value,
+    _phantom: PhantomData<L>,
+}
+
+impl<L, A> Debug for ActionIR<L, A>
+where
+    L: Lang,
+    A: Actions<L>,
+    A::Value: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ActionIR").field(&self.value).finish()
+    }
+}
+
+impl<L: Lang, A: Actions<L>> ActionIR<L, A> {
+    pub fn into_value(self) -> A::Value {
+        self.value
+    }
+}
+
+impl<'input, L, S, A> IR<'input, L, S> for ActionIR<L, A>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+    A: Actions<L>,
+{
+    type Builder = ActionIRBuilder<L, A>;
+}
+
+impl<L: Lang, A: Actions<L>> IROwned<L> for ActionIR<L, A> {
+    type Builder = ActionIRBuilder<L, A>;
+}
+
+pub struct ActionIRBuilder<L: Lang, A: Actions<L>> {
+    actions: A,
+    stack: Vec<Option<A::Value>>,  // ir_omit が指定された字句は None
+    _phantom: PhantomData<L>,
+}
+
+impl<L: Lang, A: Actions<L>> ActionIRBuilder<L, A> {
+    fn push_token(&mut self, tag: L::TokenTag, text: &str) {
+        if tag.as_option_list().contains(&"ir_omit") {
+            self.stack.push(None);
+        } else {
+            let value = self.actions.on_token(tag, text);
+            self.stack.push(Some(value));
+        }
+    }
+
+    fn reduce(&mut self, rule: L::RuleTag, len: usize) -> Result<(), Error> {
+        if self.stack.len() < len {
+            return Err(Error::IR {
+                message: format!("Cannot reduce {} elements from a stack of {}", len, self.stack.len()),
+                span: None,
+            });
+        }
+        let children = self.stack
+            .split_off(self.stack.len() - len)
+            .into_iter()
+            .flatten()
+            .collect();
+        let value = self.actions.on_rule(rule, children);
+        self.stack.push(Some(value));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<ActionIR<L, A>, Error> {
+        match (self.stack.pop(), self.stack.is_empty()) {
+            (Some(Some(value)), true) => Ok(ActionIR { value, _phantom: PhantomData }),
+            _ => Err(Error::IR {
+                message: format!("Expected a single root, but {} elements remain", self.stack.len() + 1),
+                span: None,
+            }),
+        }
+    }
+}
+
+impl<'input, L, S, A> IRBuilder<'input, L, S> for ActionIRBuilder<L, A>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+    A: Actions<L>,
+{
+    type Output = ActionIR<L, A>;
+
+    fn new() -> Self {
+        ActionIRBuilder {
+            actions: A::default(),
+            stack: vec![],
+            _phantom: PhantomData,
+        }
+    }
+
+    // バイト列の解析時，UTF-8 として不正なバイトは置換文字に変換される
+    fn on_read(&mut self, token: Token<'input, L::TokenTag, S>) -> Result<(), Error> {
+        let text = token.as_text();
+        self.push_token(token.kind, &text);
+        Ok(())
+    }
+
+    fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> Result<(), Error> {
+        self.reduce(rule, len)
+    }
+
+    fn build(self) -> Result<Self::Output, Error> {
+        self.finish()
+    }
+}
+
+impl<L, A> IROwnedBuilder<L> for ActionIRBuilder<L, A>
+where
+    L: Lang,
+    A: Actions<L>,
+{
+    type Output = ActionIR<L, A>;
+
+    fn new() -> Self {
+        ActionIRBuilder {
+            actions: A::default(),
+            stack: vec![],
+            _phantom: PhantomData,
+        }
+    }
+
+    fn on_read(&mut self, token: TokenOwned<L::TokenTag>) -> Result<(), Error> {
+        self.push_token(token.kind.clone(), token.as_str());
+        Ok(())
+    }
+
+    fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> Result<(), Error> {
+        self.reduce(rule, len)
+    }
+
+    fn build(self) -> Result<Self::Output, Error> {
+        self.finish()
+    }
+}
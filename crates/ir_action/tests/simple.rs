@@ -0,0 +1,136 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_action::{Actions, ActionIR};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"-")]
+    Minus,
+    #[token(r"\*")]
+    Mul,
+    #[token(r"/")]
+    Div,
+    #[token(r"\(", ir_omit)]
+    BracketL,
+    #[token(r"\)", ir_omit)]
+    BracketR,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= <expr> Minus <term>")]
+    #[rule("<expr> ::= <term>")]
+    Expr,
+    #[rule("<term> ::= <term> Mul <num>")]
+    #[rule("<term> ::= <term> Div <num>")]
+    #[rule("<term> ::= <num>")]
+    Term,
+    #[rule("<num> ::= BracketL <expr> BracketR")]
+    #[rule("<num> ::= Num")]
+    Num,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+// 式の値を計算する (演算子の字句は値を持たない)
+#[derive(Default)]
+struct Eval;
+
+impl Actions<TestLang> for Eval {
+    type Value = Option<i32>;
+
+    fn on_token(&mut self, tag: TestToken, text: &str) -> Self::Value {
+        match tag {
+            TestToken::Num => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn on_rule(&mut self, _: TestRule, children: Vec<Self::Value>) -> Self::Value {
+        match children.as_slice() {
+            [value] => *value,
+            [Some(lhs), None, Some(rhs)] => Some(*lhs + *rhs),
+            _ => None,
+        }
+    }
+}
+
+// 演算子ごとに計算するため字句の種類を値として持つ
+#[derive(Default)]
+struct Calc;
+
+#[derive(Debug, PartialEq)]
+enum CalcValue {
+    Num(i32),
+    Op(TestToken),
+}
+
+impl Actions<TestLang> for Calc {
+    type Value = CalcValue;
+
+    fn on_token(&mut self, tag: TestToken, text: &str) -> Self::Value {
+        match tag {
+            TestToken::Num => CalcValue::Num(text.parse().unwrap()),
+            tag => CalcValue::Op(tag),
+        }
+    }
+
+    fn on_rule(&mut self, _: TestRule, mut children: Vec<Self::Value>) -> Self::Value {
+        match (children.pop(), children.pop(), children.pop()) {
+            (Some(CalcValue::Num(rhs)), Some(CalcValue::Op(op)), Some(CalcValue::Num(lhs))) => {
+                match op {
+                    TestToken::Plus => CalcValue::Num(lhs + rhs),
+                    TestToken::Minus => CalcValue::Num(lhs - rhs),
+                    TestToken::Mul => CalcValue::Num(lhs * rhs),
+                    TestToken::Div => CalcValue::Num(lhs / rhs),
+                    _ => unreachable!(),
+                }
+            }
+            (Some(value), None, None) => value,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn action_eval() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let ir = processor.process::<ActionIR<_, Eval>>("1 + 2 + (3 + 4)")?;
+    assert_eq!(ir.value, Some(10));
+
+    let ir = processor.process::<ActionIR<_, Calc>>("10 - (2 + 3) * 4 / 2")?;
+    assert_eq!(ir.into_value(), CalcValue::Num(0));
+
+    Ok(())
+}
+
+#[test]
+fn action_reader() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let ir = processor.process_reader::<ActionIR<_, Calc>, _>("2 * (3 + 4)".as_bytes())?;
+    assert_eq!(ir.into_value(), CalcValue::Num(14));
+
+    Ok(())
+}
@@ -1,2 +1,3 @@
 pub mod ir;
 pub mod builder;
+pub mod from_cst;
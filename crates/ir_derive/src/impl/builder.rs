@@ -11,25 +11,38 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
     let generics = to_generics_without_where(&ast.generics);
 
     quote! {
-        #vis struct #ident_builder<'input, L: Lang> {
-            stack: Vec<RawIR<'input, L>>,
+        #vis struct #ident_builder<'input, L, S = str>
+        where
+            L: Lang,
+            S: copager_ir::TokenSource + ?Sized + 'input,
+        {
+            stack: Vec<RawIR<'input, L, S>>,
         }
 
-        impl <'input, L: Lang> IRBuilder<'input, L> for #ident_builder<'input, L> {
-            type Output = #ident #generics;
-
-            fn new() -> #ident_builder<'input, L> {
+        impl<'input, L, S> Clone for #ident_builder<'input, L, S>
+        where
+            L: Lang,
+            S: copager_ir::TokenSource + ?Sized + 'input,
+        {
+            fn clone(&self) -> Self {
                 #ident_builder {
-                    stack: Vec::new(),
+                    stack: self.stack.clone(),
                 }
             }
+        }
 
-            fn on_read(&mut self, token: Token<'input, L::TokenTag>) -> anyhow::Result<()> {
-                self.stack.push(RawIR::Atom(token));
-                Ok(())
-            }
-
-            fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> anyhow::Result<()> {
+        impl<'input, L, S> #ident_builder<'input, L, S>
+        where
+            L: Lang,
+            S: copager_ir::TokenSource + ?Sized + 'input,
+        {
+            fn reduce(&mut self, rule: L::RuleTag, alt: Option<usize>, len: usize) -> Result<(), copager_ir::Error> {
+                if self.stack.len() < len {
+                    return Err(copager_ir::Error::IR {
+                        message: format!("Cannot reduce {} elements from a stack of {}", len, self.stack.len()),
+                        span: None,
+                    });
+                }
                 let elems = self.stack.split_off(self.stack.len() - len);
                 let elems = elems
                     .into_iter()
@@ -38,15 +51,45 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
                         _ => true,
                     })
                     .collect();
-                self.stack.push(RawIR::List { rule, elems });
+                self.stack.push(RawIR::List { rule, alt, elems });
+                Ok(())
+            }
+        }
+
+        impl<'input, L, S> IRBuilder<'input, L, S> for #ident_builder<'input, L, S>
+        where
+            L: Lang,
+            S: copager_ir::TokenSource + ?Sized + 'input,
+            #ident #generics: From<RawIR<'input, L, S>>,
+        {
+            type Output = #ident #generics;
+
+            fn new() -> #ident_builder<'input, L, S> {
+                #ident_builder {
+                    stack: Vec::new(),
+                }
+            }
+
+            fn on_read(&mut self, token: Token<'input, L::TokenTag, S>) -> Result<(), copager_ir::Error> {
+                self.stack.push(RawIR::Atom(token));
                 Ok(())
             }
 
-            fn build(mut self) -> anyhow::Result<Self::Output>
-            where
-                Self::Output: From<RawIR<'input, L>>,
-            {
-                assert!(self.stack.len() == 1);
+            fn on_parse(&mut self, rule: L::RuleTag, len: usize) -> Result<(), copager_ir::Error> {
+                self.reduce(rule, None, len)
+            }
+
+            fn on_reduce(&mut self, rule: L::RuleTag, alt: usize, len: usize) -> Result<(), copager_ir::Error> {
+                self.reduce(rule, Some(alt), len)
+            }
+
+            fn build(mut self) -> Result<Self::Output, copager_ir::Error> {
+                if self.stack.len() != 1 {
+                    return Err(copager_ir::Error::IR {
+                        message: format!("Expected a single root, but {} elements remain", self.stack.len()),
+                        span: None,
+                    });
+                }
                 Ok(Self::Output::from(self.stack.pop().unwrap()))
             }
         };
@@ -0,0 +1,242 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Fields, Ident, Lifetime, LitStr, Path, Type};
+
+pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
+    let ident = &ast.ident;
+
+    // 規則の指定 (#[cst(rule = EARule::Expr)])
+    let rule = match parse_cst_attrs(&ast.attrs).rule {
+        Some(rule) => rule,
+        None => panic!("\"FromCst\" proc-macro requires a \"cst(rule = ...)\" attribute."),
+    };
+    let mut rule_ty = rule.clone();
+    rule_ty.segments.pop();
+    rule_ty.segments.pop_punct();
+
+    // 構造体・列挙型の最初のライフタイムを入力のライフタイムとして用いる
+    if ast.generics.type_params().next().is_some() {
+        panic!("\"FromCst\" proc-macro does not support type parameters.");
+    }
+    let lifetimes = ast.generics.lifetimes().map(|param| &param.lifetime).collect::<Vec<_>>();
+    let input_lifetime = match lifetimes.first() {
+        Some(lifetime) => (*lifetime).clone(),
+        None => Lifetime::new("'input", ident.span()),
+    };
+    let impl_lifetimes = match lifetimes.is_empty() {
+        true => quote! { #input_lifetime },
+        false => quote! { #( #lifetimes ),* },
+    };
+    let ty_generics = match lifetimes.is_empty() {
+        true => quote! {},
+        false => quote! { < #( #lifetimes ),* > },
+    };
+
+    let (accepts, from_cst) = match &ast.data {
+        Data::Struct(data_struct) => {
+            // 構造体の場合は子の数のみを検査し，各フィールドの変換時のエラーをそのまま返す
+            // (ラベルを指定したフィールドを含む場合は子の数も検査しない)
+            let shape = Shape::parse(&data_struct.fields, &input_lifetime);
+            let len = shape.fields.len();
+            let construct = shape.gen_construct(quote! { #ident });
+            let check_len = shape.fields.iter().all(|field| field.attrs.label.is_none());
+            let from_cst = quote! {
+                let children = cst.expect_children(&#rule)?;
+                if #check_len && children.len() != #len {
+                    return Err(::copager::ir::r#ref::CstError::Unexpected {
+                        expected: format!("{} children of {}", #len, cst.describe()),
+                        found: format!("{} children", children.len()),
+                        span: cst.span(),
+                    });
+                }
+                Ok(#construct)
+            };
+            (quote! { cst.is_node_of(&#rule) }, from_cst)
+        }
+        Data::Enum(data_enum) => {
+            let variants = data_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_ident = &variant.ident;
+                    let shape = Shape::parse(&variant.fields, &input_lifetime);
+                    let cond = shape.gen_cond();
+                    let construct = shape.gen_construct(quote! { #ident :: #variant_ident });
+                    quote! {
+                        if #cond {
+                            return Ok(#construct);
+                        }
+                    }
+                });
+            let expected = data_enum
+                .variants
+                .iter()
+                .map(|variant| format!("{}::{}", ident, variant.ident))
+                .collect::<Vec<_>>()
+                .join(" or ");
+            let from_cst = quote! {
+                let children = cst.expect_children(&#rule)?;
+                #( #variants )*
+                let found = children.iter().map(|child| child.describe()).collect::<Vec<_>>();
+                Err(::copager::ir::r#ref::CstError::Unexpected {
+                    expected: #expected.to_string(),
+                    found: format!("{} [{}]", cst.describe(), found.join(", ")),
+                    span: cst.span(),
+                })
+            };
+            (quote! { cst.is_node_of(&#rule) }, from_cst)
+        }
+        Data::Union(_) => panic!("\"FromCst\" proc-macro is not implemented for union."),
+    };
+
+    // 利用側で名前を use しなくてよいよう，copager を経由したパスを用いる
+    quote! {
+        impl<#impl_lifetimes, L> ::copager::ir::r#ref::FromCst<#input_lifetime, L> for #ident #ty_generics
+        where
+            L: ::copager::lang::Lang<RuleTag = #rule_ty>,
+        {
+            fn accepts(cst: &::copager::ir::r#ref::CSTree<#input_lifetime, L>) -> bool {
+                #accepts
+            }
+
+            fn from_cst(
+                cst: &::copager::ir::r#ref::CSTree<#input_lifetime, L>,
+            ) -> ::std::result::Result<Self, ::copager::ir::r#ref::CstError> {
+                #from_cst
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct CstAttrs {
+    rule: Option<Path>,
+    token: Option<Path>,
+    with: Option<Path>,
+    label: Option<LitStr>,
+}
+
+fn parse_cst_attrs(attrs: &[Attribute]) -> CstAttrs {
+    let mut cst_attrs = CstAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("cst")) {
+        attr.parse_nested_meta(|meta| {
+            let key = meta.path.get_ident().map(Ident::to_string).unwrap_or_default();
+            if key == "label" {
+                cst_attrs.label = Some(meta.value()?.parse::<LitStr>()?);
+                return Ok(());
+            }
+            let value = meta.value()?.parse::<Path>()?;
+            match key.as_str() {
+                "rule" => cst_attrs.rule = Some(value),
+                "token" => cst_attrs.token = Some(value),
+                "with" => cst_attrs.with = Some(value),
+                key => panic!("Unknown cst option \"{}\" is specified.", key),
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+    cst_attrs
+}
+
+// 字句の指定 (EAToken::Num) の列挙子名
+// (導出した実装は L::TokenTag の型を固定しないため，L::TokenTag の字句と列挙子名で照合する)
+fn token_name(token: &Path) -> String {
+    token.segments.last().unwrap().ident.to_string()
+}
+
+// 子の位置と対応付けられたフィールドの列
+struct Shape<'a> {
+    kind: &'a Fields,
+    input_lifetime: &'a Lifetime,
+    fields: Vec<FieldInfo<'a>>,
+}
+
+struct FieldInfo<'a> {
+    ident: Ident,
+    ty: &'a Type,
+    attrs: CstAttrs,
+}
+
+impl<'a> Shape<'a> {
+    fn parse(kind: &'a Fields, input_lifetime: &'a Lifetime) -> Shape<'a> {
+        let fields = kind
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| FieldInfo {
+                ident: field.ident.clone().unwrap_or_else(|| format_ident!("_{}", idx)),
+                ty: &field.ty,
+                attrs: parse_cst_attrs(&field.attrs),
+            })
+            .collect();
+        Shape { kind, input_lifetime, fields }
+    }
+
+    // 子の数と各フィールドの変換先が一致するか
+    // (位置で対応付けるフィールドのみの場合は子の数を，ラベルで対応付けるフィールドはその子の有無を検査する)
+    fn gen_cond(&self) -> TokenStream {
+        let len = self.fields.len();
+        let input_lifetime = self.input_lifetime;
+        let check_len = match self.fields.iter().all(|field| field.attrs.label.is_none()) {
+            true => quote! { children.len() == #len },
+            false => quote! { true },
+        };
+        let field_conds = self.fields
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                let ty = field.ty;
+                let accepts = match (&field.attrs.token, &field.attrs.with) {
+                    (Some(token), _) => {
+                        let name = token_name(token);
+                        quote! { child.__is_leaf_named(#name) }
+                    }
+                    (None, Some(_)) => quote! { matches!(child, ::copager::ir::r#ref::CSTree::Leaf { .. }) },
+                    (None, None) => quote! { <#ty as ::copager::ir::r#ref::FromCst<#input_lifetime, L>>::accepts(child) },
+                };
+                match &field.attrs.label {
+                    Some(label) => quote! { cst.child_by_label(#label).is_some_and(|child| #accepts) },
+                    None => quote! { children.get(#idx).is_some_and(|child| #accepts) },
+                }
+            });
+        quote! { #check_len #( && #field_conds )* }
+    }
+
+    fn gen_construct(&self, path: TokenStream) -> TokenStream {
+        let input_lifetime = self.input_lifetime;
+        let values = self.fields
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                let ty = field.ty;
+                let child = match &field.attrs.label {
+                    Some(label) => quote! {
+                        cst.child_by_label(#label)
+                            .ok_or_else(|| cst.unexpected(&format!("a child labeled {:?}", #label)))?
+                    },
+                    None => quote! {
+                        children.get(#idx)
+                            .ok_or_else(|| cst.unexpected(&format!("at least {} children", #idx + 1)))?
+                    },
+                };
+                let check = match &field.attrs.token {
+                    Some(token) => {
+                        let name = token_name(token);
+                        quote! { let _ = &#token; child.__expect_leaf_named(#name)?; }
+                    }
+                    None => quote! {},
+                };
+                let value = match &field.attrs.with {
+                    Some(with) => quote! { child.convert(#with)? },
+                    None => quote! { <#ty as ::copager::ir::r#ref::FromCst<#input_lifetime, L>>::from_cst(child)? },
+                };
+                quote! {{ let child = #child; #check #value }}
+            });
+        let idents = self.fields.iter().map(|field| &field.ident);
+        match self.kind {
+            Fields::Named(_) => quote! { #path { #( #idents: #values ),* } },
+            Fields::Unnamed(_) => quote! { #path ( #( #values ),* ) },
+            Fields::Unit => quote! { #path },
+        }
+    }
+}
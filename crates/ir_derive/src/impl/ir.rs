@@ -10,8 +10,13 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
     let generics = to_generics_without_where(&ast.generics);
 
     quote! {
-        impl<'input, L: Lang> IR<'input, L> for #ident #generics {
-            type Builder = #ident_builder<'input, L>;
+        impl<'input, L, S> IR<'input, L, S> for #ident #generics
+        where
+            L: Lang,
+            S: copager_ir::TokenSource + ?Sized + 'input,
+            #ident #generics: From<RawIR<'input, L, S>>,
+        {
+            type Builder = #ident_builder<'input, L, S>;
         }
     }
 }
@@ -14,3 +14,9 @@ pub fn derive_ir_builder(input: proc_macro::TokenStream) -> proc_macro::TokenStr
     let ast = parse_macro_input!(input as DeriveInput);
     r#impl::builder::proc_macro_impl(ast).into()
 }
+
+#[proc_macro_derive(FromCst, attributes(cst))]
+pub fn derive_from_cst(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    r#impl::from_cst::proc_macro_impl(ast).into()
+}
@@ -1,5 +1,8 @@
 mod r#ref;
 mod owned;
+mod pretty;
+mod parse;
 
 pub use r#ref::SExp;
 pub use owned::SExpOwned;
+pub use parse::SExpParseError;
@@ -1,9 +1,12 @@
 use std::collections::VecDeque;
 use std::fmt::{Debug, Display};
 
-use copager_lang::token::{Token, TokenTag};
+use copager_lang::token::{Token, TokenSource, TokenTag};
 use copager_lang::Lang;
-use copager_ir::{IR, IRBuilder, RawIR};
+use copager_ir::{IR, IRBuilder, IROwned, RawIR, RawIROwned, RawIROwnedBuilder};
+use copager_ir::visit::Tree;
+
+use crate::pretty::pretty;
 
 #[derive(Debug, IR, IRBuilder)]
 pub enum SExpOwned<L: Lang> {
@@ -14,6 +17,25 @@ pub enum SExpOwned<L: Lang> {
     },
 }
 
+impl<L: Lang> PartialEq for SExpOwned<L> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SExpOwned::Atom(lhs), SExpOwned::Atom(rhs)) => lhs == rhs,
+            (SExpOwned::List { rule: lhs_rule, elems: lhs }, SExpOwned::List { rule: rhs_rule, elems: rhs }) => {
+                lhs_rule == rhs_rule && lhs == rhs
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<L: Lang> SExpOwned<L> {
+    // 1 行あたり width 文字に収まるように字下げして出力する
+    pub fn pretty(&self, width: usize) -> String {
+        pretty::<L, Self>(self, width)
+    }
+}
+
 impl<L: Lang> Display for SExpOwned<L>
 where
     L::TokenTag: Debug,
@@ -33,17 +55,74 @@ where
     }
 }
 
-impl<'input, L: Lang> From<RawIR<'input, L>> for SExpOwned<L> {
-    fn from(raw: RawIR<'input, L>) -> Self {
+// バイト列の解析時，UTF-8 として不正なバイトは置換文字に変換される
+impl<'input, L, S> From<RawIR<'input, L, S>> for SExpOwned<L>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
+    fn from(raw: RawIR<'input, L, S>) -> Self {
         match raw {
             RawIR::Atom(token) => {
+                let s = token.as_text().into_owned();
+                SExpOwned::Atom(s)
+            },
+            RawIR::List { rule, elems, .. } => {
+                let elems = elems.into_iter().map(SExpOwned::from).collect();
+                SExpOwned::List { rule, elems }
+            }
+        }
+    }
+}
+
+impl<L: Lang> IROwned<L> for SExpOwned<L> {
+    type Builder = RawIROwnedBuilder<L, Self>;
+}
+
+impl<L: Lang> From<RawIROwned<L>> for SExpOwned<L> {
+    fn from(raw: RawIROwned<L>) -> Self {
+        match raw {
+            RawIROwned::Atom(token) => {
                 let s = token.as_str().to_string();
                 SExpOwned::Atom(s)
             },
-            RawIR::List { rule, elems } => {
+            RawIROwned::List { rule, elems, .. } => {
                 let elems = elems.into_iter().map(SExpOwned::from).collect();
                 SExpOwned::List { rule, elems }
             }
         }
     }
 }
+
+impl<L: Lang> Tree for SExpOwned<L> {
+    type Rule = L::RuleTag;
+    type Token = str;
+
+    fn rule(&self) -> Option<&Self::Rule> {
+        match self {
+            SExpOwned::List { rule, .. } => Some(rule),
+            _ => None,
+        }
+    }
+
+    fn token(&self) -> Option<&Self::Token> {
+        match self {
+            SExpOwned::Atom(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> Option<&VecDeque<Self>> {
+        match self {
+            SExpOwned::List { elems, .. } => Some(elems),
+            _ => None,
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut VecDeque<Self>> {
+        match self {
+            SExpOwned::List { elems, .. } => Some(elems),
+            _ => None,
+        }
+    }
+}
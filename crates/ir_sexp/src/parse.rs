@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use copager_lang::rule::{RuleSet, RuleTag};
+use copager_lang::Lang;
+
+use crate::SExpOwned;
+
+#[derive(Debug, Error)]
+#[error("{message} (at {pos})")]
+pub struct SExpParseError {
+    pub message: String,
+    pub pos: usize,
+}
+
+// Display や pretty により出力された S 式を読み戻す (規則は列挙子名で指定する)
+impl<L: Lang> FromStr for SExpOwned<L> {
+    type Err = SExpParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let mut parser = SExpParser::<L>::new(src);
+        let sexp = parser.parse_sexp()?;
+        parser.skip_spaces();
+        if !parser.rest().is_empty() {
+            return Err(parser.error("Expected end of input"));
+        }
+        Ok(sexp)
+    }
+}
+
+struct SExpParser<'a, L: Lang> {
+    src: &'a str,
+    cursor: usize,
+    rules: Vec<L::RuleTag>,
+}
+
+impl<'a, L: Lang> SExpParser<'a, L> {
+    fn new(src: &'a str) -> Self {
+        let rules = L::RuleSet::instantiate().iter().collect();
+        SExpParser { src, cursor: 0, rules }
+    }
+
+    // <sexp> ::= '(' <ident> <sexp>* ')' | <string>
+    fn parse_sexp(&mut self) -> Result<SExpOwned<L>, SExpParseError> {
+        self.skip_spaces();
+        match self.rest().chars().next() {
+            Some('(') => {
+                self.cursor += 1;
+                let rule = self.parse_rule()?;
+                let mut elems = VecDeque::new();
+                loop {
+                    self.skip_spaces();
+                    match self.rest().chars().next() {
+                        Some(')') => break,
+                        Some(_) => elems.push_back(self.parse_sexp()?),
+                        None => return Err(self.error("Expected ')'")),
+                    }
+                }
+                self.cursor += 1;
+                Ok(SExpOwned::List { rule, elems })
+            }
+            Some('"') => Ok(SExpOwned::Atom(self.parse_string()?)),
+            _ => Err(self.error("Expected '(' or a string")),
+        }
+    }
+
+    fn parse_rule(&mut self) -> Result<L::RuleTag, SExpParseError> {
+        self.skip_spaces();
+        let pos = self.cursor;
+        let end_idx = self.rest()
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(self.rest().len());
+        if end_idx == 0 {
+            return Err(self.error("Expected a rule name"));
+        }
+        let name = &self.src[pos..pos + end_idx];
+        self.cursor += end_idx;
+
+        match self.rules.iter().find(|rule| rule.as_name() == name) {
+            Some(rule) => Ok(rule.clone()),
+            None => Err(SExpParseError { message: format!("Unknown rule {}", name), pos }),
+        }
+    }
+
+    // Rust の文字列リテラル ({:?} の出力) として読む
+    fn parse_string(&mut self) -> Result<String, SExpParseError> {
+        let mut s = String::new();
+        let mut chars = self.rest().char_indices().skip(1);
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.cursor += idx + 1;
+                    return Ok(s);
+                }
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, '0')) => '\0',
+                        Some((_, 'u')) => {
+                            let rest = &self.rest()[idx + 2..];
+                            let code = rest
+                                .strip_prefix('{')
+                                .and_then(|rest| rest.split_once('}'))
+                                .and_then(|(hex, _)| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32);
+                            match code {
+                                Some(c) => {
+                                    chars.find(|(_, c)| *c == '}');
+                                    c
+                                }
+                                None => return Err(self.error_at("Invalid unicode escape", self.cursor + idx)),
+                            }
+                        }
+                        Some((_, c)) => c,
+                        None => break,
+                    };
+                    s.push(escaped);
+                }
+                c => s.push(c),
+            }
+        }
+        Err(self.error("Unterminated string"))
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+        self.cursor += rest.len() - rest.trim_start().len();
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.cursor..]
+    }
+
+    fn error(&self, message: &str) -> SExpParseError {
+        self.error_at(message, self.cursor)
+    }
+
+    fn error_at(&self, message: &str, pos: usize) -> SExpParseError {
+        SExpParseError { message: message.to_string(), pos }
+    }
+}
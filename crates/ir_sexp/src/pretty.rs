@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use copager_lang::rule::RuleTag;
+use copager_lang::Lang;
+use copager_ir::visit::Tree;
+
+// 1 行に収まらないリストは要素ごとに改行し，2 文字ずつ字下げする
+// (規則は列挙子名，アトムは Rust の文字列リテラルとして出力するため SExpOwned として読み戻せる)
+pub(crate) fn pretty<L, T>(sexp: &T, width: usize) -> String
+where
+    L: Lang,
+    T: Tree<Rule = L::RuleTag, Token = str>,
+{
+    let mut out = String::new();
+    write_pretty::<L, T>(sexp, width, 0, 0, &mut out);
+    out
+}
+
+// trailing は同じ行に続く閉じ括弧の数
+fn write_pretty<L, T>(sexp: &T, width: usize, indent: usize, trailing: usize, out: &mut String)
+where
+    L: Lang,
+    T: Tree<Rule = L::RuleTag, Token = str>,
+{
+    let (Some(rule), Some(elems)) = (sexp.rule(), sexp.children()) else {
+        write_flat::<L, T>(sexp, out);
+        return;
+    };
+    let fits = width
+        .checked_sub(indent + trailing)
+        .and_then(|limit| flat_width::<L, T>(sexp, limit))
+        .is_some();
+    if fits || elems.is_empty() {
+        write_flat::<L, T>(sexp, out);
+        return;
+    }
+
+    write!(out, "({}", rule.as_name()).unwrap();
+    for (idx, elem) in elems.iter().enumerate() {
+        let trailing = if idx + 1 == elems.len() { trailing + 1 } else { 0 };
+        write!(out, "\n{}", " ".repeat(indent + 2)).unwrap();
+        write_pretty::<L, T>(elem, width, indent + 2, trailing, out);
+    }
+    out.push(')');
+}
+
+fn write_flat<L, T>(sexp: &T, out: &mut String)
+where
+    L: Lang,
+    T: Tree<Rule = L::RuleTag, Token = str>,
+{
+    match (sexp.rule(), sexp.children(), sexp.token()) {
+        (Some(rule), Some(elems), _) => {
+            write!(out, "({}", rule.as_name()).unwrap();
+            for elem in elems {
+                out.push(' ');
+                write_flat::<L, T>(elem, out);
+            }
+            out.push(')');
+        }
+        (_, _, Some(atom)) => write!(out, "{:?}", atom).unwrap(),
+        _ => unreachable!(),
+    }
+}
+
+// 1 行で出力した場合の表示幅 (文字数)
+// limit を超えた時点で計測を打ち切り None を返す
+fn flat_width<L, T>(sexp: &T, limit: usize) -> Option<usize>
+where
+    L: Lang,
+    T: Tree<Rule = L::RuleTag, Token = str>,
+{
+    let width = match (sexp.rule(), sexp.children(), sexp.token()) {
+        (Some(rule), Some(elems), _) => {
+            let mut width = 1 + rule.as_name().chars().count() + 1;
+            for elem in elems {
+                let rest = limit.checked_sub(width + 1)?;
+                width += 1 + flat_width::<L, T>(elem, rest)?;
+            }
+            width
+        }
+        (_, _, Some(atom)) => format!("{:?}", atom).chars().count(),
+        _ => unreachable!(),
+    };
+    (width <= limit).then_some(width)
+}
@@ -4,6 +4,9 @@ use std::fmt::{Debug, Display};
 use copager_lang::token::{Token, TokenTag};
 use copager_lang::Lang;
 use copager_ir::{IR, IRBuilder, RawIR};
+use copager_ir::visit::Tree;
+
+use crate::pretty::pretty;
 
 #[derive(Debug, IR, IRBuilder)]
 pub enum SExp<'input, L: Lang> {
@@ -14,6 +17,25 @@ pub enum SExp<'input, L: Lang> {
     },
 }
 
+impl<L: Lang> PartialEq for SExp<'_, L> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SExp::Atom(lhs), SExp::Atom(rhs)) => lhs == rhs,
+            (SExp::List { rule: lhs_rule, elems: lhs }, SExp::List { rule: rhs_rule, elems: rhs }) => {
+                lhs_rule == rhs_rule && lhs == rhs
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<L: Lang> SExp<'_, L> {
+    // 1 行あたり width 文字に収まるように字下げして出力する
+    pub fn pretty(&self, width: usize) -> String {
+        pretty::<L, Self>(self, width)
+    }
+}
+
 impl<L: Lang> Display for SExp<'_, L>
 where
     L::TokenTag: Debug,
@@ -21,7 +43,7 @@ where
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            SExp::List { rule, elems } => {
+            SExp::List { rule, elems, .. } => {
                 write!(f, "({:?}", rule)?;
                 for elem in elems {
                     write!(f, " {}", elem)?;
@@ -40,10 +62,43 @@ impl<'input, L: Lang> From<RawIR<'input, L>> for SExp<'input, L> {
                 let s = token.as_str();
                 SExp::Atom(s)
             }
-            RawIR::List { rule, elems } => {
+            RawIR::List { rule, elems, .. } => {
                 let elems = elems.into_iter().map(SExp::from).collect();
                 SExp::List { rule, elems }
             }
         }
     }
 }
+
+impl<L: Lang> Tree for SExp<'_, L> {
+    type Rule = L::RuleTag;
+    type Token = str;
+
+    fn rule(&self) -> Option<&Self::Rule> {
+        match self {
+            SExp::List { rule, .. } => Some(rule),
+            _ => None,
+        }
+    }
+
+    fn token(&self) -> Option<&Self::Token> {
+        match self {
+            SExp::Atom(s) => Some(*s),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> Option<&VecDeque<Self>> {
+        match self {
+            SExp::List { elems, .. } => Some(elems),
+            _ => None,
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut VecDeque<Self>> {
+        match self {
+            SExp::List { elems, .. } => Some(elems),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_sexp::{SExp, SExpOwned};
+
+#[allow(dead_code)]
+#[derive(Debug, Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"\*")]
+    Mul,
+    #[token(r"\(", ir_omit)]
+    BracketL,
+    #[token(r"\)", ir_omit)]
+    BracketR,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r#""([^"\\]|\\.)*""#)]
+    Str,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= <term>")]
+    Expr,
+    #[rule("<term> ::= <term> Mul <num>")]
+    #[rule("<term> ::= <num>")]
+    Term,
+    #[rule("<num> ::= BracketL <expr> BracketR")]
+    #[rule("<num> ::= Num")]
+    #[rule("<num> ::= Str")]
+    Num,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+#[test]
+fn pretty_print() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+    let sexp = processor.process::<SExp<_>>("1 + 2 * 3")?;
+
+    // 幅に収まる場合は 1 行で出力する
+    assert_eq!(sexp.pretty(80), sexp.to_string());
+
+    let expected = r#"
+(Expr
+  (Expr (Term (Num "1")))
+  "+"
+  (Term (Term (Num "2")) "*" (Num "3")))
+"#;
+    assert_eq!(sexp.pretty(40), expected.trim());
+
+    // 閉じ括弧も幅に含める
+    let expected = r#"
+(Expr
+  (Expr (Term (Num "1")))
+  "+"
+  (Term
+    (Term (Num "2"))
+    "*"
+    (Num "3")))
+"#;
+    assert_eq!(sexp.pretty(38), expected.trim());
+
+    // 幅はバイト数ではなく文字数で数える
+    let sexp = processor.process::<SExp<_>>(r#""あいう" * 2"#)?;
+    let flat = sexp.to_string();
+    assert_eq!(sexp.pretty(flat.chars().count()), flat);
+    assert_ne!(sexp.pretty(flat.chars().count() - 1), flat);
+
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let input = r#"(1 + "a \"b\"\n") * 2"#;
+    let sexp = processor.process::<SExpOwned<_>>(input)?;
+    for width in [0, 20, 80] {
+        let printed = sexp.pretty(width);
+        assert_eq!(printed.parse::<SExpOwned<TestLang>>()?, sexp);
+    }
+    assert_eq!(sexp.to_string().parse::<SExpOwned<TestLang>>()?, sexp);
+
+    let sexp = processor.process::<SExp<_>>(input)?;
+    let parsed = sexp.pretty(10).parse::<SExpOwned<TestLang>>()?;
+    assert_eq!(parsed.pretty(10), sexp.pretty(10));
+
+    Ok(())
+}
+
+#[test]
+fn parse_error() {
+    let check = |src: &str, message: &str, pos: usize| {
+        let err = src.parse::<SExpOwned<TestLang>>().unwrap_err();
+        assert_eq!((err.message.as_str(), err.pos), (message, pos));
+    };
+
+    check(r#"(Expr (Unknown "1"))"#, "Unknown rule Unknown", 7);
+    check(r#"(Expr (Term "1")"#, "Expected ')'", 16);
+    check(r#"(Expr "1) "#, "Unterminated string", 6);
+    check(r#"(Expr "1") "2""#, "Expected end of input", 11);
+    check(r#"(Expr 1)"#, "Expected '(' or a string", 6);
+}
@@ -0,0 +1,88 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir::visit::{Fold, Tree, Visit, Visitor};
+use copager_ir_sexp::{SExp, SExpOwned};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= <expr> Plus <num>")]
+    #[rule("<expr> ::= <num>")]
+    Expr,
+    #[rule("<num> ::= Num")]
+    Num,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+#[test]
+fn visit_sexp() -> anyhow::Result<()> {
+    struct Sum(u32);
+
+    impl Visitor<SExp<'_, TestLang>> for Sum {
+        fn visit_token(&mut self, atom: &str, _: &SExp<'_, TestLang>) -> Visit {
+            self.0 += atom.parse::<u32>().unwrap_or(0);
+            Visit::Continue
+        }
+    }
+
+    let processor = TestProcessor::new().build()?;
+    let sexp = processor.process::<SExp<_>>("1 + 2 + 3")?;
+
+    let mut sum = Sum(0);
+    assert_eq!(sexp.visit(&mut sum), Visit::Continue);
+    assert_eq!(sum.0, 6);
+    assert_eq!(sexp.leaves().filter_map(Tree::token).collect::<Vec<_>>(), vec!["1", "+", "2", "+", "3"]);
+    assert_eq!(sexp.post_order().last().and_then(Tree::rule), Some(&TestRule::Expr));
+
+    Ok(())
+}
+
+#[test]
+fn fold_sexp() -> anyhow::Result<()> {
+    // 子を 1 つだけ持つ <expr> を取り除く
+    struct Flatten;
+
+    impl Fold<SExpOwned<TestLang>> for Flatten {
+        fn fold_node(&mut self, tree: SExpOwned<TestLang>) -> SExpOwned<TestLang> {
+            match tree {
+                SExpOwned::List { rule: TestRule::Expr, mut elems } if elems.len() == 1 => {
+                    self.fold(elems.pop_front().unwrap())
+                }
+                SExpOwned::List { rule, elems } => {
+                    let elems = elems.into_iter().map(|elem| self.fold(elem)).collect();
+                    SExpOwned::List { rule, elems }
+                }
+                atom => atom,
+            }
+        }
+    }
+
+    let processor = TestProcessor::new().build()?;
+    let sexp = processor.process::<SExpOwned<_>>("1 + 2")?;
+    assert_eq!(sexp.fold(&mut Flatten).to_string(), r#"(Expr (Num "1") "+" (Num "2"))"#);
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+use copager_lang::rule::{RuleElem, RuleTag};
+use copager_lang::Lang;
+
+// CSTreeWalker, CSTreeOwnedWalker の try_ から始まるメソッドが返すエラー
+#[derive(Debug, Error)]
+#[error("Expected {expected} but found {found}")]
+pub struct WalkError {
+    pub expected: String,
+    pub found: String,
+    pub span: Option<(usize, usize)>,
+}
+
+// 規則の左辺の名前 (<expr> など，複数の左辺を持つ場合は / で区切る)
+pub(crate) fn rule_name<L: Lang>(rule: &L::RuleTag) -> String {
+    let mut names: Vec<String> = vec![];
+    for rule in rule.as_rules() {
+        if let RuleElem::NonTerm(name) = &rule.lhs {
+            let name = format!("<{}>", name);
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names.join("/")
+}
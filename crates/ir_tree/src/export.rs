@@ -0,0 +1,257 @@
+use std::fmt::Write;
+
+use serde_json::{json, Value};
+
+use copager_lang::rule::RuleTag;
+use copager_lang::token::TokenTag;
+use copager_lang::Lang;
+use copager_ir::visit::Tree;
+
+use crate::r#ref::CSTree;
+use crate::owned::CSTreeOwned;
+
+// 他の言語から読み込むための出力形式 (字句や規則は列挙子名で表す)
+//
+// JSON (version 1)
+//   { "version": 1, "root": <element> }
+//   <element> は以下のいずれか
+//     節: { "type": "node", "kind": 規則名, "span": [begin, end] | null, "text": 文字列 | null,
+//           "children": [<element>, ...] }
+//     葉: { "type": "token", "kind": 字句名, "span": [begin, end], "text": 文字列,
+//           "leading_trivia": [<trivia>, ...], "trailing_trivia": [<trivia>, ...] }
+//     <trivia> は { "kind": 字句名, "span": [begin, end], "text": 文字列 }
+//   - span は入力におけるバイト位置 (end は含まない)，葉を持たない節は null
+//   - 節の text は入力を与えた場合のみ span の範囲の文字列，それ以外は null
+//   - *_trivia は trivia を含める場合のみ出力する (空白とコメントの両方を含む)
+//
+// XML (version 1)
+//   <tree version="1"> 以下に同じ構造を <node> と <token> で表す
+//   kind, begin, end, text は属性 (span や text を持たない場合は省略)
+//   trivia は <token> の子の <trivia position="leading|trailing" kind=".." begin=".." end=".." text=".."/> とする
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Clone)]
+pub struct Exporter<'src> {
+    trivia: bool,
+    source: Option<&'src str>,
+}
+
+impl<'src> Exporter<'src> {
+    pub fn new() -> Self {
+        Exporter::default()
+    }
+
+    pub fn trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
+
+    // 節の text に用いる入力
+    pub fn source(mut self, source: &'src str) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn to_json<L, T>(&self, tree: &T) -> String
+    where
+        L: Lang,
+        T: Exportable<L>,
+    {
+        let root = json!({
+            "version": EXPORT_VERSION,
+            "root": self.json_elem(tree),
+        });
+        serde_json::to_string_pretty(&root).unwrap()
+    }
+
+    pub fn to_xml<L, T>(&self, tree: &T) -> String
+    where
+        L: Lang,
+        T: Exportable<L>,
+    {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(out, "<tree version=\"{}\">", EXPORT_VERSION).unwrap();
+        self.xml_elem(tree, 1, &mut out);
+        out.push_str("</tree>\n");
+        out
+    }
+
+    fn json_elem<L, T>(&self, tree: &T) -> Value
+    where
+        L: Lang,
+        T: Exportable<L>,
+    {
+        let span = tree.export_span().map(|(begin, end)| json!([begin, end]));
+        match (tree.rule(), tree.token(), tree.children()) {
+            (Some(rule), _, Some(children)) => json!({
+                "type": "node",
+                "kind": rule.as_name(),
+                "span": span,
+                "text": self.node_text(tree),
+                "children": children.iter().map(|child| self.json_elem(child)).collect::<Vec<_>>(),
+            }),
+            (_, Some(token), _) => {
+                let mut elem = json!({
+                    "type": "token",
+                    "kind": token.as_name(),
+                    "span": span,
+                    "text": tree.leaf_text(),
+                });
+                if self.trivia {
+                    let (leading, trailing) = split_trivia(tree);
+                    let to_json = |trivia: Vec<ExportTrivia<L>>| {
+                        trivia
+                            .into_iter()
+                            .map(|(kind, (begin, end), text)| json!({
+                                "kind": kind.as_name(),
+                                "span": [begin, end],
+                                "text": text,
+                            }))
+                            .collect::<Vec<_>>()
+                    };
+                    elem["leading_trivia"] = json!(to_json(leading));
+                    elem["trailing_trivia"] = json!(to_json(trailing));
+                }
+                elem
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn xml_elem<L, T>(&self, tree: &T, depth: usize, out: &mut String)
+    where
+        L: Lang,
+        T: Exportable<L>,
+    {
+        let indent = "  ".repeat(depth);
+        let mut attrs = String::new();
+        if let Some((begin, end)) = tree.export_span() {
+            write!(attrs, " begin=\"{}\" end=\"{}\"", begin, end).unwrap();
+        }
+
+        match (tree.rule(), tree.token(), tree.children()) {
+            (Some(rule), _, Some(children)) => {
+                if let Some(text) = self.node_text(tree) {
+                    write!(attrs, " text=\"{}\"", escape_xml(text)).unwrap();
+                }
+                if children.is_empty() {
+                    writeln!(out, "{}<node kind=\"{}\"{}/>", indent, rule.as_name(), attrs).unwrap();
+                    return;
+                }
+                writeln!(out, "{}<node kind=\"{}\"{}>", indent, rule.as_name(), attrs).unwrap();
+                for child in children {
+                    self.xml_elem(child, depth + 1, out);
+                }
+                writeln!(out, "{}</node>", indent).unwrap();
+            }
+            (_, Some(token), _) => {
+                write!(attrs, " text=\"{}\"", escape_xml(tree.leaf_text().unwrap_or_default())).unwrap();
+                let (leading, trailing) = split_trivia(tree);
+                if !self.trivia || (leading.is_empty() && trailing.is_empty()) {
+                    writeln!(out, "{}<token kind=\"{}\"{}/>", indent, token.as_name(), attrs).unwrap();
+                    return;
+                }
+                writeln!(out, "{}<token kind=\"{}\"{}>", indent, token.as_name(), attrs).unwrap();
+                let trivia = leading
+                    .into_iter()
+                    .map(|trivia| ("leading", trivia))
+                    .chain(trailing.into_iter().map(|trivia| ("trailing", trivia)));
+                for (position, (kind, (begin, end), text)) in trivia {
+                    writeln!(
+                        out,
+                        "{}  <trivia position=\"{}\" kind=\"{}\" begin=\"{}\" end=\"{}\" text=\"{}\"/>",
+                        indent, position, kind.as_name(), begin, end, escape_xml(text),
+                    ).unwrap();
+                }
+                writeln!(out, "{}</token>", indent).unwrap();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn node_text<L, T>(&self, tree: &T) -> Option<&'src str>
+    where
+        L: Lang,
+        T: Exportable<L>,
+    {
+        let (begin, end) = tree.export_span()?;
+        self.source?.get(begin..end)
+    }
+}
+
+// 葉の Trivia (字句，位置，文字列)
+type ExportTrivia<'a, L> = (&'a <L as Lang>::TokenTag, (usize, usize), &'a str);
+
+// 葉の Trivia を葉より前のものと後のものに分ける
+fn split_trivia<L, T>(tree: &T) -> (Vec<ExportTrivia<'_, L>>, Vec<ExportTrivia<'_, L>>)
+where
+    L: Lang,
+    T: Exportable<L>,
+{
+    let begin = tree.export_span().map_or(0, |(begin, _)| begin);
+    tree.trivia().into_iter().partition(|(_, span, _)| span.1 <= begin)
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Exporter により出力できる木 (CSTree と CSTreeOwned)
+pub trait Exportable<L: Lang>: Tree<Rule = L::RuleTag, Token = L::TokenTag> {
+    fn leaf_text(&self) -> Option<&str>;
+    fn export_span(&self) -> Option<(usize, usize)>;
+    fn trivia(&self) -> Vec<ExportTrivia<'_, L>>;
+}
+
+impl<L: Lang> Exportable<L> for CSTree<'_, L> {
+    fn leaf_text(&self) -> Option<&str> {
+        match self {
+            CSTree::Leaf { text, .. } => Some(text),
+            CSTree::Node { .. } => None,
+        }
+    }
+
+    fn export_span(&self) -> Option<(usize, usize)> {
+        self.span()
+    }
+
+    fn trivia(&self) -> Vec<ExportTrivia<'_, L>> {
+        CSTree::trivia(self)
+            .iter()
+            .map(|trivia| (&trivia.kind, trivia.span, trivia.text))
+            .collect()
+    }
+}
+
+impl<L: Lang> Exportable<L> for CSTreeOwned<L> {
+    fn leaf_text(&self) -> Option<&str> {
+        match self {
+            CSTreeOwned::Leaf { text, .. } => Some(text),
+            CSTreeOwned::Node { .. } => None,
+        }
+    }
+
+    fn export_span(&self) -> Option<(usize, usize)> {
+        self.span()
+    }
+
+    fn trivia(&self) -> Vec<ExportTrivia<'_, L>> {
+        CSTreeOwned::trivia(self)
+            .iter()
+            .map(|trivia| (&trivia.kind, trivia.span, trivia.text.as_str()))
+            .collect()
+    }
+}
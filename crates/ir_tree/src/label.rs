@@ -0,0 +1,30 @@
+use copager_lang::rule::{Rule, RuleElem, RuleTag};
+use copager_lang::token::TokenTag;
+use copager_lang::Lang;
+
+// 還元に用いた規則 (tag.as_rules() における alt 番目の規則) から各子に付けられたラベルを返す
+// (規則が記録されていない節の子はラベルを持たない)
+pub(crate) fn resolve_labels<L: Lang>(tag: &L::RuleTag, alt: Option<usize>, len: usize) -> Vec<Option<String>> {
+    alt.and_then(|alt| tag.as_rules().into_iter().nth(alt))
+        .map(|rule| kept_labels(&rule))
+        .filter(|labels| labels.len() == len)
+        .unwrap_or_else(|| vec![None; len])
+}
+
+// CSTree に含まれる右辺の要素のラベル (ε と ir_omit が指定された字句は CSTree に含まれない)
+fn kept_labels<T, R>(rule: &Rule<T, R>) -> Vec<Option<String>>
+where
+    T: TokenTag,
+    R: RuleTag<T>,
+{
+    rule.rhs
+        .iter()
+        .enumerate()
+        .filter(|(_, elem)| match elem {
+            RuleElem::Term(token) => !token.as_option_list().contains(&"ir_omit"),
+            RuleElem::NonTerm(_) => true,
+            _ => false,
+        })
+        .map(|(idx, _)| rule.label_of(idx).map(str::to_string))
+        .collect()
+}
@@ -1,2 +1,9 @@
 pub mod r#ref;
 pub mod owned;
+
+mod error;
+mod export;
+mod label;
+
+pub use error::WalkError;
+pub use export::{Exporter, Exportable, EXPORT_VERSION};
@@ -1,5 +1,5 @@
 mod data;
 mod walker;
 
-pub use data::CSTreeOwned;
+pub use data::{CSTreeOwned, LeafTriviaOwned};
 pub use walker::CSTreeOwnedWalker;
@@ -3,34 +3,213 @@ use std::fmt::Debug;
 
 use serde::{Serialize, Deserialize};
 
-use copager_lang::token::{Token, TokenTag};
+use copager_lang::token::{Token, TokenSource, TokenTag};
 use copager_lang::Lang;
-use copager_ir::{IR, IRBuilder, RawIR};
+use copager_ir::{IR, IRBuilder, IROwned, RawIR, RawIROwned, RawIROwnedBuilder};
+use copager_ir::visit::Tree;
+
+use crate::error::rule_name;
+use crate::label::resolve_labels;
 
 #[derive(Debug, Serialize, Deserialize, IR, IRBuilder)]
 pub enum CSTreeOwned<L: Lang> {
+    // 今後フィールドが追加されうるため，パターンでは `..` を用いる (コメントは leading_comments などから得る)
+    #[non_exhaustive]
     Leaf {
         tag: L::TokenTag,
         text: String,
+        #[serde(default)]
+        span: (usize, usize),
+        #[serde(default)]
+        leading_comments: Vec<String>,
+        #[serde(default)]
+        trailing_comments: Vec<String>,
+        #[serde(default)]
+        trivia: Vec<LeafTriviaOwned<L::TokenTag>>,
     },
     Node {
         tag: L::RuleTag,
+        #[serde(default)]
+        alt: Option<usize>,  // 還元に用いた規則の tag.as_rules() における位置 (ラベルの解決に用いる)
         children: VecDeque<CSTreeOwned<L>>,
     },
 }
 
-impl<'input, L: Lang> From<RawIR<'input, L>> for CSTreeOwned<L> {
-    fn from(raw: RawIR<'input, L>) -> Self {
+// 葉に含まれる Trivia (空白とコメント)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeafTriviaOwned<T> {
+    pub kind: T,
+    pub span: (usize, usize),
+    pub text: String,
+}
+
+// バイト列の解析時，UTF-8 として不正なバイトは置換文字に変換される
+impl<'input, L, S> From<RawIR<'input, L, S>> for CSTreeOwned<L>
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
+    fn from(raw: RawIR<'input, L, S>) -> Self {
         match raw {
             RawIR::Atom(token) => {
+                let text = token.as_text().into_owned();
+                let leading_comments = token.leading_trivia()
+                    .filter(|trivia| trivia.is_comment())
+                    .map(|trivia| token.trivia_slice(trivia).to_text().into_owned())
+                    .collect();
+                let trailing_comments = token.trailing_trivia()
+                    .filter(|trivia| trivia.is_comment())
+                    .map(|trivia| token.trivia_slice(trivia).to_text().into_owned())
+                    .collect();
+                let trivia = token.trivia
+                    .iter()
+                    .map(|trivia| LeafTriviaOwned {
+                        kind: trivia.kind.clone(),
+                        span: trivia.span,
+                        text: token.trivia_slice(trivia).to_text().into_owned(),
+                    })
+                    .collect();
+                let span = token.body;
+                let tag = token.kind;
+                CSTreeOwned::Leaf { tag, text, span, leading_comments, trailing_comments, trivia }
+            },
+            RawIR::List { rule: tag, alt, elems } => {
+                let children = elems.into_iter().map(CSTreeOwned::from).collect();
+                CSTreeOwned::Node { tag, alt, children }
+            }
+        }
+    }
+}
+
+impl<L: Lang> IROwned<L> for CSTreeOwned<L> {
+    type Builder = RawIROwnedBuilder<L, Self>;
+}
+
+impl<L: Lang> From<RawIROwned<L>> for CSTreeOwned<L> {
+    fn from(raw: RawIROwned<L>) -> Self {
+        match raw {
+            RawIROwned::Atom(token) => {
                 let text = token.as_str().to_owned();
+                let leading_comments = token.leading_trivia()
+                    .filter(|trivia| trivia.is_comment())
+                    .map(|trivia| token.trivia_str(trivia).to_owned())
+                    .collect();
+                let trailing_comments = token.trailing_trivia()
+                    .filter(|trivia| trivia.is_comment())
+                    .map(|trivia| token.trivia_str(trivia).to_owned())
+                    .collect();
+                let trivia = token.trivia
+                    .iter()
+                    .map(|trivia| LeafTriviaOwned {
+                        kind: trivia.kind.clone(),
+                        span: trivia.span,
+                        text: token.trivia_str(trivia).to_owned(),
+                    })
+                    .collect();
+                let span = token.body;
                 let tag = token.kind;
-                CSTreeOwned::Leaf { tag, text }
+                CSTreeOwned::Leaf { tag, text, span, leading_comments, trailing_comments, trivia }
             },
-            RawIR::List { rule: tag, elems } => {
+            RawIROwned::List { rule: tag, alt, elems } => {
                 let children = elems.into_iter().map(CSTreeOwned::from).collect();
-                CSTreeOwned::Node { tag, children }
+                CSTreeOwned::Node { tag, alt, children }
             }
         }
     }
 }
+
+impl<L: Lang> CSTreeOwned<L> {
+    // ラベル (規則中の <lhs:expr> の lhs) が付けられた子
+    pub fn child_by_label(&self, label: &str) -> Option<&CSTreeOwned<L>> {
+        let pos = self.child_labels()
+            .iter()
+            .position(|l| l.as_deref() == Some(label))?;
+        match self {
+            CSTreeOwned::Node { children, .. } => children.get(pos),
+            CSTreeOwned::Leaf { .. } => None,
+        }
+    }
+
+    // 各子に付けられたラベル (葉の場合は空)
+    pub(crate) fn child_labels(&self) -> Vec<Option<String>> {
+        match self {
+            CSTreeOwned::Node { tag, alt, children } => resolve_labels::<L>(tag, *alt, children.len()),
+            CSTreeOwned::Leaf { .. } => vec![],
+        }
+    }
+
+    // 葉は字句名，節は規則名 (<expr> など) で表す
+    pub fn describe(&self) -> String {
+        match self {
+            CSTreeOwned::Leaf { tag, .. } => tag.as_name().to_string(),
+            CSTreeOwned::Node { tag, .. } => rule_name::<L>(tag),
+        }
+    }
+
+    // 入力における位置 (節の場合は先頭の葉から末尾の葉まで，葉を持たない節の場合は None)
+    pub fn span(&self) -> Option<(usize, usize)> {
+        match self {
+            CSTreeOwned::Leaf { span, .. } => Some(*span),
+            CSTreeOwned::Node { children, .. } => {
+                let begin = children.iter().find_map(|child| child.span())?;
+                let end = children.iter().rev().find_map(|child| child.span())?;
+                Some((begin.0, end.1))
+            }
+        }
+    }
+
+    pub fn leading_comments(&self) -> &[String] {
+        match self {
+            CSTreeOwned::Leaf { leading_comments, .. } => leading_comments,
+            CSTreeOwned::Node { .. } => &[],
+        }
+    }
+
+    pub fn trailing_comments(&self) -> &[String] {
+        match self {
+            CSTreeOwned::Leaf { trailing_comments, .. } => trailing_comments,
+            CSTreeOwned::Node { .. } => &[],
+        }
+    }
+
+    // 葉に含まれる Trivia (入力における位置の順，節の場合は空)
+    pub fn trivia(&self) -> &[LeafTriviaOwned<L::TokenTag>] {
+        match self {
+            CSTreeOwned::Leaf { trivia, .. } => trivia,
+            CSTreeOwned::Node { .. } => &[],
+        }
+    }
+}
+
+impl<L: Lang> Tree for CSTreeOwned<L> {
+    type Rule = L::RuleTag;
+    type Token = L::TokenTag;
+
+    fn rule(&self) -> Option<&Self::Rule> {
+        match self {
+            CSTreeOwned::Node { tag: rule, .. } => Some(rule),
+            _ => None,
+        }
+    }
+
+    fn token(&self) -> Option<&Self::Token> {
+        match self {
+            CSTreeOwned::Leaf { tag, .. } => Some(tag),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> Option<&VecDeque<Self>> {
+        match self {
+            CSTreeOwned::Node { children, .. } => Some(children),
+            _ => None,
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut VecDeque<Self>> {
+        match self {
+            CSTreeOwned::Node { children, .. } => Some(children),
+            _ => None,
+        }
+    }
+}
@@ -1,14 +1,21 @@
+use std::collections::VecDeque;
+
+use copager_lang::token::TokenTag;
 use copager_lang::Lang;
 
+use crate::error::{rule_name, WalkError};
+
 use super::CSTreeOwned;
 
 pub struct CSTreeOwnedWalker<L: Lang> {
     cst: Option<CSTreeOwned<L>>,
+    labels: VecDeque<Option<String>>,  // 残りの子に付けられたラベル
+    span: Option<(usize, usize)>,      // 要素が足りない場合のエラーに用いる
 }
 
 impl <'src, L: Lang> From<CSTreeOwned<L>> for CSTreeOwnedWalker<L> {
     fn from(cst: CSTreeOwned<L>) -> Self {
-        CSTreeOwnedWalker { cst: Some(cst) }
+        CSTreeOwnedWalker::new(cst)
     }
 }
 
@@ -53,49 +60,221 @@ impl<L: Lang> CSTreeOwnedWalker<L> {
         }
     }
 
+    // ラベルが付けられた子を取り出す (子の位置によらない)
+    pub fn expect_labeled_leaf(&mut self, label: &str) -> L::TokenTag {
+        match self.remove_labeled(label) {
+            Some(CSTreeOwned::Leaf { tag, .. }) => tag,
+            Some(..) => panic!("Expected a leaf labeled \"{}\" but found a node", label),
+            None => panic!("No element labeled \"{}\" in the CSTreeOwnedWalker", label),
+        }
+    }
+
+    pub fn expect_labeled<T>(&mut self, label: &str) -> T
+    where
+        T: From<CSTreeOwnedWalker<L>>,
+    {
+        match self.remove_labeled(label) {
+            Some(tree) => T::from(CSTreeOwnedWalker::new(tree)),
+            None => panic!("No element labeled \"{}\" in the CSTreeOwnedWalker", label),
+        }
+    }
+
+    // 先頭の子をリストとして展開し，要素を出現順に変換する
+    // (リストの規則は先頭の子の規則とし，字句の子も要素として扱う)
     pub fn expect_nodes<T>(&mut self) -> Vec<T>
     where
         T: From<CSTreeOwnedWalker<L>>,
+    {
+        self.expect_list_by(None, None)
+    }
+
+    // 規則 list によるリスト (左再帰・右再帰・空の規則のいずれの形でもよい)
+    pub fn expect_list<T>(&mut self, list: &L::RuleTag) -> Vec<T>
+    where
+        T: From<CSTreeOwnedWalker<L>>,
+    {
+        self.expect_list_by(Some(list), None)
+    }
+
+    // 字句 sep で区切られたリスト (sep が ir_omit でない場合に用いる)
+    pub fn expect_separated<T>(&mut self, list: &L::RuleTag, sep: &L::TokenTag) -> Vec<T>
+    where
+        T: From<CSTreeOwnedWalker<L>>,
+    {
+        self.expect_list_by(Some(list), Some(sep))
+    }
+
+    // 以下は木の形が期待と異なる場合に panic せず WalkError を返す
+    pub fn try_expect_leaf(&mut self) -> Result<L::TokenTag, WalkError> {
+        match self.pop_front() {
+            Some(CSTreeOwned::Leaf { tag, .. }) => Ok(tag),
+            found => Err(self.error("a token", found.as_ref())),
+        }
+    }
+
+    pub fn try_expect_leaf_of(&mut self, expected: &L::TokenTag) -> Result<L::TokenTag, WalkError> {
+        match self.pop_front() {
+            Some(CSTreeOwned::Leaf { tag, .. }) if &tag == expected => Ok(tag),
+            found => Err(self.error(expected.as_name(), found.as_ref())),
+        }
+    }
+
+    pub fn try_expect_node<T>(&mut self) -> Result<T, T::Error>
+    where
+        T: TryFrom<CSTreeOwnedWalker<L>>,
+        T::Error: From<WalkError>,
     {
         match self.pop_spawn() {
-            Some(mut node_walker) => node_walker.expect_nodes_lrec::<T>(),
-            None => vec![],
+            Some(node_walker) => T::try_from(node_walker),
+            None => Err(self.error("an element", None).into()),
         }
     }
 
-    fn expect_nodes_lrec<T>(&mut self) -> Vec<T>
+    pub fn try_expect_node_of<T>(&mut self, expected: &L::RuleTag) -> Result<T, T::Error>
+    where
+        T: TryFrom<CSTreeOwnedWalker<L>>,
+        T::Error: From<WalkError>,
+    {
+        match self.pop_front() {
+            Some(tree) if matches!(&tree, CSTreeOwned::Node { tag, .. } if tag == expected) => {
+                T::try_from(CSTreeOwnedWalker::new(tree))
+            }
+            found => Err(self.error(&rule_name::<L>(expected), found.as_ref()).into()),
+        }
+    }
+
+    pub fn try_expect_labeled_leaf(&mut self, label: &str) -> Result<L::TokenTag, WalkError> {
+        match self.remove_labeled(label) {
+            Some(CSTreeOwned::Leaf { tag, .. }) => Ok(tag),
+            found => Err(self.error(&format!("a token labeled {:?}", label), found.as_ref())),
+        }
+    }
+
+    pub fn try_expect_labeled<T>(&mut self, label: &str) -> Result<T, T::Error>
+    where
+        T: TryFrom<CSTreeOwnedWalker<L>>,
+        T::Error: From<WalkError>,
+    {
+        match self.remove_labeled(label) {
+            Some(tree) => T::try_from(CSTreeOwnedWalker::new(tree)),
+            None => Err(self.error(&format!("an element labeled {:?}", label), None).into()),
+        }
+    }
+
+    pub fn try_expect_nodes<T>(&mut self) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeOwnedWalker<L>>,
+        T::Error: From<WalkError>,
+    {
+        self.try_expect_list_by(None, None)
+    }
+
+    pub fn try_expect_list<T>(&mut self, list: &L::RuleTag) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeOwnedWalker<L>>,
+        T::Error: From<WalkError>,
+    {
+        self.try_expect_list_by(Some(list), None)
+    }
+
+    pub fn try_expect_separated<T>(&mut self, list: &L::RuleTag, sep: &L::TokenTag) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeOwnedWalker<L>>,
+        T::Error: From<WalkError>,
+    {
+        self.try_expect_list_by(Some(list), Some(sep))
+    }
+
+    fn expect_list_by<T>(&mut self, list: Option<&L::RuleTag>, sep: Option<&L::TokenTag>) -> Vec<T>
     where
         T: From<CSTreeOwnedWalker<L>>,
     {
-        match (self.pop_spawn(), self.pop_spawn()) {
-            (Some(mut lrec_walker), Some(last_walker)) => {
-                let mut lrec_elems = lrec_walker.expect_nodes_lrec::<T>();
-                let last_elem = T::from(last_walker);
-                lrec_elems.push(last_elem);
-                lrec_elems
+        match self.pop_list(list, sep) {
+            Ok(elems) => elems.into_iter().map(|elem| T::from(CSTreeOwnedWalker::new(elem))).collect(),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn try_expect_list_by<T>(&mut self, list: Option<&L::RuleTag>, sep: Option<&L::TokenTag>) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeOwnedWalker<L>>,
+        T::Error: From<WalkError>,
+    {
+        self.pop_list(list, sep)?
+            .into_iter()
+            .map(|elem| T::try_from(CSTreeOwnedWalker::new(elem)))
+            .collect()
+    }
+
+    // 先頭の子をリストの節として取り出し，その要素を出現順に並べる
+    fn pop_list(&mut self, list: Option<&L::RuleTag>, sep: Option<&L::TokenTag>) -> Result<Vec<CSTreeOwned<L>>, WalkError> {
+        let list_tree = match self.pop_front() {
+            Some(tree) if matches!(&tree, CSTreeOwned::Node { tag, .. } if list.is_none_or(|list| tag == list)) => tree,
+            None => return Ok(vec![]),
+            found => {
+                let expected = list.map_or("a list".to_string(), rule_name::<L>);
+                return Err(self.error(&expected, found.as_ref()));
             }
-            (Some(last_walker), None) => {
-                vec![T::from(last_walker)]
+        };
+
+        let mut elems = vec![];
+        flatten_list(list_tree, sep, &mut elems);
+        Ok(elems)
+    }
+
+    fn error(&self, expected: &str, found: Option<&CSTreeOwned<L>>) -> WalkError {
+        WalkError {
+            expected: expected.to_string(),
+            found: found.map_or("nothing".to_string(), |tree| tree.describe()),
+            span: found.and_then(|tree| tree.span()).or(self.span),
+        }
+    }
+
+    fn new(cst: CSTreeOwned<L>) -> Self {
+        let labels = cst.child_labels().into();
+        let span = cst.span();
+        CSTreeOwnedWalker { cst: Some(cst), labels, span }
+    }
+
+    fn remove_labeled(&mut self, label: &str) -> Option<CSTreeOwned<L>> {
+        let pos = self.labels
+            .iter()
+            .position(|l| l.as_deref() == Some(label))?;
+        match &mut self.cst {
+            Some(CSTreeOwned::Node { children, .. }) => {
+                self.labels.remove(pos);
+                children.remove(pos)
             }
-            (None, None) => vec![],
-            _ => unreachable!(),
+            _ => None,
         }
     }
 
     fn pop_front(&mut self) -> Option<CSTreeOwned<L>> {
         match &mut self.cst {
-            Some(CSTreeOwned::Node { children, .. }) => children.pop_front(),
+            Some(CSTreeOwned::Node { children, .. }) => {
+                self.labels.pop_front();
+                children.pop_front()
+            }
             Some(CSTreeOwned::Leaf { .. }) => self.cst.take(),
             None => None,
         }
     }
 
     fn pop_spawn(&mut self) -> Option<CSTreeOwnedWalker<L>> {
-        match self.pop_front() {
-            Some(tree) => {
-                Some(CSTreeOwnedWalker { cst: Some(tree) })
+        self.pop_front().map(CSTreeOwnedWalker::new)
+    }
+}
+
+// リストの節を展開し，要素を出現順に集める
+// (同じ規則の子を再帰的に展開するため，左再帰と右再帰のどちらも扱える．空の規則による節は要素を持たない)
+fn flatten_list<L: Lang>(list: CSTreeOwned<L>, sep: Option<&L::TokenTag>, elems: &mut Vec<CSTreeOwned<L>>) {
+    if let CSTreeOwned::Node { tag, children, .. } = list {
+        for child in children {
+            match &child {
+                CSTreeOwned::Node { tag: child_tag, .. } if child_tag == &tag => flatten_list(child, sep, elems),
+                CSTreeOwned::Leaf { tag: child_tag, .. } if Some(child_tag) == sep => {}
+                _ => elems.push(child),
             }
-            None => None,
         }
     }
 }
@@ -1,5 +1,10 @@
 mod data;
+mod from_cst;
+mod query;
 mod walker;
 
-pub use data::CSTree;
+pub use data::{CSTree, LeafTrivia};
+pub use from_cst::{FromCst, CstError};
+pub use query::{Query, QueryMatch, Capture, QueryError};
 pub use walker::CSTreeWalker;
+pub use copager_ir_derive::FromCst;
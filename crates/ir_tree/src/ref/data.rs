@@ -6,31 +6,161 @@ use serde::{Serialize, Deserialize};
 use copager_lang::token::{Token, TokenTag};
 use copager_lang::Lang;
 use copager_ir::{IR, IRBuilder, RawIR};
+use copager_ir::visit::Tree;
+
+use crate::label::resolve_labels;
 
 #[derive(Debug, Serialize, Deserialize, IR, IRBuilder)]
 pub enum CSTree<'input, L: Lang> {
+    // 今後フィールドが追加されうるため，パターンでは `..` を用いる (コメントは leading_comments などから得る)
+    #[non_exhaustive]
     Leaf {
         tag: L::TokenTag,
         text: &'input str,
+        #[serde(default)]
+        span: (usize, usize),
+        #[serde(default, borrow)]
+        leading_comments: Vec<&'input str>,
+        #[serde(default, borrow)]
+        trailing_comments: Vec<&'input str>,
+        #[serde(default, borrow)]
+        trivia: Vec<LeafTrivia<'input, L::TokenTag>>,
     },
     Node {
         tag: L::RuleTag,
+        #[serde(default)]
+        alt: Option<usize>,  // 還元に用いた規則の tag.as_rules() における位置 (ラベルの解決に用いる)
         children: VecDeque<CSTree<'input, L>>,
     },
 }
 
+// 葉に含まれる Trivia (空白とコメント)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeafTrivia<'input, T> {
+    pub kind: T,
+    pub span: (usize, usize),
+    pub text: &'input str,
+}
+
 impl<'input, L: Lang> From<RawIR<'input, L>> for CSTree<'input, L> {
     fn from(raw: RawIR<'input, L>) -> Self {
         match raw {
             RawIR::Atom(token) => {
                 let text = token.as_str();
+                let leading_comments = token.leading_trivia()
+                    .filter(|trivia| trivia.is_comment())
+                    .map(|trivia| token.trivia_slice(trivia))
+                    .collect();
+                let trailing_comments = token.trailing_trivia()
+                    .filter(|trivia| trivia.is_comment())
+                    .map(|trivia| token.trivia_slice(trivia))
+                    .collect();
+                let trivia = token.trivia
+                    .iter()
+                    .map(|trivia| LeafTrivia {
+                        kind: trivia.kind.clone(),
+                        span: trivia.span,
+                        text: token.trivia_slice(trivia),
+                    })
+                    .collect();
+                let span = token.body;
                 let tag = token.kind;
-                CSTree::Leaf { tag, text }
+                CSTree::Leaf { tag, text, span, leading_comments, trailing_comments, trivia }
             },
-            RawIR::List { rule: tag, elems } => {
+            RawIR::List { rule: tag, alt, elems } => {
                 let children = elems.into_iter().map(CSTree::from).collect();
-                CSTree::Node { tag, children }
+                CSTree::Node { tag, alt, children }
             }
         }
     }
 }
+
+impl<'input, L: Lang> CSTree<'input, L> {
+    // ラベル (規則中の <lhs:expr> の lhs) が付けられた子
+    pub fn child_by_label(&self, label: &str) -> Option<&CSTree<'input, L>> {
+        let pos = self.child_labels()
+            .iter()
+            .position(|l| l.as_deref() == Some(label))?;
+        match self {
+            CSTree::Node { children, .. } => children.get(pos),
+            CSTree::Leaf { .. } => None,
+        }
+    }
+
+    // 各子に付けられたラベル (葉の場合は空)
+    pub(crate) fn child_labels(&self) -> Vec<Option<String>> {
+        match self {
+            CSTree::Node { tag, alt, children } => resolve_labels::<L>(tag, *alt, children.len()),
+            CSTree::Leaf { .. } => vec![],
+        }
+    }
+
+    // 入力における位置 (節の場合は先頭の葉から末尾の葉まで，葉を持たない節の場合は None)
+    pub fn span(&self) -> Option<(usize, usize)> {
+        match self {
+            CSTree::Leaf { span, .. } => Some(*span),
+            CSTree::Node { children, .. } => {
+                let begin = children.iter().find_map(|child| child.span())?;
+                let end = children.iter().rev().find_map(|child| child.span())?;
+                Some((begin.0, end.1))
+            }
+        }
+    }
+
+    // 葉の直前にあるコメント (節の場合は空)
+    pub fn leading_comments(&self) -> &[&'input str] {
+        match self {
+            CSTree::Leaf { leading_comments, .. } => leading_comments,
+            CSTree::Node { .. } => &[],
+        }
+    }
+
+    // 葉の直後にあるコメント (post_trivia として読み飛ばされたもののみ)
+    pub fn trailing_comments(&self) -> &[&'input str] {
+        match self {
+            CSTree::Leaf { trailing_comments, .. } => trailing_comments,
+            CSTree::Node { .. } => &[],
+        }
+    }
+
+    // 葉に含まれる Trivia (入力における位置の順，節の場合は空)
+    pub fn trivia(&self) -> &[LeafTrivia<'input, L::TokenTag>] {
+        match self {
+            CSTree::Leaf { trivia, .. } => trivia,
+            CSTree::Node { .. } => &[],
+        }
+    }
+}
+
+impl<L: Lang> Tree for CSTree<'_, L> {
+    type Rule = L::RuleTag;
+    type Token = L::TokenTag;
+
+    fn rule(&self) -> Option<&Self::Rule> {
+        match self {
+            CSTree::Node { tag: rule, .. } => Some(rule),
+            _ => None,
+        }
+    }
+
+    fn token(&self) -> Option<&Self::Token> {
+        match self {
+            CSTree::Leaf { tag, .. } => Some(tag),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> Option<&VecDeque<Self>> {
+        match self {
+            CSTree::Node { children, .. } => Some(children),
+            _ => None,
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut VecDeque<Self>> {
+        match self {
+            CSTree::Node { children, .. } => Some(children),
+            _ => None,
+        }
+    }
+}
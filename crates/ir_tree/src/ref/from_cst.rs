@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use copager_lang::rule::{RuleElem, RuleTag};
+use copager_lang::token::TokenTag;
+use copager_lang::Lang;
+
+use crate::error::rule_name;
+
+use super::CSTree;
+
+#[derive(Debug, Error)]
+pub enum CstError {
+    #[error("Expected {expected} but found {found}")]
+    Unexpected {
+        expected: String,
+        found: String,
+        span: Option<(usize, usize)>,
+    },
+    #[error("Cannot convert {text:?}: {message}")]
+    Convert {
+        text: String,
+        message: String,
+        span: (usize, usize),
+    },
+}
+
+impl CstError {
+    pub fn span(&self) -> Option<(usize, usize)> {
+        match self {
+            CstError::Unexpected { span, .. } => *span,
+            CstError::Convert { span, .. } => Some(*span),
+        }
+    }
+}
+
+// CSTree から型付きの AST を構築する (#[derive(FromCst)] により実装できる)
+pub trait FromCst<'input, L: Lang>
+where
+    Self: Sized,
+{
+    // cst の形が変換先と一致するか (列挙子の選択に用いる，子までは検査しない)
+    fn accepts(cst: &CSTree<'input, L>) -> bool;
+    fn from_cst(cst: &CSTree<'input, L>) -> Result<Self, CstError>;
+}
+
+impl<'input, L: Lang> FromCst<'input, L> for &'input str {
+    fn accepts(cst: &CSTree<'input, L>) -> bool {
+        matches!(cst, CSTree::Leaf { .. })
+    }
+
+    fn from_cst(cst: &CSTree<'input, L>) -> Result<Self, CstError> {
+        cst.expect_leaf()
+    }
+}
+
+impl<'input, L: Lang> FromCst<'input, L> for String {
+    fn accepts(cst: &CSTree<'input, L>) -> bool {
+        matches!(cst, CSTree::Leaf { .. })
+    }
+
+    fn from_cst(cst: &CSTree<'input, L>) -> Result<Self, CstError> {
+        cst.expect_leaf().map(str::to_string)
+    }
+}
+
+impl<'input, L, T> FromCst<'input, L> for Box<T>
+where
+    L: Lang,
+    T: FromCst<'input, L>,
+{
+    fn accepts(cst: &CSTree<'input, L>) -> bool {
+        T::accepts(cst)
+    }
+
+    fn from_cst(cst: &CSTree<'input, L>) -> Result<Self, CstError> {
+        T::from_cst(cst).map(Box::new)
+    }
+}
+
+// 空になりうる規則 (子を持たない節は None，子を 1 つだけ持つ節はその子を変換する)
+// (空の規則を持たない節は T として変換する)
+impl<'input, L, T> FromCst<'input, L> for Option<T>
+where
+    L: Lang,
+    T: FromCst<'input, L>,
+{
+    fn accepts(cst: &CSTree<'input, L>) -> bool {
+        match cst {
+            CSTree::Node { children, .. } if is_nullable(cst) && children.is_empty() => true,
+            CSTree::Node { children, .. } if is_nullable(cst) && children.len() == 1 && !T::accepts(cst) => {
+                T::accepts(&children[0])
+            }
+            _ => T::accepts(cst),
+        }
+    }
+
+    fn from_cst(cst: &CSTree<'input, L>) -> Result<Self, CstError> {
+        match cst {
+            CSTree::Node { children, .. } if is_nullable(cst) && children.is_empty() => Ok(None),
+            CSTree::Node { children, .. } if is_nullable(cst) && children.len() == 1 && !T::accepts(cst) => {
+                T::from_cst(&children[0]).map(Some)
+            }
+            _ => T::from_cst(cst).map(Some),
+        }
+    }
+}
+
+// 左再帰の規則によるリスト (<list> ::= <list> <item> | <item> | ε)
+impl<'input, L, T> FromCst<'input, L> for Vec<T>
+where
+    L: Lang,
+    T: FromCst<'input, L>,
+{
+    fn accepts(cst: &CSTree<'input, L>) -> bool {
+        match cst {
+            CSTree::Node { tag, children, .. } if is_left_recursive(cst) => match children.len() {
+                0 => true,
+                1 => T::accepts(&children[0]),
+                2 => children[0].is_node_of(tag) && T::accepts(&children[1]),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn from_cst(cst: &CSTree<'input, L>) -> Result<Self, CstError> {
+        if !is_left_recursive(cst) {
+            return Err(cst.unexpected("list"));
+        }
+
+        let mut elems = vec![];
+        let mut cur = cst;
+        loop {
+            match cur {
+                CSTree::Node { tag, children, .. } if children.len() == 2 && children[0].is_node_of(tag) => {
+                    elems.push(T::from_cst(&children[1])?);
+                    cur = &children[0];
+                }
+                CSTree::Node { children, .. } if children.len() == 1 => {
+                    elems.push(T::from_cst(&children[0])?);
+                    break;
+                }
+                CSTree::Node { children, .. } if children.is_empty() => break,
+                _ => return Err(cur.unexpected("list")),
+            }
+        }
+        elems.reverse();
+        Ok(elems)
+    }
+}
+
+// 節の規則が左再帰の規則 (<list> ::= <list> <item>) を含むか
+fn is_left_recursive<L: Lang>(cst: &CSTree<'_, L>) -> bool {
+    match cst {
+        CSTree::Node { tag, .. } => tag
+            .as_rules()
+            .iter()
+            .any(|rule| rule.rhs.len() == 2 && rule.rhs[0] == rule.lhs),
+        CSTree::Leaf { .. } => false,
+    }
+}
+
+// 節の規則が空の規則 (<opt> ::= ) を含むか
+fn is_nullable<L: Lang>(cst: &CSTree<'_, L>) -> bool {
+    match cst {
+        CSTree::Node { tag, .. } => tag
+            .as_rules()
+            .iter()
+            .any(|rule| rule.rhs.iter().all(|elem| *elem == RuleElem::Epsilon)),
+        CSTree::Leaf { .. } => false,
+    }
+}
+
+// 以下は #[derive(FromCst)] が生成するコードから用いられる
+impl<'input, L: Lang> CSTree<'input, L> {
+    pub fn is_node_of(&self, rule: &L::RuleTag) -> bool {
+        matches!(self, CSTree::Node { tag, .. } if tag == rule)
+    }
+
+    pub fn is_leaf_of(&self, token: &L::TokenTag) -> bool {
+        matches!(self, CSTree::Leaf { tag, .. } if tag == token)
+    }
+
+    pub fn expect_children(&self, rule: &L::RuleTag) -> Result<&VecDeque<CSTree<'input, L>>, CstError> {
+        match self {
+            CSTree::Node { tag, children, .. } if tag == rule => Ok(children),
+            _ => Err(self.unexpected(&rule_name::<L>(rule))),
+        }
+    }
+
+    pub fn expect_leaf(&self) -> Result<&'input str, CstError> {
+        match self {
+            CSTree::Leaf { text, .. } => Ok(text),
+            CSTree::Node { .. } => Err(self.unexpected("token")),
+        }
+    }
+
+    pub fn expect_leaf_of(&self, token: &L::TokenTag) -> Result<&'input str, CstError> {
+        match self {
+            CSTree::Leaf { text, .. } if self.is_leaf_of(token) => Ok(text),
+            _ => Err(self.unexpected(token.as_name())),
+        }
+    }
+
+    // #[cst(token = ..)] の照合に用いる (L::TokenTag の字句のうち列挙子名が name であるもの)
+    #[doc(hidden)]
+    pub fn __is_leaf_named(&self, name: &str) -> bool {
+        matches!(self, CSTree::Leaf { tag, .. } if tag.as_name() == name)
+    }
+
+    #[doc(hidden)]
+    pub fn __expect_leaf_named(&self, name: &str) -> Result<&'input str, CstError> {
+        match self {
+            CSTree::Leaf { text, .. } if self.__is_leaf_named(name) => Ok(text),
+            _ => Err(self.unexpected(name)),
+        }
+    }
+
+    pub fn convert<V, E, F>(&self, f: F) -> Result<V, CstError>
+    where
+        E: Display,
+        F: FnOnce(&'input str) -> Result<V, E>,
+    {
+        let text = self.expect_leaf()?;
+        f(text).map_err(|err| CstError::Convert {
+            text: text.to_string(),
+            message: err.to_string(),
+            span: self.span().unwrap_or_default(),
+        })
+    }
+
+    pub fn unexpected(&self, expected: &str) -> CstError {
+        CstError::Unexpected {
+            expected: expected.to_string(),
+            found: self.describe(),
+            span: self.span(),
+        }
+    }
+
+    // 葉は字句名，節は規則名 (<expr> など) で表す
+    pub fn describe(&self) -> String {
+        match self {
+            CSTree::Leaf { tag, .. } => tag.as_name().to_string(),
+            CSTree::Node { tag, .. } => rule_name::<L>(tag),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::VecDeque;
+
+use regex::Regex;
+use thiserror::Error;
+
+use copager_lang::rule::{RuleSet, RuleTag};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::Lang;
+use copager_ir::visit::Tree;
+
+use super::CSTree;
+
+#[derive(Debug, Error)]
+#[error("{message} (at {pos})")]
+pub struct QueryError {
+    pub message: String,
+    pub pos: usize,
+}
+
+// CSTree に対する S 式のクエリ (tree-sitter のクエリを参考にしている)
+//
+// (Rule 子 ...)    規則 Rule の節 (子は順序を保った部分列として照合する)
+// (Token)          字句 Token の葉
+// (_ 子 ...) / _   任意の節 / 任意の要素
+// "text"           テキストが一致する葉
+// [A B ...]        いずれかに一致する要素
+// 要素 @name       一致した要素を name として取り出す
+// (#eq? @name "text"), (#not-eq? ...), (#match? @name "regex"), (#not-match? ...)
+//                  取り出した葉のテキストに対する条件
+pub struct Query<L: Lang> {
+    patterns: Vec<QueryPattern<L>>,
+}
+
+pub struct QueryMatch<'a, 'input, L: Lang> {
+    pub pattern: usize,  // 一致したパターンの位置
+    pub captures: Vec<Capture<'a, 'input, L>>,
+}
+
+pub struct Capture<'a, 'input, L: Lang> {
+    pub name: String,
+    pub tree: &'a CSTree<'input, L>,
+    pub span: Option<(usize, usize)>,
+}
+
+impl<L: Lang> Clone for Capture<'_, '_, L> {
+    fn clone(&self) -> Self {
+        Capture {
+            name: self.name.clone(),
+            tree: self.tree,
+            span: self.span,
+        }
+    }
+}
+
+impl<'a, 'input, L: Lang> QueryMatch<'a, 'input, L> {
+    pub fn get(&self, name: &str) -> Option<&Capture<'a, 'input, L>> {
+        self.captures.iter().find(|capture| capture.name == name)
+    }
+}
+
+impl<L: Lang> Query<L> {
+    pub fn new(src: &str) -> Result<Self, QueryError> {
+        let patterns = QueryParser::<L>::new(src).parse()?;
+        Ok(Query { patterns })
+    }
+
+    // 一致した箇所を行きがけ順に返す (1 つの要素に対してパターンごとに最初の一致のみを返す)
+    pub fn matches<'a, 'input>(&self, cst: &'a CSTree<'input, L>) -> Vec<QueryMatch<'a, 'input, L>> {
+        let mut matches = vec![];
+        for tree in cst.pre_order() {
+            for (idx, pattern) in self.patterns.iter().enumerate() {
+                let mut found = None;
+                pattern.root.match_tree(tree, &mut vec![], &mut |captures| {
+                    if pattern.predicates.iter().all(|pred| pred.test(captures)) {
+                        found = Some(captures.clone());
+                        true
+                    } else {
+                        false
+                    }
+                });
+                if let Some(captures) = found {
+                    matches.push(QueryMatch { pattern: idx, captures });
+                }
+            }
+        }
+        matches
+    }
+
+    // 名前が name である全ての取り出し結果
+    pub fn captures<'a, 'input>(&self, cst: &'a CSTree<'input, L>, name: &str) -> Vec<Capture<'a, 'input, L>> {
+        self.matches(cst)
+            .into_iter()
+            .flat_map(|m| m.captures)
+            .filter(|capture| capture.name == name)
+            .collect()
+    }
+}
+
+struct QueryPattern<L: Lang> {
+    root: Pattern<L>,
+    predicates: Vec<Predicate>,
+}
+
+struct Pattern<L: Lang> {
+    kind: PatternKind<L>,
+    captures: Vec<String>,
+}
+
+enum PatternKind<L: Lang> {
+    Any,
+    AnyNode(Vec<Pattern<L>>),
+    Named {
+        rule: Option<L::RuleTag>,
+        token: Option<L::TokenTag>,
+        children: Vec<Pattern<L>>,
+    },
+    Text(String),
+    Alt(Vec<Pattern<L>>),
+}
+
+// 照合に成功した際に呼ばれる継続 (true を返すと照合を終える)
+type Cont<'k, 'a, 'input, L> = &'k mut dyn FnMut(&mut Vec<Capture<'a, 'input, L>>) -> bool;
+
+impl<L: Lang> Pattern<L> {
+    fn match_tree<'a, 'input>(
+        &self,
+        tree: &'a CSTree<'input, L>,
+        captures: &mut Vec<Capture<'a, 'input, L>>,
+        k: Cont<'_, 'a, 'input, L>,
+    ) -> bool {
+        let saved = captures.len();
+        for name in &self.captures {
+            captures.push(Capture { name: name.clone(), tree, span: tree.span() });
+        }
+
+        let matched = match (&self.kind, tree) {
+            (PatternKind::Any, _) => k(captures),
+            (PatternKind::AnyNode(patterns), CSTree::Node { children, .. }) => {
+                match_children(patterns, children, 0, captures, k)
+            }
+            (PatternKind::Named { rule: Some(rule), children: patterns, .. }, CSTree::Node { tag, children, .. })
+                if rule == tag =>
+            {
+                match_children(patterns, children, 0, captures, k)
+            }
+            (PatternKind::Named { token: Some(token), children, .. }, CSTree::Leaf { tag, .. })
+                if token == tag && children.is_empty() =>
+            {
+                k(captures)
+            }
+            (PatternKind::Text(expected), CSTree::Leaf { text, .. }) if expected == text => k(captures),
+            (PatternKind::Alt(alts), _) => alts.iter().any(|alt| alt.match_tree(tree, captures, k)),
+            _ => false,
+        };
+
+        if !matched {
+            captures.truncate(saved);
+        }
+        matched
+    }
+
+    fn capture_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        names.extend(self.captures.iter().map(String::as_str));
+        match &self.kind {
+            PatternKind::AnyNode(patterns)
+            | PatternKind::Named { children: patterns, .. }
+            | PatternKind::Alt(patterns) => {
+                patterns.iter().for_each(|pattern| pattern.capture_names(names));
+            }
+            PatternKind::Any | PatternKind::Text(_) => {}
+        }
+    }
+}
+
+// patterns を children[start..] の部分列に順に照合する
+fn match_children<'a, 'input, L: Lang>(
+    patterns: &[Pattern<L>],
+    children: &'a VecDeque<CSTree<'input, L>>,
+    start: usize,
+    captures: &mut Vec<Capture<'a, 'input, L>>,
+    k: Cont<'_, 'a, 'input, L>,
+) -> bool {
+    match patterns.split_first() {
+        None => k(captures),
+        Some((first, rest)) => (start..children.len()).any(|idx| {
+            first.match_tree(&children[idx], captures, &mut |captures| {
+                match_children(rest, children, idx + 1, captures, k)
+            })
+        }),
+    }
+}
+
+enum Predicate {
+    Eq { capture: String, text: String, negate: bool },
+    Match { capture: String, regex: Regex, negate: bool },
+}
+
+impl Predicate {
+    fn capture(&self) -> &str {
+        match self {
+            Predicate::Eq { capture, .. } | Predicate::Match { capture, .. } => capture,
+        }
+    }
+
+    // 対象の要素が全て条件を満たす葉であるか (取り出されていない場合は満たすものとする)
+    fn test<L: Lang>(&self, captures: &[Capture<'_, '_, L>]) -> bool {
+        captures
+            .iter()
+            .filter(|capture| capture.name == self.capture())
+            .all(|capture| match (self, capture.tree) {
+                (Predicate::Eq { text: expected, negate, .. }, CSTree::Leaf { text, .. }) => {
+                    (expected == text) != *negate
+                }
+                (Predicate::Match { regex, negate, .. }, CSTree::Leaf { text, .. }) => {
+                    regex.is_match(text) != *negate
+                }
+                _ => false,
+            })
+    }
+}
+
+struct QueryParser<'a, L: Lang> {
+    src: &'a str,
+    cursor: usize,
+    rules: Vec<L::RuleTag>,
+    tokens: Vec<L::TokenTag>,
+}
+
+impl<'a, L: Lang> QueryParser<'a, L> {
+    fn new(src: &'a str) -> Self {
+        let rules = L::RuleSet::instantiate().iter().collect();
+        let tokens = L::TokenSet::instantiate().iter().collect();
+        QueryParser { src, cursor: 0, rules, tokens }
+    }
+
+    // <query> ::= (<pattern> | <predicate>)*
+    fn parse(mut self) -> Result<Vec<QueryPattern<L>>, QueryError> {
+        let mut patterns: Vec<QueryPattern<L>> = vec![];
+        loop {
+            self.skip_spaces();
+            if self.rest().is_empty() {
+                break;
+            }
+            if self.rest().starts_with("(#") {
+                let pos = self.cursor;
+                let predicate = self.parse_predicate()?;
+                match patterns.last_mut() {
+                    Some(pattern) => self.push_predicate(pattern, predicate, pos)?,
+                    None => return Err(self.error_at("Predicate without a pattern", pos)),
+                }
+            } else {
+                let mut predicates = vec![];
+                let pos = self.cursor;
+                let root = self.parse_pattern(&mut predicates)?;
+                let mut pattern = QueryPattern { root, predicates: vec![] };
+                for predicate in predicates {
+                    self.push_predicate(&mut pattern, predicate, pos)?;
+                }
+                patterns.push(pattern);
+            }
+        }
+        Ok(patterns)
+    }
+
+    // <pattern> ::= ('_' | <string> | '[' <pattern>+ ']' | '(' <name> (<pattern> | <predicate>)* ')') ('@' <ident>)*
+    fn parse_pattern(&mut self, predicates: &mut Vec<Predicate>) -> Result<Pattern<L>, QueryError> {
+        self.skip_spaces();
+        let kind = match self.rest().chars().next() {
+            Some('"') => PatternKind::Text(self.parse_string()?),
+            Some('[') => {
+                self.consume("[")?;
+                let mut alts = vec![];
+                while !self.peek_is("]") && !self.rest().is_empty() {
+                    alts.push(self.parse_pattern(predicates)?);
+                }
+                self.consume("]")?;
+                if alts.is_empty() {
+                    return Err(self.error("Empty alternation"));
+                }
+                PatternKind::Alt(alts)
+            }
+            Some('(') => {
+                self.consume("(")?;
+                let pos = self.cursor;
+                let name = self.parse_ident()?;
+                let mut children = vec![];
+                loop {
+                    if self.peek_is(")") || self.rest().is_empty() {
+                        break;
+                    } else if self.peek_is("(#") {
+                        predicates.push(self.parse_predicate()?);
+                    } else {
+                        children.push(self.parse_pattern(predicates)?);
+                    }
+                }
+                self.consume(")")?;
+                self.resolve(name, children, pos)?
+            }
+            Some('_') if !self.rest()[1..].starts_with(|c: char| c.is_alphanumeric() || c == '_') => {
+                self.consume("_")?;
+                PatternKind::Any
+            }
+            _ => return Err(self.error("Expected a pattern")),
+        };
+
+        let mut captures = vec![];
+        while self.peek_is("@") {
+            self.consume("@")?;
+            captures.push(self.parse_ident()?.to_string());
+        }
+
+        Ok(Pattern { kind, captures })
+    }
+
+    // <predicate> ::= '(' '#' <ident> '@' <ident> <string> ')'
+    fn parse_predicate(&mut self) -> Result<Predicate, QueryError> {
+        self.consume("(")?;
+        self.consume("#")?;
+        let pos = self.cursor;
+        let end_idx = self.rest()
+            .find(|c: char| !c.is_alphanumeric() && !matches!(c, '_' | '-' | '?'))
+            .unwrap_or(self.rest().len());
+        let name = &self.src[self.cursor..self.cursor + end_idx];
+        self.cursor += end_idx;
+
+        self.consume("@")?;
+        let capture = self.parse_ident()?.to_string();
+        let arg = self.parse_string()?;
+        self.consume(")")?;
+
+        let (negate, name) = match name.strip_prefix("not-") {
+            Some(name) => (true, name),
+            None => (false, name),
+        };
+        match name {
+            "eq?" => Ok(Predicate::Eq { capture, text: arg, negate }),
+            "match?" => match Regex::new(&arg) {
+                Ok(regex) => Ok(Predicate::Match { capture, regex, negate }),
+                Err(err) => Err(self.error_at(&format!("Invalid regex: {}", err), pos)),
+            },
+            _ => Err(self.error_at(&format!("Unknown predicate #{}", name), pos)),
+        }
+    }
+
+    fn push_predicate(&self, pattern: &mut QueryPattern<L>, predicate: Predicate, pos: usize) -> Result<(), QueryError> {
+        let mut names = vec![];
+        pattern.root.capture_names(&mut names);
+        if !names.contains(&predicate.capture()) {
+            return Err(self.error_at(&format!("Undefined capture @{}", predicate.capture()), pos));
+        }
+        pattern.predicates.push(predicate);
+        Ok(())
+    }
+
+    // 名前を規則と字句から探す (同名の規則と字句がある場合はどちらにも一致する)
+    fn resolve(&self, name: &str, children: Vec<Pattern<L>>, pos: usize) -> Result<PatternKind<L>, QueryError> {
+        if name == "_" {
+            return Ok(PatternKind::AnyNode(children));
+        }
+
+        let rule = self.rules.iter().find(|rule| rule.as_name() == name).cloned();
+        let token = self.tokens.iter().find(|token| token.as_name() == name).cloned();
+        match (&rule, &token) {
+            (None, None) => Err(self.error_at(&format!("Unknown rule or token {}", name), pos)),
+            (None, Some(_)) if !children.is_empty() => {
+                Err(self.error_at(&format!("Token {} cannot have children", name), pos))
+            }
+            _ => Ok(PatternKind::Named { rule, token, children }),
+        }
+    }
+
+    // <string> ::= '"' ([^"\\] | '\\' .)* '"'
+    fn parse_string(&mut self) -> Result<String, QueryError> {
+        self.consume("\"")?;
+        let mut s = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.cursor += idx + 1;
+                    return Ok(s);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, c)) => s.push(c),
+                    None => break,
+                },
+                c => s.push(c),
+            }
+        }
+        Err(self.error("Unterminated string"))
+    }
+
+    // <ident> ::= [a-zA-Z0-9_]+
+    fn parse_ident(&mut self) -> Result<&'a str, QueryError> {
+        self.skip_spaces();
+        let end_idx = self.rest()
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(self.rest().len());
+        if end_idx == 0 {
+            return Err(self.error("Expected an identifier"));
+        }
+        let ident = &self.src[self.cursor..self.cursor + end_idx];
+        self.cursor += end_idx;
+        Ok(ident)
+    }
+
+    fn consume(&mut self, expected: &str) -> Result<(), QueryError> {
+        self.skip_spaces();
+        if self.rest().starts_with(expected) {
+            self.cursor += expected.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", expected)))
+        }
+    }
+
+    fn peek_is(&mut self, expected: &str) -> bool {
+        self.skip_spaces();
+        self.rest().starts_with(expected)
+    }
+
+    // 空白と ';' から行末までのコメントを読み飛ばす
+    fn skip_spaces(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.cursor += rest.len() - trimmed.len();
+            if trimmed.starts_with(';') {
+                self.cursor += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.cursor..]
+    }
+
+    fn error(&self, message: &str) -> QueryError {
+        self.error_at(message, self.cursor)
+    }
+
+    fn error_at(&self, message: &str, pos: usize) -> QueryError {
+        QueryError { message: message.to_string(), pos }
+    }
+}
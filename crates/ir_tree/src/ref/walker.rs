@@ -1,14 +1,21 @@
+use std::collections::VecDeque;
+
+use copager_lang::token::TokenTag;
 use copager_lang::Lang;
 
+use crate::error::{rule_name, WalkError};
+
 use super::CSTree;
 
 pub struct CSTreeWalker<'src, L: Lang> {
     cst: Option<CSTree<'src, L>>,
+    labels: VecDeque<Option<String>>,  // 残りの子に付けられたラベル
+    span: Option<(usize, usize)>,      // 要素が足りない場合のエラーに用いる
 }
 
 impl <'src, L: Lang> From<CSTree<'src, L>> for CSTreeWalker<'src, L> {
     fn from(cst: CSTree<'src, L>) -> Self {
-        CSTreeWalker { cst: Some(cst) }
+        CSTreeWalker::new(cst)
     }
 }
 
@@ -37,7 +44,7 @@ impl<'src, L: Lang> CSTreeWalker<'src, L> {
 
     pub fn expect_leaf(&mut self) -> (L::TokenTag, &'src str) {
         match self.pop_front() {
-            Some(CSTree::Leaf { tag, text }) => (tag, text),
+            Some(CSTree::Leaf { tag, text, .. }) => (tag, text),
             Some(..) => panic!("Expected a leaf but found a node"),
             None => panic!("No more elements in the CSTreeWalker"),
         }
@@ -53,49 +60,221 @@ impl<'src, L: Lang> CSTreeWalker<'src, L> {
         }
     }
 
+    // ラベルが付けられた子を取り出す (子の位置によらない)
+    pub fn expect_labeled_leaf(&mut self, label: &str) -> (L::TokenTag, &'src str) {
+        match self.remove_labeled(label) {
+            Some(CSTree::Leaf { tag, text, .. }) => (tag, text),
+            Some(..) => panic!("Expected a leaf labeled \"{}\" but found a node", label),
+            None => panic!("No element labeled \"{}\" in the CSTreeWalker", label),
+        }
+    }
+
+    pub fn expect_labeled<T>(&mut self, label: &str) -> T
+    where
+        T: From<CSTreeWalker<'src, L>>,
+    {
+        match self.remove_labeled(label) {
+            Some(tree) => T::from(CSTreeWalker::new(tree)),
+            None => panic!("No element labeled \"{}\" in the CSTreeWalker", label),
+        }
+    }
+
+    // 先頭の子をリストとして展開し，要素を出現順に変換する
+    // (リストの規則は先頭の子の規則とし，字句の子も要素として扱う)
     pub fn expect_nodes<T>(&mut self) -> Vec<T>
     where
         T: From<CSTreeWalker<'src, L>>,
+    {
+        self.expect_list_by(None, None)
+    }
+
+    // 規則 list によるリスト (左再帰・右再帰・空の規則のいずれの形でもよい)
+    pub fn expect_list<T>(&mut self, list: &L::RuleTag) -> Vec<T>
+    where
+        T: From<CSTreeWalker<'src, L>>,
+    {
+        self.expect_list_by(Some(list), None)
+    }
+
+    // 字句 sep で区切られたリスト (sep が ir_omit でない場合に用いる)
+    pub fn expect_separated<T>(&mut self, list: &L::RuleTag, sep: &L::TokenTag) -> Vec<T>
+    where
+        T: From<CSTreeWalker<'src, L>>,
+    {
+        self.expect_list_by(Some(list), Some(sep))
+    }
+
+    // 以下は木の形が期待と異なる場合に panic せず WalkError を返す
+    pub fn try_expect_leaf(&mut self) -> Result<(L::TokenTag, &'src str), WalkError> {
+        match self.pop_front() {
+            Some(CSTree::Leaf { tag, text, .. }) => Ok((tag, text)),
+            found => Err(self.error("a token", found.as_ref())),
+        }
+    }
+
+    pub fn try_expect_leaf_of(&mut self, expected: &L::TokenTag) -> Result<(L::TokenTag, &'src str), WalkError> {
+        match self.pop_front() {
+            Some(CSTree::Leaf { tag, text, .. }) if &tag == expected => Ok((tag, text)),
+            found => Err(self.error(expected.as_name(), found.as_ref())),
+        }
+    }
+
+    pub fn try_expect_node<T>(&mut self) -> Result<T, T::Error>
+    where
+        T: TryFrom<CSTreeWalker<'src, L>>,
+        T::Error: From<WalkError>,
     {
         match self.pop_spawn() {
-            Some(mut node_walker) => node_walker.expect_nodes_lrec::<T>(),
-            None => vec![],
+            Some(node_walker) => T::try_from(node_walker),
+            None => Err(self.error("an element", None).into()),
+        }
+    }
+
+    pub fn try_expect_node_of<T>(&mut self, expected: &L::RuleTag) -> Result<T, T::Error>
+    where
+        T: TryFrom<CSTreeWalker<'src, L>>,
+        T::Error: From<WalkError>,
+    {
+        match self.pop_front() {
+            Some(tree) if matches!(&tree, CSTree::Node { tag, .. } if tag == expected) => {
+                T::try_from(CSTreeWalker::new(tree))
+            }
+            found => Err(self.error(&rule_name::<L>(expected), found.as_ref()).into()),
+        }
+    }
+
+    pub fn try_expect_labeled_leaf(&mut self, label: &str) -> Result<(L::TokenTag, &'src str), WalkError> {
+        match self.remove_labeled(label) {
+            Some(CSTree::Leaf { tag, text, .. }) => Ok((tag, text)),
+            found => Err(self.error(&format!("a token labeled {:?}", label), found.as_ref())),
+        }
+    }
+
+    pub fn try_expect_labeled<T>(&mut self, label: &str) -> Result<T, T::Error>
+    where
+        T: TryFrom<CSTreeWalker<'src, L>>,
+        T::Error: From<WalkError>,
+    {
+        match self.remove_labeled(label) {
+            Some(tree) => T::try_from(CSTreeWalker::new(tree)),
+            None => Err(self.error(&format!("an element labeled {:?}", label), None).into()),
         }
     }
 
-    fn expect_nodes_lrec<T>(&mut self) -> Vec<T>
+    pub fn try_expect_nodes<T>(&mut self) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeWalker<'src, L>>,
+        T::Error: From<WalkError>,
+    {
+        self.try_expect_list_by(None, None)
+    }
+
+    pub fn try_expect_list<T>(&mut self, list: &L::RuleTag) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeWalker<'src, L>>,
+        T::Error: From<WalkError>,
+    {
+        self.try_expect_list_by(Some(list), None)
+    }
+
+    pub fn try_expect_separated<T>(&mut self, list: &L::RuleTag, sep: &L::TokenTag) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeWalker<'src, L>>,
+        T::Error: From<WalkError>,
+    {
+        self.try_expect_list_by(Some(list), Some(sep))
+    }
+
+    fn expect_list_by<T>(&mut self, list: Option<&L::RuleTag>, sep: Option<&L::TokenTag>) -> Vec<T>
     where
         T: From<CSTreeWalker<'src, L>>,
     {
-        match (self.pop_spawn(), self.pop_spawn()) {
-            (Some(mut lrec_walker), Some(last_walker)) => {
-                let mut lrec_elems = lrec_walker.expect_nodes_lrec::<T>();
-                let last_elem = T::from(last_walker);
-                lrec_elems.push(last_elem);
-                lrec_elems
+        match self.pop_list(list, sep) {
+            Ok(elems) => elems.into_iter().map(|elem| T::from(CSTreeWalker::new(elem))).collect(),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn try_expect_list_by<T>(&mut self, list: Option<&L::RuleTag>, sep: Option<&L::TokenTag>) -> Result<Vec<T>, T::Error>
+    where
+        T: TryFrom<CSTreeWalker<'src, L>>,
+        T::Error: From<WalkError>,
+    {
+        self.pop_list(list, sep)?
+            .into_iter()
+            .map(|elem| T::try_from(CSTreeWalker::new(elem)))
+            .collect()
+    }
+
+    // 先頭の子をリストの節として取り出し，その要素を出現順に並べる
+    fn pop_list(&mut self, list: Option<&L::RuleTag>, sep: Option<&L::TokenTag>) -> Result<Vec<CSTree<'src, L>>, WalkError> {
+        let list_tree = match self.pop_front() {
+            Some(tree) if matches!(&tree, CSTree::Node { tag, .. } if list.is_none_or(|list| tag == list)) => tree,
+            None => return Ok(vec![]),
+            found => {
+                let expected = list.map_or("a list".to_string(), rule_name::<L>);
+                return Err(self.error(&expected, found.as_ref()));
             }
-            (Some(last_walker), None) => {
-                vec![T::from(last_walker)]
+        };
+
+        let mut elems = vec![];
+        flatten_list(list_tree, sep, &mut elems);
+        Ok(elems)
+    }
+
+    fn error(&self, expected: &str, found: Option<&CSTree<'src, L>>) -> WalkError {
+        WalkError {
+            expected: expected.to_string(),
+            found: found.map_or("nothing".to_string(), |tree| tree.describe()),
+            span: found.and_then(|tree| tree.span()).or(self.span),
+        }
+    }
+
+    fn new(cst: CSTree<'src, L>) -> Self {
+        let labels = cst.child_labels().into();
+        let span = cst.span();
+        CSTreeWalker { cst: Some(cst), labels, span }
+    }
+
+    fn remove_labeled(&mut self, label: &str) -> Option<CSTree<'src, L>> {
+        let pos = self.labels
+            .iter()
+            .position(|l| l.as_deref() == Some(label))?;
+        match &mut self.cst {
+            Some(CSTree::Node { children, .. }) => {
+                self.labels.remove(pos);
+                children.remove(pos)
             }
-            (None, None) => vec![],
-            _ => unreachable!(),
+            _ => None,
         }
     }
 
     fn pop_front(&mut self) -> Option<CSTree<'src, L>> {
         match &mut self.cst {
-            Some(CSTree::Node { children, .. }) => children.pop_front(),
+            Some(CSTree::Node { children, .. }) => {
+                self.labels.pop_front();
+                children.pop_front()
+            }
             Some(CSTree::Leaf { .. }) => self.cst.take(),
             None => None,
         }
     }
 
     fn pop_spawn(&mut self) -> Option<CSTreeWalker<'src, L>> {
-        match self.pop_front() {
-            Some(tree) => {
-                Some(CSTreeWalker { cst: Some(tree) })
+        self.pop_front().map(CSTreeWalker::new)
+    }
+}
+
+// リストの節を展開し，要素を出現順に集める
+// (同じ規則の子を再帰的に展開するため，左再帰と右再帰のどちらも扱える．空の規則による節は要素を持たない)
+fn flatten_list<'src, L: Lang>(list: CSTree<'src, L>, sep: Option<&L::TokenTag>, elems: &mut Vec<CSTree<'src, L>>) {
+    if let CSTree::Node { tag, children, .. } = list {
+        for child in children {
+            match &child {
+                CSTree::Node { tag: child_tag, .. } if child_tag == &tag => flatten_list(child, sep, elems),
+                CSTree::Leaf { tag: child_tag, .. } if Some(child_tag) == sep => {}
+                _ => elems.push(child),
             }
-            None => None,
         }
     }
 }
@@ -0,0 +1,83 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_tree::r#ref::CSTree;
+use copager_ir_tree::owned::CSTreeOwned;
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[ \t\n]+", pre_trivia)]
+    _Whitespace,
+    #[token(r"//[^\n]*", pre_trivia, comment)]
+    _LineComment,
+    #[token(r"[ \t]*#[^\n]*", post_trivia, comment)]
+    _TrailingComment,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= <expr> Plus Num")]
+    #[rule("<expr> ::= Num")]
+    Expr,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+const TEST_INPUT: &str = "// one\n// uno\n1\n+ 2 # two\n";
+
+#[test]
+fn leaf_comments() -> anyhow::Result<()> {
+    let cst = TestProcessor::new()
+        .build()?
+        .process::<CSTree<_>>(TEST_INPUT)?;
+
+    let leaves = collect_leaves(&cst);
+    assert_eq!(leaves.len(), 3);
+    assert_eq!(leaves[0].leading_comments(), &["// one", "// uno"]);
+    assert!(leaves[0].trailing_comments().is_empty());
+    assert!(leaves[1].leading_comments().is_empty());
+    assert_eq!(leaves[2].trailing_comments(), &[" # two"]);
+
+    Ok(())
+}
+
+#[test]
+fn leaf_comments_owned() -> anyhow::Result<()> {
+    let cst = TestProcessor::new()
+        .build()?
+        .process::<CSTreeOwned<_>>(TEST_INPUT)?;
+
+    let CSTreeOwned::Node { children, .. } = &cst else {
+        panic!("Expected a node");
+    };
+    let CSTreeOwned::Node { children: first, .. } = &children[0] else {
+        panic!("Expected a node");
+    };
+    assert_eq!(first[0].leading_comments(), &["// one".to_string(), "// uno".to_string()]);
+    assert_eq!(children[2].trailing_comments(), &[" # two".to_string()]);
+
+    Ok(())
+}
+
+fn collect_leaves<'a, 'input>(cst: &'a CSTree<'input, TestLang>) -> Vec<&'a CSTree<'input, TestLang>> {
+    match cst {
+        CSTree::Leaf { .. } => vec![cst],
+        CSTree::Node { children, .. } => children.iter().flat_map(collect_leaves).collect(),
+    }
+}
@@ -0,0 +1,149 @@
+use serde_json::{json, Value};
+
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_tree::r#ref::CSTree;
+use copager_ir_tree::owned::CSTreeOwned;
+use copager_ir_tree::Exporter;
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"<")]
+    Lt,
+    #[token(r"[a-z]+")]
+    Id,
+    #[token(r"[ \t\n]+", pre_trivia)]
+    _Whitespace,
+    #[token(r"//[^\n]*", pre_trivia, comment)]
+    _LineComment,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<cmp> ::= Id Lt <rhs>")]
+    Cmp,
+    #[rule("<rhs> ::= Id")]
+    #[rule("<rhs> ::= ")]
+    Rhs,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+const TEST_INPUT: &str = "// cmp\na < b";
+
+#[test]
+fn export_json() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+    let cst = processor.process::<CSTree<_>>(TEST_INPUT)?;
+
+    let exported = Exporter::new().source(TEST_INPUT).trivia(true).to_json(&cst);
+    let expected = json!({
+        "version": 1,
+        "root": {
+            "type": "node",
+            "kind": "Cmp",
+            "span": [7, 12],
+            "text": "a < b",
+            "children": [
+                {
+                    "type": "token",
+                    "kind": "Id",
+                    "span": [7, 8],
+                    "text": "a",
+                    "leading_trivia": [
+                        { "kind": "_LineComment", "span": [0, 6], "text": "// cmp" },
+                        { "kind": "_Whitespace", "span": [6, 7], "text": "\n" },
+                    ],
+                    "trailing_trivia": [],
+                },
+                {
+                    "type": "token",
+                    "kind": "Lt",
+                    "span": [9, 10],
+                    "text": "<",
+                    "leading_trivia": [{ "kind": "_Whitespace", "span": [8, 9], "text": " " }],
+                    "trailing_trivia": [],
+                },
+                {
+                    "type": "node",
+                    "kind": "Rhs",
+                    "span": [11, 12],
+                    "text": "b",
+                    "children": [
+                        {
+                            "type": "token",
+                            "kind": "Id",
+                            "span": [11, 12],
+                            "text": "b",
+                            "leading_trivia": [{ "kind": "_Whitespace", "span": [10, 11], "text": " " }],
+                            "trailing_trivia": [],
+                        },
+                    ],
+                },
+            ],
+        },
+    });
+    assert_eq!(serde_json::from_str::<Value>(&exported)?, expected);
+
+    // 入力を与えない場合は節の text を持たず，trivia も出力しない
+    let cst = processor.process::<CSTreeOwned<_>>("a <")?;
+    let exported = serde_json::from_str::<Value>(&Exporter::new().to_json(&cst))?;
+    assert_eq!(exported["root"]["text"], Value::Null);
+    assert_eq!(exported["root"]["children"][0].get("leading_trivia"), None);
+    assert_eq!(exported["root"]["children"][2], json!({
+        "type": "node",
+        "kind": "Rhs",
+        "span": null,
+        "text": null,
+        "children": [],
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn export_xml() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTree<_>>(TEST_INPUT)?;
+    let exported = Exporter::new().source(TEST_INPUT).trivia(true).to_xml(&cst);
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tree version="1">
+  <node kind="Cmp" begin="7" end="12" text="a &lt; b">
+    <token kind="Id" begin="7" end="8" text="a">
+      <trivia position="leading" kind="_LineComment" begin="0" end="6" text="// cmp"/>
+      <trivia position="leading" kind="_Whitespace" begin="6" end="7" text="&#10;"/>
+    </token>
+    <token kind="Lt" begin="9" end="10" text="&lt;">
+      <trivia position="leading" kind="_Whitespace" begin="8" end="9" text=" "/>
+    </token>
+    <node kind="Rhs" begin="11" end="12" text="b">
+      <token kind="Id" begin="11" end="12" text="b">
+        <trivia position="leading" kind="_Whitespace" begin="10" end="11" text=" "/>
+      </token>
+    </node>
+  </node>
+</tree>
+"#;
+    assert_eq!(exported, expected);
+
+    let cst = processor.process::<CSTreeOwned<_>>("a <")?;
+    let exported = Exporter::new().to_xml(&cst);
+    assert!(exported.contains(r#"<token kind="Id" begin="0" end="1" text="a"/>"#));
+    assert!(exported.contains(r#"<node kind="Rhs"/>"#));
+
+    Ok(())
+}
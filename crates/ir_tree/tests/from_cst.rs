@@ -0,0 +1,176 @@
+use std::num::ParseIntError;
+
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_tree::r#ref::{CSTree, CstError, FromCst};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"let", ir_omit)]
+    Let,
+    #[token(r"=", ir_omit)]
+    Eql,
+    #[token(r";", ir_omit)]
+    Semi,
+    #[token(r"\+", ir_omit)]
+    Plus,
+    #[token(r"\(", ir_omit)]
+    BracketL,
+    #[token(r"\)", ir_omit)]
+    BracketR,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(r"[a-z]+")]
+    Id,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<program> ::= <stmts>")]
+    Program,
+    #[rule("<stmts> ::= <stmts> <stmt>")]
+    #[rule("<stmts> ::= ")]
+    Stmts,
+    #[rule("<stmt> ::= Let Id <init> Semi")]
+    Stmt,
+    #[rule("<init> ::= Eql <expr>")]
+    #[rule("<init> ::= ")]
+    Init,
+    #[rule("<expr> ::= <expr> Plus <atom>")]
+    #[rule("<expr> ::= <atom>")]
+    Expr,
+    #[rule("<atom> ::= Num")]
+    #[rule("<atom> ::= Id")]
+    #[rule("<atom> ::= BracketL <expr> BracketR")]
+    Atom,
+}
+
+#[derive(Debug, PartialEq, FromCst)]
+#[cst(rule = TestRule::Program)]
+struct Program<'input> {
+    stmts: Vec<Stmt<'input>>,
+}
+
+#[derive(Debug, PartialEq, FromCst)]
+#[cst(rule = TestRule::Stmt)]
+struct Stmt<'input> {
+    #[cst(token = TestToken::Id)]
+    name: &'input str,
+    init: Option<Expr<'input>>,
+}
+
+#[derive(Debug, PartialEq, FromCst)]
+#[cst(rule = TestRule::Expr)]
+enum Expr<'input> {
+    Plus(Box<Expr<'input>>, Atom<'input>),
+    Atom(Atom<'input>),
+}
+
+#[derive(Debug, PartialEq, FromCst)]
+#[cst(rule = TestRule::Atom)]
+enum Atom<'input> {
+    Num(#[cst(token = TestToken::Num, with = parse_num)] u8),
+    Var(#[cst(token = TestToken::Id)] &'input str),
+    Paren(Box<Expr<'input>>),
+}
+
+fn parse_num(s: &str) -> Result<u8, ParseIntError> {
+    s.parse()
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+#[test]
+fn from_cst_success() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTree<_>>("let a = 1 + (2 + b); let c;")?;
+    let program = Program::from_cst(&cst)?;
+    assert_eq!(
+        program.stmts,
+        vec![
+            Stmt {
+                name: "a",
+                init: Some(Expr::Plus(
+                    Box::new(Expr::Atom(Atom::Num(1))),
+                    Atom::Paren(Box::new(Expr::Plus(
+                        Box::new(Expr::Atom(Atom::Num(2))),
+                        Atom::Var("b"),
+                    ))),
+                )),
+            },
+            Stmt { name: "c", init: None },
+        ],
+    );
+
+    let cst = processor.process::<CSTree<_>>("")?;
+    assert_eq!(Program::from_cst(&cst)?, Program { stmts: vec![] });
+
+    Ok(())
+}
+
+#[test]
+fn from_cst_failed() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    // 変換時のエラーは字句の位置を持つ
+    let cst = processor.process::<CSTree<_>>("let a = 1;\nlet b = 300;")?;
+    let err = Program::from_cst(&cst).unwrap_err();
+    assert!(matches!(&err, CstError::Convert { text, span: (19, 22), .. } if text == "300"));
+
+    // 規則が一致しない場合は期待した規則と実際の規則を返す
+    let err = Stmt::from_cst(&cst).unwrap_err();
+    match err {
+        CstError::Unexpected { expected, found, span } => {
+            assert_eq!(expected, "<stmt>");
+            assert_eq!(found, "<program>");
+            assert_eq!(span, Some((4, 22)));
+        }
+        err => panic!("Unexpected error: {}", err),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn from_cst_accepts() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTree<_>>("let a = 1;")?;
+    let stmts = child(&cst, 0);
+    let stmt = child(stmts, 1);
+    let init = child(stmt, 1);
+    let expr = child(init, 0);
+
+    // 子の数が一致していても，左再帰の規則でない節はリストとして受理しない
+    assert!(<Vec<Stmt> as FromCst<TestLang>>::accepts(stmts));
+    assert!(!<Vec<Atom> as FromCst<TestLang>>::accepts(stmt));
+
+    // 空の規則を持たない節は，子を 1 つだけ持つ場合も Option として受理しない
+    assert!(<Option<Expr> as FromCst<TestLang>>::accepts(init));
+    assert!(!<Option<Atom> as FromCst<TestLang>>::accepts(expr));
+
+    Ok(())
+}
+
+fn child<'a, 'input>(cst: &'a CSTree<'input, TestLang>, idx: usize) -> &'a CSTree<'input, TestLang> {
+    match cst {
+        CSTree::Node { children, .. } => &children[idx],
+        CSTree::Leaf { .. } => panic!("Unexpected leaf"),
+    }
+}
@@ -0,0 +1,219 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_tree::r#ref::{CSTree, CSTreeWalker, CstError, FromCst};
+use copager_ir_tree::owned::{CSTreeOwned, CSTreeOwnedWalker};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"=", ir_omit)]
+    Eql,
+    #[token(r"\+")]
+    Plus,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(r"[a-z]+")]
+    Id,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<assign> ::= name:Id Eql <value:expr>")]
+    Assign,
+    #[rule("<expr> ::= <lhs:expr> op:Plus <rhs:atom>")]
+    #[rule("<expr> ::= <atom>")]
+    Expr,
+    #[rule("<atom> ::= Num")]
+    Atom,
+}
+
+// ir_omit を除くと子の並びが一致する規則を持つ言語
+#[allow(dead_code)]
+#[derive(Lang)]
+struct PairLang (
+    #[tokenset] PairToken,
+    #[ruleset]  PairRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum PairToken {
+    #[token(r"=", ir_omit)]
+    Eql,
+    #[token(r"<-", ir_omit)]
+    Arrow,
+    #[token(r"[a-z]+")]
+    Id,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum PairRule {
+    #[tokenset(PairToken)]
+    #[rule("<pair> ::= key:Id Eql value:Id")]
+    #[rule("<pair> ::= value:Id Arrow key:Id")]
+    Pair,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+type PairProcessor = Processor<TestGenerator<PairLang>>;
+
+#[test]
+fn child_by_label() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    // ir_omit が指定された字句が子に含まれなくてもラベルで子を取り出せる
+    let cst = processor.process::<CSTree<_>>("x = 1 + 2")?;
+    let name = cst.child_by_label("name").unwrap();
+    assert!(matches!(name, CSTree::Leaf { text: "x", .. }));
+
+    let value = cst.child_by_label("value").unwrap();
+    assert!(matches!(value.child_by_label("op"), Some(CSTree::Leaf { text: "+", .. })));
+    assert_eq!(value.child_by_label("rhs").unwrap().span(), Some((8, 9)));
+    assert!(value.child_by_label("lhs").unwrap().child_by_label("lhs").is_none());
+    assert!(cst.child_by_label("undefined").is_none());
+
+    let cst = processor.process::<CSTreeOwned<_>>("x = 1 + 2")?;
+    let value = cst.child_by_label("value").unwrap();
+    assert!(matches!(value.child_by_label("op"), Some(CSTreeOwned::Leaf { text, .. }) if text == "+"));
+
+    Ok(())
+}
+
+#[test]
+fn child_by_label_reduced_rule() -> anyhow::Result<()> {
+    let processor = PairProcessor::new().build()?;
+
+    // 子の並びではなく還元に用いた規則からラベルを求める
+    for input in ["a = b", "b <- a"] {
+        let cst = processor.process::<CSTree<_>>(input)?;
+        assert!(matches!(cst.child_by_label("key"), Some(CSTree::Leaf { text: "a", .. })));
+        assert!(matches!(cst.child_by_label("value"), Some(CSTree::Leaf { text: "b", .. })));
+
+        let cst = processor.process::<CSTreeOwned<_>>(input)?;
+        assert!(matches!(cst.child_by_label("key"), Some(CSTreeOwned::Leaf { text, .. }) if text == "a"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn walker_expect_labeled() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq)]
+    struct Value(Vec<String>);
+
+    impl From<CSTreeWalker<'_, TestLang>> for Value {
+        fn from(mut walker: CSTreeWalker<'_, TestLang>) -> Self {
+            match walker.len() {
+                3 => {
+                    // ラベルを用いると子の順序によらず取り出せる
+                    let rhs = walker.expect_labeled::<Value>("rhs");
+                    let op = walker.expect_labeled_leaf("op").1;
+                    let mut lhs = walker.expect_labeled::<Value>("lhs");
+                    lhs.0.push(op.to_string());
+                    lhs.0.extend(rhs.0);
+                    lhs
+                }
+                _ => Value(vec![walker.expect_node::<Atom>().0]),
+            }
+        }
+    }
+
+    struct Atom(String);
+
+    impl From<CSTreeWalker<'_, TestLang>> for Atom {
+        fn from(mut walker: CSTreeWalker<'_, TestLang>) -> Self {
+            Atom(walker.expect_leaf().1.to_string())
+        }
+    }
+
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTree<_>>("x = 1 + 2 + 3")?;
+    let mut walker = CSTreeWalker::from(cst);
+    let value = walker.expect_labeled::<Value>("value");
+    assert_eq!(walker.expect_labeled_leaf("name").1, "x");
+    assert_eq!(value, Value(vec!["1", "+", "2", "+", "3"].into_iter().map(String::from).collect()));
+
+    let cst = processor.process::<CSTreeOwned<_>>("x = 1")?;
+    let mut walker = CSTreeOwnedWalker::from(cst);
+    assert!(walker.expect_labeled_leaf("name") == TestToken::Id);
+
+    Ok(())
+}
+
+#[test]
+fn from_cst_labeled() -> anyhow::Result<()> {
+    // フィールドの順序は子の順序と一致しなくてもよい
+    #[derive(Debug, PartialEq, FromCst)]
+    #[cst(rule = TestRule::Assign)]
+    struct Assign<'input> {
+        #[cst(label = "value")]
+        value: Expr<'input>,
+        #[cst(label = "name", token = TestToken::Id)]
+        name: &'input str,
+    }
+
+    #[derive(Debug, PartialEq, FromCst)]
+    #[cst(rule = TestRule::Expr)]
+    enum Expr<'input> {
+        Plus {
+            #[cst(label = "rhs")]
+            rhs: Atom<'input>,
+            #[cst(label = "lhs")]
+            lhs: Box<Expr<'input>>,
+        },
+        Atom(Atom<'input>),
+    }
+
+    #[derive(Debug, PartialEq, FromCst)]
+    #[cst(rule = TestRule::Atom)]
+    struct Atom<'input>(#[cst(token = TestToken::Num)] &'input str);
+
+    #[derive(Debug, FromCst)]
+    #[cst(rule = TestRule::Assign)]
+    struct Broken<'input> {
+        #[cst(label = "undefined")]
+        _value: Expr<'input>,
+    }
+
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTree<_>>("x = 1 + 2")?;
+    let assign = Assign::from_cst(&cst)?;
+    assert_eq!(
+        assign,
+        Assign {
+            value: Expr::Plus {
+                rhs: Atom("2"),
+                lhs: Box::new(Expr::Atom(Atom("1"))),
+            },
+            name: "x",
+        },
+    );
+
+    let err = Broken::from_cst(&cst).unwrap_err();
+    match err {
+        CstError::Unexpected { expected, span, .. } => {
+            assert_eq!(expected, r#"a child labeled "undefined""#);
+            assert_eq!(span, Some((0, 9)));
+        }
+        err => panic!("Unexpected error: {}", err),
+    }
+
+    Ok(())
+}
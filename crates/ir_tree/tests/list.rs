@@ -0,0 +1,160 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_tree::r#ref::{CSTree, CSTreeWalker};
+use copager_ir_tree::owned::{CSTreeOwned, CSTreeOwnedWalker};
+use copager_ir_tree::WalkError;
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"l")]
+    L,
+    #[token(r"r")]
+    R,
+    #[token(r"s")]
+    S,
+    #[token(r",")]
+    Comma,
+    #[token(r"[0-9]")]
+    Num,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<top> ::= L <lrec>")]
+    #[rule("<top> ::= R <rrec>")]
+    #[rule("<top> ::= S <sep>")]
+    Top,
+    #[rule("<lrec> ::= <lrec> <num>")]
+    #[rule("<lrec> ::= ")]
+    LRec,
+    #[rule("<rrec> ::= <num> <rrec>")]
+    #[rule("<rrec> ::= <num>")]
+    RRec,
+    #[rule("<sep> ::= <sep> Comma <num>")]
+    #[rule("<sep> ::= <num>")]
+    Sep,
+    #[rule("<num> ::= Num")]
+    Num,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+#[derive(Debug, PartialEq)]
+struct Num(String);
+
+impl From<CSTreeWalker<'_, TestLang>> for Num {
+    fn from(mut walker: CSTreeWalker<'_, TestLang>) -> Self {
+        Num(walker.expect_leaf().1.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct TryNum(String);
+
+impl TryFrom<CSTreeWalker<'_, TestLang>> for TryNum {
+    type Error = WalkError;
+
+    fn try_from(mut walker: CSTreeWalker<'_, TestLang>) -> Result<Self, Self::Error> {
+        let (_, text) = walker.try_expect_leaf_of(&TestToken::Num)?;
+        Ok(TryNum(text.to_string()))
+    }
+}
+
+fn nums(nums: &[&str]) -> Vec<Num> {
+    nums.iter().map(|num| Num(num.to_string())).collect()
+}
+
+#[test]
+fn expect_list() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    // 左再帰 (空の規則を含む)
+    let cst = processor.process::<CSTree<_>>("l 1 2 3")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.expect_leaf();
+    assert_eq!(walker.expect_list::<Num>(&TestRule::LRec), nums(&["1", "2", "3"]));
+
+    let cst = processor.process::<CSTree<_>>("l")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.expect_leaf();
+    assert_eq!(walker.expect_list::<Num>(&TestRule::LRec), vec![]);
+
+    // 右再帰
+    let cst = processor.process::<CSTree<_>>("r 1 2 3")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.expect_leaf();
+    assert_eq!(walker.expect_nodes::<Num>(), nums(&["1", "2", "3"]));
+
+    // 区切り字句を含むリスト
+    let cst = processor.process::<CSTree<_>>("s 1, 2, 3")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.expect_leaf();
+    assert_eq!(walker.expect_separated::<Num>(&TestRule::Sep, &TestToken::Comma), nums(&["1", "2", "3"]));
+
+    Ok(())
+}
+
+#[test]
+fn try_expect_list() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTree<_>>("r 1 2")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.try_expect_leaf()?;
+    assert_eq!(walker.try_expect_list::<TryNum>(&TestRule::RRec)?, vec![TryNum("1".to_string()), TryNum("2".to_string())]);
+
+    // 区切り字句を指定しない場合は区切り字句も要素として扱う
+    let cst = processor.process::<CSTree<_>>("s 1, 2")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.try_expect_leaf()?;
+    let err = walker.try_expect_list::<TryNum>(&TestRule::Sep).unwrap_err();
+    assert_eq!(err.expected, "Num");
+    assert_eq!(err.found, "Comma");
+    assert_eq!(err.span, Some((3, 4)));
+
+    // 規則が一致しない場合
+    let cst = processor.process::<CSTree<_>>("s 1, 2")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.try_expect_leaf()?;
+    let err = walker.try_expect_separated::<TryNum>(&TestRule::LRec, &TestToken::Comma).unwrap_err();
+    assert_eq!(err.expected, "<lrec>");
+    assert_eq!(err.found, "<sep>");
+
+    Ok(())
+}
+
+#[test]
+fn expect_list_owned() -> anyhow::Result<()> {
+    struct Num;
+
+    impl From<CSTreeOwnedWalker<TestLang>> for Num {
+        fn from(mut walker: CSTreeOwnedWalker<TestLang>) -> Self {
+            assert_eq!(walker.expect_leaf(), TestToken::Num);
+            Num
+        }
+    }
+
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTreeOwned<_>>("s 1, 2, 3, 4")?;
+    let mut walker = CSTreeOwnedWalker::from(cst);
+    walker.expect_leaf();
+    assert_eq!(walker.expect_separated::<Num>(&TestRule::Sep, &TestToken::Comma).len(), 4);
+
+    Ok(())
+}
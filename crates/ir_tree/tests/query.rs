@@ -0,0 +1,129 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_tree::r#ref::{CSTree, Query};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"print")]
+    Print,
+    #[token(r"=")]
+    Eql,
+    #[token(r";", ir_omit)]
+    Semi,
+    #[token(r"\+")]
+    Plus,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(r"[a-z]+")]
+    Id,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<stmts> ::= <stmts> <stmt>")]
+    #[rule("<stmts> ::= <stmt>")]
+    Stmts,
+    #[rule("<stmt> ::= <assign> Semi")]
+    #[rule("<stmt> ::= Print <expr> Semi")]
+    Stmt,
+    #[rule("<assign> ::= Id Eql <expr>")]
+    Assign,
+    #[rule("<expr> ::= <expr> Plus <atom>")]
+    #[rule("<expr> ::= <atom>")]
+    Expr,
+    #[rule("<atom> ::= Num")]
+    #[rule("<atom> ::= Id")]
+    Atom,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+const INPUT: &str = "a = 1;\nprint a + b;\nfoo = a + 2;";
+
+fn texts<'a>(captures: impl IntoIterator<Item = &'a CSTree<'a, TestLang>>) -> Vec<&'a str> {
+    captures
+        .into_iter()
+        .map(|tree| match tree {
+            CSTree::Leaf { text, .. } => *text,
+            CSTree::Node { .. } => "<node>",
+        })
+        .collect()
+}
+
+#[test]
+fn query_capture() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+    let cst = processor.process::<CSTree<_>>(INPUT)?;
+
+    // 子は部分列として照合される
+    let query = Query::<TestLang>::new("(Stmt (Assign (Id) @name))")?;
+    let captures = query.captures(&cst, "name");
+    assert_eq!(texts(captures.iter().map(|capture| capture.tree)), vec!["a", "foo"]);
+    assert_eq!(captures[1].span, Some((20, 23)));
+
+    // 節の取り出しは先頭の葉から末尾の葉までの位置を持つ
+    let query = Query::<TestLang>::new("(Assign (Expr) @value)")?;
+    let spans = query.captures(&cst, "value").into_iter().map(|capture| capture.span).collect::<Vec<_>>();
+    assert_eq!(spans, vec![Some((4, 5)), Some((26, 31))]);
+
+    // ワイルドカードと選択
+    let query = Query::<TestLang>::new(r#"
+        ; 演算子の左右
+        (Expr (_) @lhs "+" (Atom [(Num) (Id)] @rhs))
+    "#)?;
+    let matches = query.matches(&cst);
+    assert_eq!(matches.len(), 2);
+    assert_eq!(texts(matches.iter().map(|m| m.get("rhs").unwrap().tree)), vec!["b", "2"]);
+    assert!(matches.iter().all(|m| m.pattern == 0 && m.get("lhs").is_some()));
+
+    Ok(())
+}
+
+#[test]
+fn query_predicate() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+    let cst = processor.process::<CSTree<_>>(INPUT)?;
+
+    let query = Query::<TestLang>::new(r#"
+        (Assign (Id) @name (#match? @name "^[a-z]$"))
+        (Atom (Id) @var) (#not-eq? @var "a")
+    "#)?;
+    let matches = query.matches(&cst);
+    let found = matches
+        .iter()
+        .map(|m| (m.pattern, texts(m.captures.iter().map(|capture| capture.tree))))
+        .collect::<Vec<_>>();
+    assert_eq!(found, vec![(0, vec!["a"]), (1, vec!["b"])]);
+
+    Ok(())
+}
+
+#[test]
+fn query_error() {
+    let check = |src: &str, message: &str, pos: usize| {
+        let err = Query::<TestLang>::new(src).err().unwrap();
+        assert_eq!((err.message.as_str(), err.pos), (message, pos));
+    };
+
+    check("(Stmt (Unknown))", "Unknown rule or token Unknown", 7);
+    check("(Id (Num))", "Token Id cannot have children", 1);
+    check("(Assign (Id) @name", "Expected ')'", 18);
+    check("(Assign (Id) @name (#eq? @other \"x\"))", "Undefined capture @other", 0);
+    check("(#eq? @name \"x\")", "Predicate without a pattern", 0);
+    check("(Id) @name (#match? @name \"(\")", "Invalid regex: regex parse error:\n    (\n    ^\nerror: unclosed group", 13);
+}
@@ -0,0 +1,195 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir::visit::{Fold, Tree, Visit, Visitor, VisitorMut};
+use copager_ir_tree::r#ref::CSTree;
+use copager_ir_tree::owned::CSTreeOwned;
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"\(", ir_omit)]
+    BracketL,
+    #[token(r"\)", ir_omit)]
+    BracketR,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= <expr> Plus <atom>")]
+    #[rule("<expr> ::= <atom>")]
+    Expr,
+    #[rule("<atom> ::= BracketL <expr> BracketR")]
+    #[rule("<atom> ::= Num")]
+    Atom,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+fn texts<'a>(trees: impl Iterator<Item = &'a CSTree<'a, TestLang>>) -> Vec<&'a str> {
+    trees
+        .filter_map(|tree| match tree {
+            CSTree::Leaf { text, .. } => Some(*text),
+            CSTree::Node { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn visitor() -> anyhow::Result<()> {
+    // 括弧内を読み飛ばし，"3" で走査を打ち切る
+    #[derive(Default)]
+    struct Collector<'a> {
+        nums: Vec<&'a str>,
+        depth: usize,
+        max_depth: usize,
+    }
+
+    impl<'a> Visitor<CSTree<'a, TestLang>> for Collector<'a> {
+        fn enter(&mut self, _: &CSTree<'a, TestLang>) -> Visit {
+            self.depth += 1;
+            self.max_depth = self.max_depth.max(self.depth);
+            Visit::Continue
+        }
+
+        fn exit(&mut self, _: &CSTree<'a, TestLang>) -> Visit {
+            self.depth -= 1;
+            Visit::Continue
+        }
+
+        fn visit_rule(&mut self, rule: &TestRule, tree: &CSTree<'a, TestLang>) -> Visit {
+            match (rule, tree.children().map(|children| children.len())) {
+                (TestRule::Atom, Some(1)) if tree.leaves().count() > 1 => Visit::SkipChildren,
+                _ => Visit::Continue,
+            }
+        }
+
+        fn visit_token(&mut self, token: &TestToken, tree: &CSTree<'a, TestLang>) -> Visit {
+            match (token, tree) {
+                (TestToken::Num, CSTree::Leaf { text: "3", .. }) => Visit::Break,
+                (TestToken::Num, CSTree::Leaf { text, .. }) => {
+                    self.nums.push(text);
+                    Visit::Continue
+                }
+                _ => Visit::Continue,
+            }
+        }
+    }
+
+    let processor = TestProcessor::new().build()?;
+    let cst = processor.process::<CSTree<_>>("1 + (2 + 4) + 3 + 5")?;
+
+    let mut collector = Collector::default();
+    assert_eq!(cst.visit(&mut collector), Visit::Break);
+    assert_eq!(collector.nums, vec!["1"]);
+    assert_eq!(collector.max_depth, 6);
+
+    Ok(())
+}
+
+#[test]
+fn visitor_mut_and_fold() -> anyhow::Result<()> {
+    struct Doubler;
+
+    impl VisitorMut<CSTreeOwned<TestLang>> for Doubler {
+        fn visit_leaf(&mut self, tree: &mut CSTreeOwned<TestLang>) -> Visit {
+            if let CSTreeOwned::Leaf { tag: TestToken::Num, text, .. } = tree {
+                *text = (text.parse::<u32>().unwrap() * 2).to_string();
+            }
+            Visit::Continue
+        }
+    }
+
+    // 括弧を取り除く (<atom> ::= ( <expr> ) を内側の <expr> に置き換える)
+    struct Unparen;
+
+    impl Fold<CSTreeOwned<TestLang>> for Unparen {
+        fn fold_node(&mut self, tree: CSTreeOwned<TestLang>) -> CSTreeOwned<TestLang> {
+            match tree {
+                CSTreeOwned::Node { tag: TestRule::Atom, alt, mut children } if children.len() == 1 => {
+                    match children.pop_front().unwrap() {
+                        child @ CSTreeOwned::Node { .. } => self.fold(child),
+                        child => {
+                            children.push_front(child);
+                            CSTreeOwned::Node { tag: TestRule::Atom, alt, children }
+                        }
+                    }
+                }
+                CSTreeOwned::Node { tag, alt, children } => {
+                    let children = children.into_iter().map(|child| self.fold(child)).collect();
+                    CSTreeOwned::Node { tag, alt, children }
+                }
+                leaf => leaf,
+            }
+        }
+    }
+
+    let processor = TestProcessor::new().build()?;
+    let mut cst = processor.process::<CSTreeOwned<_>>("1 + (2 + 3)")?;
+
+    assert_eq!(cst.visit_mut(&mut Doubler), Visit::Continue);
+    let texts = cst.leaves().filter_map(|tree| match tree {
+        CSTreeOwned::Leaf { text, .. } => Some(text.as_str()),
+        CSTreeOwned::Node { .. } => None,
+    });
+    assert_eq!(texts.collect::<Vec<_>>(), vec!["2", "+", "4", "+", "6"]);
+
+    let before = cst.pre_order().count();
+    let cst = cst.fold(&mut Unparen);
+    assert_eq!(cst.pre_order().count(), before - 1);
+    assert_eq!(cst.leaves().count(), 5);
+
+    Ok(())
+}
+
+#[test]
+fn iterators() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+    let cst = processor.process::<CSTree<_>>("1 + 2")?;
+
+    // (Expr (Expr (Atom 1)) + (Atom 2))
+    let rules = |trees: Vec<&CSTree<'_, TestLang>>| {
+        trees.into_iter().filter_map(|tree| tree.rule().cloned()).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        rules(cst.pre_order().collect()),
+        vec![TestRule::Expr, TestRule::Expr, TestRule::Atom, TestRule::Atom],
+    );
+    assert_eq!(texts(cst.pre_order()), vec!["1", "+", "2"]);
+    assert_eq!(texts(cst.post_order()), vec!["1", "+", "2"]);
+    assert!(matches!(cst.post_order().last(), Some(CSTree::Node { tag: TestRule::Expr, .. })));
+    assert_eq!(cst.descendants().count(), cst.pre_order().count() - 1);
+    assert_eq!(texts(cst.leaves()), vec!["1", "+", "2"]);
+
+    // 祖先は親から根への順に並ぶ
+    let view = cst.parent_view();
+    let one = cst.leaves().next().unwrap();
+    let node = view.find(one).unwrap();
+    assert_eq!(node.depth(), 3);
+    assert_eq!(
+        node.ancestors().map(|node| node.get().rule().cloned().unwrap()).collect::<Vec<_>>(),
+        vec![TestRule::Atom, TestRule::Expr, TestRule::Expr],
+    );
+    assert!(std::ptr::eq(node.ancestors().last().unwrap().get(), &cst));
+    assert!(view.root().parent().is_none());
+    assert_eq!(view.iter().count(), cst.pre_order().count());
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use copager_core::{Generator, Processor};
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_regex::RegexLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_tree::r#ref::{CSTree, CSTreeWalker};
+use copager_ir_tree::owned::{CSTreeOwned, CSTreeOwnedWalker};
+use copager_ir_tree::WalkError;
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"=", ir_omit)]
+    Eql,
+    #[token(r",", ir_omit)]
+    Comma,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(r"[a-z]+")]
+    Id,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<assign> ::= Id Eql <list>")]
+    Assign,
+    #[rule("<list> ::= <list> Comma <atom>")]
+    #[rule("<list> ::= <atom>")]
+    #[rule("<list> ::= ")]
+    List,
+    #[rule("<atom> ::= Num")]
+    #[rule("<atom> ::= Id")]
+    Atom,
+}
+
+type TestGenerator<T> = Generator<T, RegexLexer<T>, LR1<T>>;
+type TestProcessor = Processor<TestGenerator<TestLang>>;
+
+#[derive(Debug, PartialEq)]
+struct Num(u32);
+
+impl TryFrom<CSTreeWalker<'_, TestLang>> for Num {
+    type Error = WalkError;
+
+    fn try_from(mut walker: CSTreeWalker<'_, TestLang>) -> Result<Self, Self::Error> {
+        let (_, text) = walker.try_expect_leaf_of(&TestToken::Num)?;
+        Ok(Num(text.parse().unwrap()))
+    }
+}
+
+#[test]
+fn try_expect_success() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    let cst = processor.process::<CSTree<_>>("x = 1, 2, 3")?;
+    let mut walker = CSTreeWalker::from(cst);
+    assert!(walker.try_expect_leaf()?.0 == TestToken::Id);
+    assert_eq!(walker.try_expect_nodes::<Num>()?, vec![Num(1), Num(2), Num(3)]);
+
+    let cst = processor.process::<CSTree<_>>("x = ")?;
+    let mut walker = CSTreeWalker::from(cst);
+    walker.try_expect_leaf_of(&TestToken::Id)?;
+    assert_eq!(walker.try_expect_nodes::<Num>()?, vec![]);
+
+    Ok(())
+}
+
+#[test]
+fn try_expect_failed() -> anyhow::Result<()> {
+    let processor = TestProcessor::new().build()?;
+
+    // 期待と異なる要素は期待した要素と実際の要素，その位置を返す
+    let cst = processor.process::<CSTree<_>>("x = 1, y")?;
+    let mut walker = CSTreeWalker::from(cst);
+    let err = walker.try_expect_node_of::<Num>(&TestRule::List).unwrap_err();
+    assert_eq!(err.expected, "<list>");
+    assert_eq!(err.found, "Id");
+    assert_eq!(err.span, Some((0, 1)));
+
+    let err = walker.try_expect_nodes::<Num>().unwrap_err();
+    assert_eq!(err.expected, "Num");
+    assert_eq!(err.found, "Id");
+    assert_eq!(err.span, Some((7, 8)));
+    assert_eq!(err.to_string(), "Expected Num but found Id");
+
+    // 要素が残っていない場合は走査中の節の位置を返す
+    let err = walker.try_expect_leaf().unwrap_err();
+    assert_eq!(err.found, "nothing");
+    assert_eq!(err.span, Some((0, 8)));
+
+    let cst = processor.process::<CSTreeOwned<_>>("x = 1")?;
+    let mut walker = CSTreeOwnedWalker::from(cst);
+    let err = walker.try_expect_leaf_of(&TestToken::Num).unwrap_err();
+    assert_eq!(err.expected, "Num");
+    assert_eq!(err.found, "Id");
+
+    Ok(())
+}
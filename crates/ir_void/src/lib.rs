@@ -1,32 +1,64 @@
 use std::fmt::Debug;
 
-use copager_lang::token::Token;
+use copager_lang::token::{Token, TokenOwned};
 use copager_lang::Lang;
-use copager_ir::{IR, IRBuilder};
+use copager_ir::{IR, IRBuilder, IROwned, IROwnedBuilder, TokenSource, Error};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Void;
 
-impl<'input, L: Lang> IR<'input, L> for Void {
+impl<'input, L, S> IR<'input, L, S> for Void
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
     type Builder = Self;
 }
 
-impl <'input, L: Lang> IRBuilder<'input, L> for Void {
+impl<'input, L, S> IRBuilder<'input, L, S> for Void
+where
+    L: Lang,
+    S: TokenSource + ?Sized,
+{
     type Output = Self;
 
     fn new() -> Void {
         Void
     }
 
-    fn on_read(&mut self, _: Token<'input, L::TokenTag>) -> anyhow::Result<()> {
+    fn on_read(&mut self, _: Token<'input, L::TokenTag, S>) -> Result<(), Error> {
         Ok(())
     }
 
-    fn on_parse(&mut self, _: L::RuleTag, _: usize) -> anyhow::Result<()> {
+    fn on_parse(&mut self, _: L::RuleTag, _: usize) -> Result<(), Error> {
         Ok(())
     }
 
-    fn build(self) -> anyhow::Result<Void> {
+    fn build(self) -> Result<Void, Error> {
+        Ok(Void)
+    }
+}
+
+impl<L: Lang> IROwned<L> for Void {
+    type Builder = Self;
+}
+
+impl<L: Lang> IROwnedBuilder<L> for Void {
+    type Output = Self;
+
+    fn new() -> Void {
+        Void
+    }
+
+    fn on_read(&mut self, _: TokenOwned<L::TokenTag>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_parse(&mut self, _: L::RuleTag, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn build(self) -> Result<Void, Error> {
         Ok(Void)
     }
 }
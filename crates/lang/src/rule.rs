@@ -10,6 +10,7 @@ pub trait RuleTag<T: TokenTag>
 where
     Self: Clone + Hash + Eq,
 {
+    fn as_name(&self) -> &'static str;
     fn as_rules(&self) -> Vec<Rule<T, Self>>;
 }
 
@@ -24,9 +25,13 @@ where
         deserialize = "T: Deserialize<'de>, R: Deserialize<'de>",
     ))]
     pub id: usize,
+    #[serde(default)]
+    pub alt: usize,  // 同じタグを持つ規則の中での位置 (as_rules() における位置)
     pub tag: Option<R>,
     pub lhs: RuleElem<T>,
     pub rhs: Vec<RuleElem<T>>,
+    #[serde(default)]
+    pub labels: Vec<Option<String>>,  // 右辺の各要素のラベル (<lhs:expr> の lhs，ラベルを持たない規則の場合は空)
 }
 
 impl<T, R> Display for Rule<T, R>
@@ -81,7 +86,20 @@ where
     R: RuleTag<T>,
 {
     pub fn new(tag: Option<R>, lhs: RuleElem<T>, rhs: Vec<RuleElem<T>>) -> Self {
-        Rule { id: 0, tag, lhs, rhs }
+        Rule { id: 0, alt: 0, tag, lhs, rhs, labels: vec![] }
+    }
+
+    pub fn with_labels(mut self, labels: Vec<Option<&str>>) -> Self {
+        self.labels = labels.into_iter().map(|label| label.map(str::to_string)).collect();
+        self
+    }
+
+    pub fn label_of(&self, idx: usize) -> Option<&str> {
+        self.labels.get(idx)?.as_deref()
+    }
+
+    pub fn position_of(&self, label: &str) -> Option<usize> {
+        self.labels.iter().position(|l| l.as_deref() == Some(label))
     }
 
     pub fn nonterms<'a>(&'a self) -> Vec<&'a RuleElem<T>> {
@@ -168,7 +186,8 @@ pub trait RuleSet<T: TokenTag> {
         let set_id_for_all = |(id, tag): (usize, Self::Tag)| {
             tag.as_rules()
                 .into_iter()
-                .map(move |mut rule| { rule.id = id; rule })
+                .enumerate()
+                .map(move |(alt, mut rule)| { rule.id = id; rule.alt = alt; rule })
         };
         self.iter()
             .enumerate()
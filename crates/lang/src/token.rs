@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::fmt::Debug;
 use std::hash::Hash;
 
@@ -7,36 +8,226 @@ pub trait TokenTag
 where
     Self: Clone + Hash + Eq,
 {
+    fn as_name(&self) -> &'static str;
     fn as_str_list<'a, 'b>(&'a self) -> &'a[&'b str];
     fn as_option_list<'a, 'b>(&'a self) -> &'a[&'b str] { &[] }
+
+    // 字句が有効となるモードの一覧 (空の場合は DEFAULT_MODE)
+    fn as_mode_list<'a, 'b>(&'a self) -> &'a[&'b str] { &[] }
+
+    // 字句を読んだ後に行うモードの切り替え
+    fn as_mode_action(&self) -> Option<ModeAction> { None }
+
+    // 正規表現の代わりに用いる関数 (残りの入力を受け取り，一致した長さを返す)
+    fn as_matcher(&self) -> Option<TokenMatcher> { None }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Token<'input, T: TokenTag> {
+pub type TokenMatcher = fn(&str) -> Option<usize>;
+
+pub const DEFAULT_MODE: &str = "default";
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ModeAction {
+    Push(&'static str),
+    Pop,
+    Set(&'static str),
+}
+
+// 字句解析の入力となる列 (UTF-8 文字列またはバイト列)
+pub trait TokenSource {
+    fn slice(&self, range: (usize, usize)) -> &Self;
+    fn to_text(&self) -> Cow<'_, str>;
+}
+
+impl TokenSource for str {
+    fn slice(&self, (l, r): (usize, usize)) -> &Self {
+        &self[l..r]
+    }
+
+    fn to_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl TokenSource for [u8] {
+    fn slice(&self, (l, r): (usize, usize)) -> &Self {
+        &self[l..r]
+    }
+
+    // UTF-8 として不正なバイトは置換文字 (U+FFFD) に変換する
+    fn to_text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, S: Serialize",
+    deserialize = "T: Deserialize<'de>, &'input S: Deserialize<'de>",
+))]
+pub struct Token<'input, T, S = str>
+where
+    T: TokenTag,
+    S: TokenSource + ?Sized,
+{
     pub kind: T,
-    pub src: &'input str,
+    #[serde(borrow)]
+    pub src: &'input S,
     pub body: (usize, usize),  // Trivia を含まない
     pub full: (usize, usize),  // Trivia を含む
+    #[serde(default)]
+    pub trivia: Vec<Trivia<T>>,  // full に含まれる Trivia (位置の順)
 }
 
-impl<'input, T: TokenTag> Token<'input, T> {
+impl<T, S> Clone for Token<'_, T, S>
+where
+    T: TokenTag,
+    S: TokenSource + ?Sized,
+{
+    fn clone(&self) -> Self {
+        Token {
+            kind: self.kind.clone(),
+            src: self.src,
+            body: self.body,
+            full: self.full,
+            trivia: self.trivia.clone(),
+        }
+    }
+}
+
+impl<'input, T, S> Token<'input, T, S>
+where
+    T: TokenTag,
+    S: TokenSource + ?Sized,
+{
     pub fn new(
         kind: T,
-        src: &'input str,
+        src: &'input S,
         body: (usize, usize),
         full: (usize, usize),
     ) -> Self {
-        Token { kind, src, body, full }
+        Token { kind, src, body, full, trivia: vec![] }
+    }
+
+    pub fn with_trivia(mut self, trivia: Vec<Trivia<T>>) -> Self {
+        self.trivia = trivia;
+        self
+    }
+
+    // body より前にある Trivia
+    pub fn leading_trivia(&self) -> impl Iterator<Item = &Trivia<T>> {
+        self.trivia.iter().filter(|trivia| trivia.span.1 <= self.body.0)
+    }
+
+    // body より後にある Trivia
+    pub fn trailing_trivia(&self) -> impl Iterator<Item = &Trivia<T>> {
+        self.trivia.iter().filter(|trivia| trivia.span.0 >= self.body.1)
+    }
+
+    pub fn trivia_slice(&self, trivia: &Trivia<T>) -> &'input S {
+        self.src.slice(trivia.span)
+    }
+
+    pub fn as_slice(&self) -> &'input S {
+        self.src.slice(self.body)
     }
 
+    pub fn as_full_slice(&self) -> &'input S {
+        self.src.slice(self.full)
+    }
+
+    pub fn as_text(&self) -> Cow<'input, str> {
+        self.as_slice().to_text()
+    }
+}
+
+impl<'input, T: TokenTag> Token<'input, T> {
     pub fn as_str(&self) -> &'input str {
-        let (l, r) = self.body;
-        &self.src[l..r]
+        self.as_slice()
     }
 
     pub fn as_full_str(&self) -> &'input str {
-        let (l, r) = self.full;
-        &self.src[l..r]
+        self.as_full_slice()
+    }
+}
+
+impl<'input, T: TokenTag> Token<'input, T, [u8]> {
+    pub fn as_bytes(&self) -> &'input [u8] {
+        self.as_slice()
+    }
+
+    pub fn as_full_bytes(&self) -> &'input [u8] {
+        self.as_full_slice()
+    }
+}
+
+// Trivia として読み飛ばされた字句 (span は入力全体における位置)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Trivia<T> {
+    pub kind: T,
+    pub span: (usize, usize),
+}
+
+impl<T: TokenTag> Trivia<T> {
+    // comment オプションを持つ字句はコメント，それ以外は空白として扱う
+    pub fn is_comment(&self) -> bool {
+        self.kind.as_option_list().contains(&"comment")
+    }
+
+    pub fn is_whitespace(&self) -> bool {
+        !self.is_comment()
+    }
+
+    pub fn shift(&self, offset: usize) -> Self {
+        Trivia {
+            kind: self.kind.clone(),
+            span: (self.span.0 + offset, self.span.1 + offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenOwned<T: TokenTag> {
+    pub kind: T,
+    pub src: String,           // Trivia を含む字句の文字列
+    pub body: (usize, usize),  // 入力全体における位置 (Trivia を含まない)
+    pub full: (usize, usize),  // 入力全体における位置 (Trivia を含む)
+    #[serde(default)]
+    pub trivia: Vec<Trivia<T>>,
+}
+
+impl<T: TokenTag> TokenOwned<T> {
+    // offset は token.src の先頭の入力全体における位置
+    pub fn from_token(token: &Token<'_, T>, offset: usize) -> Self {
+        TokenOwned {
+            kind: token.kind.clone(),
+            src: token.as_full_str().to_string(),
+            body: (token.body.0 + offset, token.body.1 + offset),
+            full: (token.full.0 + offset, token.full.1 + offset),
+            trivia: token.trivia.iter().map(|trivia| trivia.shift(offset)).collect(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        let (l, r) = self.body;
+        &self.src[l - self.full.0..r - self.full.0]
+    }
+
+    pub fn as_full_str(&self) -> &str {
+        &self.src
+    }
+
+    pub fn leading_trivia(&self) -> impl Iterator<Item = &Trivia<T>> {
+        self.trivia.iter().filter(|trivia| trivia.span.1 <= self.body.0)
+    }
+
+    pub fn trailing_trivia(&self) -> impl Iterator<Item = &Trivia<T>> {
+        self.trivia.iter().filter(|trivia| trivia.span.0 >= self.body.1)
+    }
+
+    pub fn trivia_str(&self, trivia: &Trivia<T>) -> &str {
+        let (l, r) = trivia.span;
+        &self.src[l - self.full.0..r - self.full.0]
     }
 }
 
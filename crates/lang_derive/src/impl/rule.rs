@@ -30,6 +30,9 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
 
     // 列挙型に関する情報を用意
     let enum_name = &ast.ident;
+    let enum_name_matchers = parsed_variantes
+        .iter()
+        .map(|variant| variant.gen_matcher_ident_to_name());
     let enum_rule_matchers = parsed_variantes
         .iter()
         .map(|variant| variant.gen_matcher_ident_to_rule());
@@ -43,6 +46,12 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
 
     quote! {
         impl RuleTag<#tokenset_ty> for #enum_name {
+            fn as_name(&self) -> &'static str {
+                match self {
+                    #( #enum_name_matchers, )*
+                }
+            }
+
             fn as_rules(&self) -> Vec<Rule<#tokenset_ty, Self>> {
                 match self {
                     #( #enum_rule_matchers, )*
@@ -66,7 +75,7 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
 
 struct VariantInfo<'a> {
     ident: &'a Ident,
-    rule_lhs_rhs_tuples: Vec<TokenStream>,
+    rule_lhs_rhs_tuples: Vec<(TokenStream, Vec<Option<String>>)>,
 }
 
 impl<'a> VariantInfo<'a> {
@@ -81,7 +90,7 @@ impl<'a> VariantInfo<'a> {
                 let attr = attr.parse_args::<LitStr>().unwrap();
                 let bnf = match BNF::parse(tokenset_ty, &attr.value().as_str()) {
                     Ok(bnf) => bnf,
-                    Err(e) => syn::Error::new(attr.span(), e).to_compile_error(),
+                    Err(e) => (syn::Error::new(attr.span(), e).to_compile_error(), vec![]),
                 };
                 rule_lhs_rhs_tuples.push(bnf);
             }
@@ -95,13 +104,31 @@ impl<'a> VariantInfo<'a> {
         quote! { Self :: #ident }
     }
 
+    fn gen_matcher_ident_to_name(&self) -> TokenStream {
+        let ident = self.gen_ident();
+        let name = self.ident.to_string();
+        quote! { #ident => #name }
+    }
+
     fn gen_matcher_ident_to_rule(&self) -> TokenStream {
         let ident = self.gen_ident();
         if self.rule_lhs_rhs_tuples.is_empty() {
             quote! { #ident => unimplemented!() }
         } else {
-            let lhs_rhs_tuple = &self.rule_lhs_rhs_tuples;
-            quote! { #ident => vec![#(Rule::new(Some(#ident), #lhs_rhs_tuple)),*] }
+            let rules = self.rule_lhs_rhs_tuples
+                .iter()
+                .map(|(lhs_rhs_tuple, labels)| {
+                    let rule = quote! { Rule::new(Some(#ident), #lhs_rhs_tuple) };
+                    if labels.iter().all(Option::is_none) {
+                        return rule;
+                    }
+                    let labels = labels.iter().map(|label| match label {
+                        Some(label) => quote! { Some(#label) },
+                        None => quote! { None },
+                    });
+                    quote! { #rule.with_labels(vec![ #( #labels, )* ]) }
+                });
+            quote! { #ident => vec![#(#rules),*] }
         }
     }
 }
@@ -116,20 +143,23 @@ struct BNF<'a> {
 
 impl<'a> BNF<'a> {
     // <bnf> ::= <rule>
-    fn parse(tokenset_ty: &'a Ident, src: &'a str) -> Result<TokenStream, String> {
+    fn parse(tokenset_ty: &'a Ident, src: &'a str) -> Result<(TokenStream, Vec<Option<String>>), String> {
         BNF { tokenset_ty, src, cursor: 0, row: 1, col: 1 }.parse_rule()
     }
 
     // <rule> ::= <nonterm> '::=' <rhs>
-    fn parse_rule(&mut self) -> Result<TokenStream, String> {
-        let lhs = self.parse_nonterm()?;
+    fn parse_rule(&mut self) -> Result<(TokenStream, Vec<Option<String>>), String> {
+        let (lhs, label) = self.parse_nonterm()?;
+        if label.is_some() {
+            self.error("Labels cannot be used on the left-hand side")?;
+        }
         self.consume("::=")?;
-        let rhs = self.parse_rhs()?;
-        Ok(quote! { #lhs, vec![ #( #rhs, )* ], })
+        let (rhs, labels): (Vec<_>, Vec<_>) = self.parse_rhs()?.into_iter().unzip();
+        Ok((quote! { #lhs, vec![ #( #rhs, )* ], }, labels))
     }
 
-    // <rhs> ::= ((<nonterm> | <ident>)*)?
-    fn parse_rhs(&mut self) -> Result<Vec<TokenStream>, String> {
+    // <rhs> ::= (((<label> ':')? <ident> | <nonterm>)*)?
+    fn parse_rhs(&mut self) -> Result<Vec<(TokenStream, Option<String>)>, String> {
         let mut rhs = vec![];
         loop {
             self.skip_spaces();
@@ -140,27 +170,40 @@ impl<'a> BNF<'a> {
                 rhs.push(self.parse_nonterm()?);
             } else {
                 let tokenset_ty = self.tokenset_ty;
-                let ident = self.parse_ident()?.parse::<TokenStream>().unwrap();
-                rhs.push(quote! { RuleElem::new_term(#tokenset_ty :: #ident) });
+                let (label, ident) = self.parse_labeled_ident()?;
+                let ident = ident.parse::<TokenStream>().unwrap();
+                rhs.push((quote! { RuleElem::new_term(#tokenset_ty :: #ident) }, label));
             }
         }
         if rhs.is_empty() {
-            rhs.push(quote! { RuleElem::Epsilon });
+            rhs.push((quote! { RuleElem::Epsilon }, None));
         }
         Ok(rhs)
     }
 
-    // <nonterm> ::= '<' <nonterm> '>'
-    fn parse_nonterm(&mut self) -> Result<TokenStream, String> {
+    // <nonterm> ::= '<' (<label> ':')? <ident> '>'
+    fn parse_nonterm(&mut self) -> Result<(TokenStream, Option<String>), String> {
         self.consume("<")?;
-        let ident = self.parse_ident()?;
+        let (label, ident) = self.parse_labeled_ident()?;
         let lhs = quote! { RuleElem::new_nonterm(#ident) };
         self.consume(">")?;
-        Ok(lhs)
+        Ok((lhs, label))
+    }
+
+    // (<label> ':')? <ident>
+    fn parse_labeled_ident(&mut self) -> Result<(Option<String>, &'a str), String> {
+        let ident = self.parse_ident()?;
+        if self.src[self.cursor..].starts_with(':') {
+            self.consume(":")?;
+            let labeled = self.parse_ident()?;
+            Ok((Some(ident.to_string()), labeled))
+        } else {
+            Ok((None, ident))
+        }
     }
 
     // <ident> ::= [a-zA-Z_][a-zA-Z0-9_]*
-    fn parse_ident(&mut self) -> Result<&str, String> {
+    fn parse_ident(&mut self) -> Result<&'a str, String> {
         self.skip_spaces();
 
         let end_idx = self.src[self.cursor..]
@@ -18,12 +18,17 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
         .collect::<Vec<_>>();
 
     let enum_name = &ast.ident;
+    let enum_name_matchers = parsed_variantes
+        .iter()
+        .map(|variant| variant.gen_name_matcher());
     let enum_str_matchers = parsed_variantes
         .iter()
         .map(|variant| variant.gen_str_matcher());
     let enum_opts_matchers = parsed_variantes
         .iter()
         .map(|variant| variant.gen_option_matcher());
+    let enum_mode_methods = gen_mode_methods(&parsed_variantes);
+    let enum_matcher_method = gen_matcher_method(&parsed_variantes);
     let enum_first_variant = parsed_variantes
         .first()
         .unwrap()
@@ -34,6 +39,12 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
 
     quote! {
         impl TokenTag for #enum_name {
+            fn as_name(&self) -> &'static str {
+                match self {
+                    #( #enum_name_matchers, )*
+                }
+            }
+
             fn as_str_list<'a, 'b>(&'a self) -> &'a[&'b str] {
                 match self {
                     #( #enum_str_matchers, )*
@@ -45,6 +56,9 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
                     #( #enum_opts_matchers, )*
                 }
             }
+
+            #enum_mode_methods
+            #enum_matcher_method
         }
 
         impl TokenSet for #enum_name {
@@ -61,11 +75,64 @@ pub fn proc_macro_impl(ast: DeriveInput) -> TokenStream {
     }
 }
 
+// モードを用いる場合のみ as_mode_list, as_mode_action を生成する
+fn gen_mode_methods(variants: &[TokenDefVariant]) -> TokenStream {
+    let use_mode = variants
+        .iter()
+        .any(|variant| !variant.modes.is_empty() || variant.mode_action.is_some());
+    if !use_mode {
+        return quote! {};
+    }
+
+    let mode_matchers = variants
+        .iter()
+        .map(|variant| variant.gen_mode_matcher());
+    let mode_action_matchers = variants
+        .iter()
+        .map(|variant| variant.gen_mode_action_matcher());
+
+    quote! {
+        fn as_mode_list<'a, 'b>(&'a self) -> &'a[&'b str] {
+            match self {
+                #( #mode_matchers, )*
+            }
+        }
+
+        fn as_mode_action(&self) -> Option<ModeAction> {
+            match self {
+                #( #mode_action_matchers, )*
+            }
+        }
+    }
+}
+
+// 関数による字句を用いる場合のみ as_matcher を生成する
+fn gen_matcher_method(variants: &[TokenDefVariant]) -> TokenStream {
+    if variants.iter().all(|variant| variant.matcher.is_none()) {
+        return quote! {};
+    }
+
+    let matcher_matchers = variants
+        .iter()
+        .map(|variant| variant.gen_matcher_matcher());
+
+    quote! {
+        fn as_matcher(&self) -> Option<fn(&str) -> Option<usize>> {
+            match self {
+                #( #matcher_matchers, )*
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct TokenDefVariant<'a> {
     ident: &'a Ident,
     texts: Vec<TokenStream>,
     options: Vec<TokenStream>,
+    modes: Vec<TokenStream>,
+    mode_action: Option<TokenStream>,
+    matcher: Option<TokenStream>,
 }
 
 impl<'a> TokenDefVariant<'a> {
@@ -76,18 +143,49 @@ impl<'a> TokenDefVariant<'a> {
         // 字句定義とオプションを抽出
         let mut texts = vec![];
         let mut options = vec![];
+        let mut modes = vec![];
+        let mut mode_action = None;
+        let mut matcher = None;
         for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("token")) {
+            // カンマ区切りで要素に分割
             let meta_list = attr.meta.require_list().unwrap().tokens.clone();
+            let mut metas = vec![vec![]];
             for meta in meta_list.into_iter() {
                 match meta {
-                    TokenTree::Literal(lit) => texts.push(lit.to_token_stream()),
-                    TokenTree::Ident(ident) => options.push(ident.to_token_stream()),
-                    _ => {},
+                    TokenTree::Punct(punct) if punct.as_char() == ',' => metas.push(vec![]),
+                    meta => metas.last_mut().unwrap().push(meta),
+                }
+            }
+
+            for meta in metas {
+                match meta.as_slice() {
+                    [TokenTree::Literal(lit)] => texts.push(lit.to_token_stream()),
+                    [TokenTree::Ident(ident)] if ident == "pop_mode" => {
+                        mode_action = Some(quote! { ModeAction::Pop });
+                    }
+                    [TokenTree::Ident(ident)] => options.push(ident.to_token_stream()),
+                    [TokenTree::Ident(key), TokenTree::Punct(punct), TokenTree::Literal(value)]
+                        if punct.as_char() == '=' =>
+                    {
+                        match key.to_string().as_str() {
+                            "mode" => modes.push(value.to_token_stream()),
+                            "push_mode" => mode_action = Some(quote! { ModeAction::Push(#value) }),
+                            "set_mode" => mode_action = Some(quote! { ModeAction::Set(#value) }),
+                            key => panic!("Unknown token option \"{}\" is specified.", key),
+                        }
+                    }
+                    [TokenTree::Ident(key), TokenTree::Punct(punct), path @ ..]
+                        if key == "fn" && punct.as_char() == '=' && !path.is_empty() =>
+                    {
+                        matcher = Some(path.iter().cloned().collect::<TokenStream>());
+                    }
+                    [] => {},
+                    _ => panic!("Invalid token definition of \"{}\".", ident),
                 }
             }
         }
 
-        TokenDefVariant { ident, texts, options }
+        TokenDefVariant { ident, texts, options, modes, mode_action, matcher }
     }
 
     fn gen_ident(&self) -> TokenStream {
@@ -95,6 +193,12 @@ impl<'a> TokenDefVariant<'a> {
         quote! { Self :: #ident }
     }
 
+    fn gen_name_matcher(&self) -> TokenStream {
+        let ident = self.gen_ident();
+        let name = self.ident.to_string();
+        quote! { #ident => #name }
+    }
+
     fn gen_str_matcher(&self) -> TokenStream {
         let ident = self.gen_ident();
         let str_list = &self.texts;
@@ -106,4 +210,26 @@ impl<'a> TokenDefVariant<'a> {
         let opt_list = &self.options;
         quote! { #ident => &[#(stringify!(#opt_list),)*] }
     }
+
+    fn gen_mode_matcher(&self) -> TokenStream {
+        let ident = self.gen_ident();
+        let mode_list = &self.modes;
+        quote! { #ident => &[#(#mode_list,)*] }
+    }
+
+    fn gen_mode_action_matcher(&self) -> TokenStream {
+        let ident = self.gen_ident();
+        match &self.mode_action {
+            Some(mode_action) => quote! { #ident => Some(#mode_action) },
+            None => quote! { #ident => None },
+        }
+    }
+
+    fn gen_matcher_matcher(&self) -> TokenStream {
+        let ident = self.gen_ident();
+        match &self.matcher {
+            Some(matcher) => quote! { #ident => Some(#matcher) },
+            None => quote! { #ident => None },
+        }
+    }
 }
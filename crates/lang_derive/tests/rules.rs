@@ -36,3 +36,26 @@ fn check_compile_rules() {
     assert_eq!(rules[2].lhs, RuleElem::new_nonterm("expr"));
     assert_eq!(rules[2].rhs, vec![RuleElem::new_term(MyToken::Number)]);
 }
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum LabeledRule {
+    #[tokenset(MyToken)]
+    #[rule("<expr> ::= <lhs:expr> op:Plus rhs:Number")]
+    #[rule("<expr> ::= Number")]
+    Expr,
+}
+
+#[test]
+fn check_compile_labeled_rules() {
+    let rules = LabeledRule::Expr.as_rules();
+    assert_eq!(rules[0].rhs, vec![RuleElem::new_nonterm("expr"), RuleElem::new_term(MyToken::Plus), RuleElem::new_term(MyToken::Number)]);
+    assert_eq!(rules[0].label_of(0), Some("lhs"));
+    assert_eq!(rules[0].label_of(1), Some("op"));
+    assert_eq!(rules[0].position_of("rhs"), Some(2));
+    assert_eq!(rules[0].position_of("none"), None);
+    assert!(rules[1].labels.is_empty());
+
+    // ラベルは規則の同一性に影響しない
+    let unlabeled = Rule::new(Some(LabeledRule::Expr), rules[0].lhs.clone(), rules[0].rhs.clone());
+    assert_eq!(rules[0], unlabeled);
+}
@@ -17,6 +17,7 @@ fn check_compile_tokens() {
     assert_eq!(mytoken.iter().count(), 3);
 
     // TokenTag
+    assert_eq!(MyToken::Plus.as_name(), "Plus");
     assert_eq!(MyToken::Plus.as_str_list(), &[r"\+", r"plus"]);
     assert_eq!(MyToken::Plus.as_option_list().len(), 0);
     assert_eq!(MyToken::Minus.as_str_list(), &[r"\-", r"minus"]);
@@ -0,0 +1,36 @@
+use copager_lang::token::{ModeAction, TokenSet, TokenTag};
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum MyToken {
+    #[token(r"<", push_mode = "tag")]
+    TagL,
+    #[token(r">", mode = "tag", pop_mode)]
+    TagR,
+    #[token(r"[a-z]+", mode = "tag")]
+    Name,
+    #[token(r"[^<]+")]
+    Text,
+    #[token(r"@", mode = "tag", set_mode = "attr")]
+    At,
+    #[token(r"[ \t\n]+", mode = "tag", mode = "attr", trivia)]
+    _WhiteSpace,
+}
+
+#[test]
+fn check_compile_tokens_with_mode() {
+    // TokenSet
+    let mytoken = MyToken::instantiate();
+    assert_eq!(mytoken.iter().count(), 6);
+
+    // TokenTag
+    assert_eq!(MyToken::TagL.as_str_list(), &[r"<"]);
+    assert_eq!(MyToken::TagL.as_mode_list().len(), 0);
+    assert_eq!(MyToken::TagL.as_mode_action(), Some(ModeAction::Push("tag")));
+    assert_eq!(MyToken::TagR.as_mode_list(), &["tag"]);
+    assert_eq!(MyToken::TagR.as_mode_action(), Some(ModeAction::Pop));
+    assert_eq!(MyToken::Name.as_mode_action(), None);
+    assert_eq!(MyToken::Text.as_mode_list().len(), 0);
+    assert_eq!(MyToken::At.as_mode_action(), Some(ModeAction::Set("attr")));
+    assert_eq!(MyToken::_WhiteSpace.as_mode_list(), &["tag", "attr"]);
+    assert_eq!(MyToken::_WhiteSpace.as_option_list(), &["trivia"]);
+}
@@ -0,0 +1,163 @@
+use std::iter;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+
+use copager_lang::token::{Token, TokenTag, Trivia};
+use copager_lang::Lang;
+use copager_utils::error::{Error, Result};
+
+use crate::mode::LexState;
+use crate::BaseLexer;
+
+// 字句の種類と位置の列を返す外部の字句解析器 (logos や手書きの字句解析器など)
+pub trait Tokenizer<L: Lang>
+where
+    Self: Sized,
+{
+    fn init() -> Result<Self>;
+    fn tokenize(&self, input: &str)
+        -> impl Iterator<Item = (L::TokenTag, Range<usize>)>;
+
+    // tokenize が pos で停止した原因となった範囲 (字句として読めない範囲が分かる場合に返す)
+    fn rejected(&self, _input: &str, _pos: usize) -> Option<Range<usize>> {
+        None
+    }
+}
+
+// Tokenizer を BaseLexer として扱うための字句解析器
+// trivia, pre_trivia オプションを持つ字句は次の字句の前に，post_trivia オプションを持つ字句は同じ行にある直前の字句の後に含める
+// (Tokenizer が読み飛ばした範囲は Trivia を持たない隙間として full に含める)
+#[derive(Debug)]
+pub struct ExternalLexer<L, Tz>
+where
+    L: Lang,
+    Tz: Tokenizer<L>,
+{
+    tokenizer: Tz,
+    _phantom: PhantomData<L>,
+}
+
+impl<L, Tz> BaseLexer<L> for ExternalLexer<L, Tz>
+where
+    L: Lang,
+    Tz: Tokenizer<L>,
+{
+    fn init() -> Result<Self> {
+        Ok(ExternalLexer {
+            tokenizer: Tz::init()?,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn run<'input>(&self, input: &'input str) -> impl Iterator<Item = Token<'input, L::TokenTag>> {
+        let mut items = self.items(input).fuse();
+        let mut full_begin = 0;
+        let mut trivia = vec![];
+        let mut pending: Option<Token<'input, L::TokenTag>> = None;
+        iter::from_fn(move || {
+            for (kind, range) in items.by_ref() {
+                let span = (range.start, range.end);
+                match (role_of(&kind), &mut pending) {
+                    (Role::PostTrivia, Some(token)) if is_same_line(input, token.full.1, span.0) && trivia.is_empty() => {
+                        token.full.1 = span.1;
+                        token.trivia.push(Trivia { kind, span });
+                    }
+                    (Role::PreTrivia | Role::PostTrivia, _) => trivia.push(Trivia { kind, span }),
+                    (Role::Body, pending) => {
+                        if let Some(token) = pending {
+                            full_begin = token.full.1;
+                        }
+                        let token = Token::new(kind, input, span, (full_begin, span.1))
+                            .with_trivia(mem::take(&mut trivia));
+                        if let Some(token) = pending.replace(token) {
+                            return Some(token);
+                        }
+                    }
+                }
+            }
+            pending.take()
+        })
+    }
+
+    fn check_rest(&self, input: &str, end: &LexState) -> Result<()> {
+        // 停止位置以降の Trivia と空白を読み飛ばした位置から字句として読めない文字を探す
+        let mut begin = skip_whitespace(input, end.pos());
+        let start = begin;
+        for (kind, range) in self.items(input).skip_while(|(_, range)| range.start < start) {
+            if range.start != begin || role_of(&kind) == Role::Body {
+                break;
+            }
+            begin = skip_whitespace(input, range.end);
+        }
+
+        // Tokenizer が読めなかった範囲が分かる場合はその範囲を報告する
+        let rejected = self.tokenizer
+            .rejected(input, begin)
+            .filter(|range| range.start == begin && range.start < range.end);
+        if let Some(range) = rejected {
+            if let Some(found) = input.get(range.clone()) {
+                return Err(Error::Lex { found: found.to_string(), span: (range.start, range.end) });
+            }
+        }
+        match input[begin..].chars().next() {
+            Some(c) => Err(Error::Lex {
+                found: c.to_string(),
+                span: (begin, begin + c.len_utf8()),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<L, Tz> ExternalLexer<L, Tz>
+where
+    L: Lang,
+    Tz: Tokenizer<L>,
+{
+    // 位置が前の字句と重なる・入力の範囲外である・文字の境界に位置しない場合はそこで打ち切る
+    fn items<'input>(&self, input: &'input str) -> impl Iterator<Item = (L::TokenTag, Range<usize>)> + use<'_, 'input, L, Tz> {
+        let mut last_end = 0;
+        self.tokenizer
+            .tokenize(input)
+            .map_while(move |(kind, range)| {
+                let valid = last_end <= range.start
+                    && range.start <= range.end
+                    && range.end <= input.len()
+                    && input.is_char_boundary(range.start)
+                    && input.is_char_boundary(range.end);
+                if !valid {
+                    return None;
+                }
+                last_end = range.end;
+                Some((kind, range))
+            })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Role {
+    PreTrivia,
+    PostTrivia,
+    Body,
+}
+
+fn role_of<T: TokenTag>(kind: &T) -> Role {
+    let opts = kind.as_option_list();
+    if opts.contains(&"post_trivia") {
+        Role::PostTrivia
+    } else if opts.contains(&"trivia") || opts.contains(&"pre_trivia") {
+        Role::PreTrivia
+    } else {
+        Role::Body
+    }
+}
+
+fn is_same_line(input: &str, begin: usize, end: usize) -> bool {
+    !input[begin..end].contains('\n')
+}
+
+fn skip_whitespace(input: &str, pos: usize) -> usize {
+    let rest = &input[pos..];
+    pos + rest.len() - rest.trim_start().len()
+}
@@ -0,0 +1,267 @@
+use std::fmt::{self, Debug};
+use std::iter;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use copager_lang::token::{Token, TokenOwned};
+use copager_lang::Lang;
+
+// 字句解析器から構文解析器へ渡される字句列
+// (S は入力の型)
+pub type TokenStream<'a, 'input, T, S = str> = Box<dyn Iterator<Item = Token<'input, T, S>> + 'a>;
+
+// 取り除いた字句の記録先
+pub type TokenRecord<T> = Arc<Mutex<Vec<TokenOwned<T>>>>;
+
+// 字句解析器と構文解析器の間で字句列を書き換える
+pub trait TokenFilter<L: Lang> {
+    fn apply<'a, 'input: 'a>(
+        &'a self,
+        tokens: TokenStream<'a, 'input, L::TokenTag>,
+    ) -> TokenStream<'a, 'input, L::TokenTag>;
+}
+
+// 登録された順に適用されるフィルタの列
+pub struct FilterChain<L: Lang> {
+    filters: Vec<Box<dyn TokenFilter<L> + Send + Sync>>,
+}
+
+impl<L: Lang> Default for FilterChain<L> {
+    fn default() -> Self {
+        FilterChain { filters: vec![] }
+    }
+}
+
+impl<L: Lang> Debug for FilterChain<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterChain")
+            .field("len", &self.filters.len())
+            .finish()
+    }
+}
+
+impl<L: Lang> FilterChain<L> {
+    pub fn push<F>(&mut self, filter: F)
+    where
+        F: TokenFilter<L> + Send + Sync + 'static,
+    {
+        self.filters.push(Box::new(filter));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn apply<'a, 'input: 'a>(
+        &'a self,
+        tokens: TokenStream<'a, 'input, L::TokenTag>,
+    ) -> TokenStream<'a, 'input, L::TokenTag> {
+        self.filters
+            .iter()
+            .fold(tokens, |tokens, filter| filter.apply(tokens))
+    }
+}
+
+// 改行の直前の字句が after に含まれる場合に区切り記号を挿入する (Go の自動セミコロン挿入に相当)
+// 次の字句が unless_before に含まれる場合は挿入しない
+#[derive(Debug)]
+pub struct SemicolonInsertion<L: Lang> {
+    semicolon: L::TokenTag,
+    after: Vec<L::TokenTag>,
+    unless_before: Vec<L::TokenTag>,
+    _phantom: PhantomData<L>,
+}
+
+impl<L: Lang> SemicolonInsertion<L> {
+    pub fn new(semicolon: L::TokenTag) -> Self {
+        SemicolonInsertion {
+            semicolon,
+            after: vec![],
+            unless_before: vec![],
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn after(mut self, kinds: impl IntoIterator<Item = L::TokenTag>) -> Self {
+        self.after.extend(kinds);
+        self
+    }
+
+    pub fn unless_before(mut self, kinds: impl IntoIterator<Item = L::TokenTag>) -> Self {
+        self.unless_before.extend(kinds);
+        self
+    }
+}
+
+impl<L: Lang> TokenFilter<L> for SemicolonInsertion<L> {
+    fn apply<'a, 'input: 'a>(
+        &'a self,
+        tokens: TokenStream<'a, 'input, L::TokenTag>,
+    ) -> TokenStream<'a, 'input, L::TokenTag> {
+        let mut tokens = tokens.fuse();
+        // 直前の字句の (入力, body.1) (after に含まれる字句の場合のみ)
+        let mut last: Option<(&'input str, usize)> = None;
+        let mut queued = None;
+        Box::new(iter::from_fn(move || {
+            if let Some(token) = queued.take() {
+                return Some(token);
+            }
+            let Some(token) = tokens.next() else {
+                // 入力の終端も改行として扱う
+                return last
+                    .take()
+                    .map(|(src, end)| Token::new(self.semicolon.clone(), src, (end, end), (end, end)));
+            };
+
+            let inserted = last
+                .filter(|(src, end)| src[*end..token.body.0].contains('\n'))
+                .filter(|_| !self.unless_before.contains(&token.kind))
+                .map(|(src, end)| Token::new(self.semicolon.clone(), src, (end, end), (end, end)));
+            last = match self.after.contains(&token.kind) {
+                true => Some((token.src, token.body.1)),
+                false => None,
+            };
+            match inserted {
+                Some(inserted) => {
+                    queued = Some(token);
+                    Some(inserted)
+                }
+                None => Some(token),
+            }
+        }))
+    }
+}
+
+// 直前の字句が after に含まれる場合に from の字句を to に置き換える (文脈に応じたキーワードの識別子化など)
+#[derive(Debug)]
+pub struct RetagToken<L: Lang> {
+    from: Vec<L::TokenTag>,
+    to: L::TokenTag,
+    after: Vec<L::TokenTag>,
+    _phantom: PhantomData<L>,
+}
+
+impl<L: Lang> RetagToken<L> {
+    pub fn new(from: impl IntoIterator<Item = L::TokenTag>, to: L::TokenTag) -> Self {
+        RetagToken {
+            from: from.into_iter().collect(),
+            to,
+            after: vec![],
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn after(mut self, kinds: impl IntoIterator<Item = L::TokenTag>) -> Self {
+        self.after.extend(kinds);
+        self
+    }
+}
+
+impl<L: Lang> TokenFilter<L> for RetagToken<L> {
+    fn apply<'a, 'input: 'a>(
+        &'a self,
+        tokens: TokenStream<'a, 'input, L::TokenTag>,
+    ) -> TokenStream<'a, 'input, L::TokenTag> {
+        let mut retag = false;
+        Box::new(tokens.map(move |mut token| {
+            if retag && self.from.contains(&token.kind) {
+                token.kind = self.to.clone();
+            }
+            retag = self.after.contains(&token.kind);
+            token
+        }))
+    }
+}
+
+// 1 つの字句を指定したバイト長の字句の列に分割する (">>" を 2 つの ">" として扱うなど)
+// 長さの合計が字句の長さと一致しない場合は分割しない
+#[derive(Debug)]
+pub struct SplitToken<L: Lang> {
+    target: L::TokenTag,
+    parts: Vec<(L::TokenTag, usize)>,
+    _phantom: PhantomData<L>,
+}
+
+impl<L: Lang> SplitToken<L> {
+    pub fn new(target: L::TokenTag, parts: impl IntoIterator<Item = (L::TokenTag, usize)>) -> Self {
+        SplitToken {
+            target,
+            parts: parts.into_iter().collect(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<L: Lang> TokenFilter<L> for SplitToken<L> {
+    fn apply<'a, 'input: 'a>(
+        &'a self,
+        tokens: TokenStream<'a, 'input, L::TokenTag>,
+    ) -> TokenStream<'a, 'input, L::TokenTag> {
+        let total = self.parts.iter().map(|(_, len)| len).sum::<usize>();
+        Box::new(tokens.flat_map(move |token| {
+            if token.kind != self.target || token.body.1 - token.body.0 != total {
+                return vec![token];
+            }
+
+            // Trivia は先頭と末尾の字句に含める
+            let mut begin = token.body.0;
+            let last = self.parts.len() - 1;
+            self.parts
+                .iter()
+                .enumerate()
+                .map(|(idx, (kind, len))| {
+                    let body = (begin, begin + len);
+                    let full_begin = if idx == 0 { token.full.0 } else { body.0 };
+                    let full_end = if idx == last { token.full.1 } else { body.1 };
+                    begin += len;
+                    let trivia = token.trivia
+                        .iter()
+                        .filter(|trivia| full_begin <= trivia.span.0 && trivia.span.1 <= full_end)
+                        .cloned()
+                        .collect();
+                    Token::new(kind.clone(), token.src, body, (full_begin, full_end)).with_trivia(trivia)
+                })
+                .collect()
+        }))
+    }
+}
+
+// 指定した字句を取り除く (record_to を指定した場合は取り除いた字句を記録する)
+#[derive(Debug)]
+pub struct DropToken<L: Lang> {
+    kinds: Vec<L::TokenTag>,
+    record: Option<TokenRecord<L::TokenTag>>,
+    _phantom: PhantomData<L>,
+}
+
+impl<L: Lang> DropToken<L> {
+    pub fn new(kinds: impl IntoIterator<Item = L::TokenTag>) -> Self {
+        DropToken {
+            kinds: kinds.into_iter().collect(),
+            record: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn record_to(mut self, record: TokenRecord<L::TokenTag>) -> Self {
+        self.record = Some(record);
+        self
+    }
+}
+
+impl<L: Lang> TokenFilter<L> for DropToken<L> {
+    fn apply<'a, 'input: 'a>(
+        &'a self,
+        tokens: TokenStream<'a, 'input, L::TokenTag>,
+    ) -> TokenStream<'a, 'input, L::TokenTag> {
+        Box::new(tokens.filter(move |token| {
+            if !self.kinds.contains(&token.kind) {
+                return true;
+            }
+            if let Some(record) = &self.record {
+                record.lock().unwrap().push(TokenOwned::from_token(token, 0));
+            }
+            false
+        }))
+    }
+}
@@ -0,0 +1,165 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::iter;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use copager_lang::token::{Token, TokenSet, TokenTag};
+use copager_lang::Lang;
+use copager_utils::error::{Error, GrammarError, Result};
+
+use crate::mode::LexState;
+use crate::BaseLexer;
+
+const TAB_WIDTH: usize = 8;
+
+// 行頭の字下げを追跡し，Indent・Dedent・Newline を表す字句を挿入する字句解析器
+// (各字句は TokenSet 上で indent, dedent, newline オプションを持つ字句として定義する)
+#[derive(Debug)]
+pub struct IndentLexer<L, Lx>
+where
+    L: Lang,
+    Lx: BaseLexer<L>,
+{
+    lexer: Lx,
+    indent: L::TokenTag,
+    dedent: L::TokenTag,
+    newline: Option<L::TokenTag>,
+    _phantom: PhantomData<L>,
+}
+
+impl<L, Lx> BaseLexer<L> for IndentLexer<L, Lx>
+where
+    L: Lang,
+    Lx: BaseLexer<L>,
+{
+    fn init() -> Result<Self> {
+        Ok(IndentLexer {
+            lexer: Lx::init()?,
+            indent: find_token_by_opt::<L>("indent")?,
+            dedent: find_token_by_opt::<L>("dedent")?,
+            newline: find_token_by_opt::<L>("newline").ok(),
+            _phantom: PhantomData,
+        })
+    }
+
+    // 字下げの誤りは記録されないため，誤りを報告する場合は run_tracked を用いる
+    fn run<'input>(&self, input: &'input str) -> impl Iterator<Item = Token<'input, L::TokenTag>> {
+        self.tokens(input, |_| {})
+    }
+
+    fn run_tracked<'input>(&self, input: &'input str, lexed: Rc<RefCell<LexState>>)
+        -> impl Iterator<Item = Token<'input, L::TokenTag>>
+    {
+        let on_error = {
+            let lexed = Rc::clone(&lexed);
+            move |span| lexed.borrow_mut().fail_indent(span)
+        };
+        self.tokens(input, on_error)
+            .inspect(move |token| lexed.borrow_mut().advance(token))
+    }
+
+    fn check_rest(&self, input: &str, end: &LexState) -> Result<()> {
+        // 字下げの誤りで停止した場合はその誤りを返す
+        if let Some(span) = end.indent_error() {
+            return Err(Error::Indent { span });
+        }
+        self.lexer.check_rest(input, end)
+    }
+}
+
+impl<L, Lx> IndentLexer<L, Lx>
+where
+    L: Lang,
+    Lx: BaseLexer<L>,
+{
+    // 字下げの誤りを見つけた場合は on_error にその範囲を渡して停止する
+    fn tokens<'input, F>(&self, input: &'input str, mut on_error: F)
+        -> impl Iterator<Item = Token<'input, L::TokenTag>> + use<'_, 'input, L, Lx, F>
+    where
+        F: FnMut((usize, usize)),
+    {
+        let mut tokens = self.lexer.run(input);
+        let mut levels = vec![0];
+        let mut last: Option<(usize, usize)> = None;  // 直前の字句の (body.1, full.1)
+        let mut queue = VecDeque::new();
+        let mut finished = false;
+        iter::from_fn(move || loop {
+            if let Some(token) = queue.pop_front() {
+                return Some(token);
+            }
+            if finished {
+                return None;
+            }
+
+            let Some(token) = tokens.next() else {
+                // 入力の終端では開いている字下げを全て閉じる
+                finished = true;
+                if let Some((_, full_end)) = last {
+                    if let Some(newline) = &self.newline {
+                        queue.push_back(self.synthetic(newline, input, full_end));
+                    }
+                    for _ in 1..levels.len() {
+                        queue.push_back(self.synthetic(&self.dedent, input, full_end));
+                    }
+                }
+                continue;
+            };
+
+            let is_line_head = match last {
+                Some((body_end, _)) => input[body_end..token.body.0].contains('\n'),
+                None => true,
+            };
+            if is_line_head {
+                let pos = token.body.0;
+                if let (Some(newline), Some(_)) = (&self.newline, last) {
+                    queue.push_back(self.synthetic(newline, input, pos));
+                }
+
+                let (line_begin, width) = indent_width(input, pos);
+                if width > levels[levels.len() - 1] {
+                    levels.push(width);
+                    queue.push_back(self.synthetic(&self.indent, input, pos));
+                }
+                while width < levels[levels.len() - 1] {
+                    levels.pop();
+                    queue.push_back(self.synthetic(&self.dedent, input, pos));
+                }
+                if width != levels[levels.len() - 1] {
+                    // 誤りのある行に対する Newline・Dedent は返さない
+                    on_error((line_begin, pos));
+                    queue.clear();
+                    finished = true;
+                    continue;
+                }
+            }
+
+            last = Some((token.body.1, token.full.1));
+            queue.push_back(token);
+        })
+    }
+
+    fn synthetic<'input>(&self, kind: &L::TokenTag, input: &'input str, pos: usize) -> Token<'input, L::TokenTag> {
+        Token::new(kind.clone(), input, (pos, pos), (pos, pos))
+    }
+}
+
+fn find_token_by_opt<L: Lang>(opt: &str) -> Result<L::TokenTag> {
+    L::TokenSet::instantiate()
+        .iter()
+        .find(|token| token.as_option_list().contains(&opt))
+        .ok_or_else(|| Error::from(GrammarError::MissingToken { option: opt.to_string() }))
+}
+
+// pos を含む行の行頭位置と字下げの幅
+fn indent_width(input: &str, pos: usize) -> (usize, usize) {
+    let line_begin = input[..pos].rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let width = input[line_begin..pos]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .fold(0, |width, c| match c {
+            '\t' => (width / TAB_WIDTH + 1) * TAB_WIDTH,
+            _ => width + 1,
+        });
+    (line_begin, width)
+}
@@ -1,12 +1,72 @@
-use copager_lang::token::Token;
+mod external;
+mod filter;
+mod indent;
+#[cfg(feature = "logos")]
+mod logos_lexer;
+pub mod mode;
+pub mod pattern;
+mod stream;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use copager_lang::token::{Token, TokenSource};
 use copager_lang::Lang;
+use copager_utils::error::Result;
+
+use mode::LexState;
+
+pub use external::{Tokenizer, ExternalLexer};
+pub use filter::{TokenFilter, TokenStream, TokenRecord, FilterChain};
+pub use filter::{SemicolonInsertion, RetagToken, SplitToken, DropToken};
+pub use indent::IndentLexer;
+#[cfg(feature = "logos")]
+pub use logos_lexer::{LogosLexer, LogosTokenizer};
+pub use stream::{StreamLexer, LexWindow, OwnedTokens};
 
-pub trait BaseLexer<L>
+// S は入力の型 (バイト列を字句解析する場合は [u8])
+pub trait BaseLexer<L, S = str>
 where
     Self: Sized,
     L: Lang,
+    S: TokenSource + ?Sized,
+{
+    fn init() -> Result<Self>;
+    fn run<'input>(&self, input: &'input S)
+        -> impl Iterator<Item = Token<'input, L::TokenTag, S>>;
+
+    // run と同様に字句解析を行い，返した字句を lexed に適用する
+    // (字句解析器が誤りにより停止した場合はその誤りも lexed に記録し，check_rest で返す)
+    fn run_tracked<'input>(&self, input: &'input S, lexed: Rc<RefCell<LexState>>)
+        -> impl Iterator<Item = Token<'input, L::TokenTag, S>>
+    {
+        self.run(input).inspect(move |token| lexed.borrow_mut().advance(token))
+    }
+
+    // run が end で停止した際，残りの入力が字句として不正であればエラーを返す
+    fn check_rest(&self, _input: &S, _end: &LexState) -> Result<()> {
+        Ok(())
+    }
+}
+
+// 構文解析器から受理可能な字句を受け取りながら字句解析を行う
+pub trait ContextualLexer<L>
+where
+    Self: BaseLexer<L>,
+    L: Lang,
 {
-    fn init() -> anyhow::Result<Self>;
-    fn run<'input>(&self, input: &'input str)
-        -> impl Iterator<Item = Token<'input, L::TokenTag>>;
+    type State: Clone;
+
+    fn start(&self) -> Self::State;
+
+    // acceptable に含まれる字句のみを試して次の字句を切り出す (None の場合は全ての字句を試す)
+    fn next_token<'input>(
+        &self,
+        state: &mut Self::State,
+        input: &'input str,
+        acceptable: Option<&[L::TokenTag]>,
+    ) -> Option<Token<'input, L::TokenTag>>;
+
+    // 入力の先頭から offset バイトを取り除いた場合の状態に変換する (StreamLexer が用いる)
+    fn rebase(state: &mut Self::State, offset: usize);
 }
@@ -0,0 +1,102 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use logos::Logos;
+
+use copager_lang::Lang;
+use copager_utils::error::Result;
+
+use crate::external::{ExternalLexer, Tokenizer};
+
+// logos で定義された字句解析器を用いる字句解析器
+pub type LogosLexer<L, Tk> = ExternalLexer<L, LogosTokenizer<L, Tk>>;
+
+// logos の字句を TryFrom により TokenTag へ変換する
+// (変換できない字句とエラーはそこで字句解析を打ち切り，check_rest がその字句を報告する)
+// 変換は logos_tokens! により列挙子名で対応付けて生成できる
+#[derive(Debug)]
+pub struct LogosTokenizer<L, Tk>
+where
+    L: Lang,
+{
+    _phantom: PhantomData<(L, Tk)>,
+}
+
+impl<L, Tk> Tokenizer<L> for LogosTokenizer<L, Tk>
+where
+    L: Lang,
+    L::TokenTag: TryFrom<Tk>,
+    Tk: for<'s> Logos<'s, Source = str>,
+    for<'s> <Tk as Logos<'s>>::Extras: Default,
+{
+    fn init() -> Result<Self> {
+        Ok(LogosTokenizer { _phantom: PhantomData })
+    }
+
+    fn tokenize(&self, input: &str)
+        -> impl Iterator<Item = (L::TokenTag, Range<usize>)>
+    {
+        Tk::lexer(input)
+            .spanned()
+            .map_while(|(token, span)| {
+                let kind = L::TokenTag::try_from(token.ok()?).ok()?;
+                Some((kind, span))
+            })
+    }
+
+    fn rejected(&self, input: &str, pos: usize) -> Option<Range<usize>> {
+        Tk::lexer(input)
+            .spanned()
+            .skip_while(|(_, span)| span.start < pos)
+            .find_map(|(token, span)| {
+                let converted = token.ok().and_then(|token| L::TokenTag::try_from(token).ok());
+                match converted {
+                    Some(_) => None,
+                    None => Some(span),
+                }
+            })
+    }
+}
+
+// logos の字句の定義を包み，同じ名前の TokenTag の列挙子への変換 (From) を生成する
+// (#[tokenset(..)] で変換先の TokenTag を指定する)
+//
+// logos_tokens! {
+//     #[tokenset(ExprToken)]
+//     #[derive(Logos)]
+//     enum LogosToken {
+//         #[token("+")]
+//         Plus,
+//         #[regex("[0-9]+", |lex| lex.slice().parse::<u32>().ok())]
+//         Num(u32),
+//     }
+// }
+#[macro_export]
+macro_rules! logos_tokens {
+    (
+        #[tokenset($tag:ident)]
+        $(#[$($attr:tt)*])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$($variant_attr:tt)*])*
+                $variant:ident $(($($field:tt)*))?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$($attr)*])*
+        $vis enum $name {
+            $(
+                $(#[$($variant_attr)*])*
+                $variant $(($($field)*))?,
+            )*
+        }
+
+        impl ::core::convert::From<$name> for $tag {
+            fn from(token: $name) -> Self {
+                match token {
+                    $($name::$variant { .. } => $tag::$variant,)*
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,125 @@
+use copager_lang::token::{ModeAction, Token, TokenSet, TokenSource, TokenTag, DEFAULT_MODE};
+use copager_utils::error::{Error, GrammarError, Result};
+
+// 字句解析中のモードのスタック
+#[derive(Debug, Clone)]
+pub struct ModeStack(Vec<&'static str>);
+
+impl Default for ModeStack {
+    fn default() -> Self {
+        ModeStack::new()
+    }
+}
+
+impl ModeStack {
+    pub fn new() -> Self {
+        ModeStack(vec![DEFAULT_MODE])
+    }
+
+    pub fn current(&self) -> &'static str {
+        self.0[self.0.len() - 1]
+    }
+
+    // 最後のモードは取り除かない
+    pub fn apply(&mut self, action: Option<ModeAction>) {
+        match action {
+            Some(ModeAction::Push(mode)) => self.0.push(mode),
+            Some(ModeAction::Pop) if self.0.len() > 1 => { self.0.pop(); }
+            Some(ModeAction::Set(mode)) => *self.0.last_mut().unwrap() = mode,
+            _ => {}
+        }
+    }
+}
+
+// 字句解析を停止した位置とその時点のモード (run が返した字句を順に適用して求める)
+#[derive(Debug, Clone, Default)]
+pub struct LexState {
+    pos: usize,
+    modes: ModeStack,
+    indent_error: Option<(usize, usize)>,  // 字下げの誤りで停止した場合の範囲 (run_tracked が記録する)
+}
+
+impl LexState {
+    pub fn new() -> Self {
+        LexState::default()
+    }
+
+    pub fn after<'input, T, S>(tokens: impl IntoIterator<Item = Token<'input, T, S>>) -> Self
+    where
+        T: TokenTag,
+        S: TokenSource + ?Sized + 'input,
+    {
+        let mut state = LexState::new();
+        for token in tokens {
+            state.advance(&token);
+        }
+        state
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn mode(&self) -> &'static str {
+        self.modes.current()
+    }
+
+    // token を読んだ直後の状態に進める
+    pub fn advance<T, S>(&mut self, token: &Token<'_, T, S>)
+    where
+        T: TokenTag,
+        S: TokenSource + ?Sized,
+    {
+        self.pos = token.full.1;
+        self.modes.apply(token.kind.as_mode_action());
+    }
+
+    pub fn indent_error(&self) -> Option<(usize, usize)> {
+        self.indent_error
+    }
+
+    pub fn fail_indent(&mut self, span: (usize, usize)) {
+        self.indent_error = Some(span);
+    }
+
+    // 入力の先頭から offset バイトを取り除いた場合の状態に変換する
+    pub fn rebase(&mut self, offset: usize) {
+        self.pos -= offset;
+    }
+}
+
+pub type ModeTokens<T> = (&'static str, Vec<T>);
+
+// モードごとに有効な字句の一覧を作成する
+pub fn split_by_mode<Ts: TokenSet>(tokens: &Ts) -> Result<Vec<ModeTokens<Ts::Tag>>> {
+    let mut modes: Vec<ModeTokens<Ts::Tag>> = vec![(DEFAULT_MODE, vec![])];
+    for token in tokens.iter() {
+        let token_modes: &[&'static str] = match token.as_mode_list() {
+            [] => &[DEFAULT_MODE],
+            token_modes => token_modes,
+        };
+        for &mode in token_modes {
+            match modes.iter_mut().find(|(name, _)| *name == mode) {
+                Some((_, mode_tokens)) => mode_tokens.push(token.clone()),
+                None => modes.push((mode, vec![token.clone()])),
+            }
+        }
+    }
+
+    // 切り替え先のモードが存在するか確認
+    for token in tokens.iter() {
+        let mode = match token.as_mode_action() {
+            Some(ModeAction::Push(mode)) | Some(ModeAction::Set(mode)) => mode,
+            _ => continue,
+        };
+        if !modes.iter().any(|(name, _)| *name == mode) {
+            let err = GrammarError::UndefinedMode {
+                mode: mode.to_string(),
+                token: token.as_name().to_string(),
+            };
+            return Err(Error::from(err));
+        }
+    }
+
+    Ok(modes)
+}
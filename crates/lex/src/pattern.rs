@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+use copager_lang::token::{TokenMatcher, TokenTag};
+use copager_utils::error::{Error, GrammarError, Result};
+
+// XID_Start (または _) と XID_Continue の列からなる識別子
+pub const UNICODE_IDENT: &str = r"[\p{XID_Start}_]\p{XID_Continue}*";
+
+// 字句のオプション (ignore_case, unicode_ident) を反映したパターン
+// (word の扱いは字句解析器ごとに異なるため含めない)
+pub fn to_token_pattern<T: TokenTag>(token: &T) -> String {
+    let opts = token.as_option_list();
+
+    let mut str_list = token.as_str_list().to_vec();
+    if opts.contains(&"unicode_ident") {
+        str_list.push(UNICODE_IDENT);
+    }
+    let pattern = str_list.join("|");
+    match opts.contains(&"ignore_case") {
+        true => format!("(?i:{})", pattern),
+        false => format!("(?:{})", pattern),
+    }
+}
+
+pub fn is_word<T: TokenTag>(token: &T) -> bool {
+    token.as_option_list().contains(&"word")
+}
+
+// at が Unicode の単語境界 (\b) であるか
+// (前後の文字の一方のみが単語の文字である位置，UTF-8 として不正なバイトは単語の文字として扱わない)
+pub fn is_word_boundary(input: &[u8], at: usize) -> bool {
+    let prev = (1..=at.min(4))
+        .find_map(|len| std::str::from_utf8(&input[at - len..at]).ok()?.chars().next_back());
+    let next = (1..=(input.len() - at).min(4))
+        .find_map(|len| std::str::from_utf8(&input[at..at + len]).ok()?.chars().next());
+    let is_word_char = |c: Option<char>| c.is_some_and(regex_syntax::is_word_character);
+    is_word_char(prev) != is_word_char(next)
+}
+
+// 空の一致や文字の境界に位置しない長さは一致しなかったものとして扱う
+pub fn run_matcher(matcher: TokenMatcher, s: &str) -> Option<usize> {
+    match matcher(s) {
+        Some(len) if len > 0 && s.is_char_boundary(len) => Some(len),
+        _ => None,
+    }
+}
+
+// パターンを持たない字句 (IndentLexer などにより生成される字句) は字句解析の対象としない
+pub fn is_synthetic<T: TokenTag>(token: &T) -> bool {
+    token.as_str_list().is_empty()
+        && token.as_matcher().is_none()
+        && !token.as_option_list().contains(&"unicode_ident")
+}
+
+pub fn invalid_pattern(token: &str, pattern: &str, message: impl Display) -> Error {
+    let err = GrammarError::InvalidPattern {
+        token: token.to_string(),
+        pattern: pattern.to_string(),
+        message: message.to_string(),
+    };
+    Error::from(err)
+}
+
+// Trivia を読み飛ばした位置 begin に文字が残っている場合は，その文字を字句として読めない文字として報告する
+pub fn check_rest_from(input: &str, begin: usize) -> Result<()> {
+    match input[begin..].chars().next() {
+        Some(c) => Err(Error::Lex {
+            found: c.to_string(),
+            span: (begin, begin + c.len_utf8()),
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::marker::PhantomData;
+
+use copager_lang::token::{Token, TokenOwned, Trivia};
+use copager_lang::Lang;
+use copager_utils::error::{Error, Result};
+
+use crate::mode::LexState;
+use crate::ContextualLexer;
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+// io::Read から少しずつ入力を読み込みながら字句解析を行う
+// (読み込み済みの範囲の末尾にある字句は続きの入力によって変化しうるため，次の入力を読み込むまで確定させない)
+// 字句解析器の状態 (モードなど) は確定した字句の直後から引き継ぐ
+pub struct StreamLexer<'lexer, L, Lx, R>
+where
+    L: Lang,
+    Lx: ContextualLexer<L>,
+    R: Read,
+{
+    lexer: &'lexer Lx,
+    reader: R,
+    chunk_size: usize,
+
+    // 未確定の入力
+    buf: String,
+    pending: Vec<u8>,
+    offset: usize,
+    consumed: usize,
+
+    // 確定した最後の字句の直後における字句解析器の状態
+    lex_state: Lx::State,
+    lexed: LexState,
+
+    // 未確定の入力から読み進めた字句
+    scanned: Vec<Scanned<L, Lx::State>>,
+
+    // 状態
+    eof: bool,
+    finished: bool,
+    error: Option<Error>,
+
+    _phantom: PhantomData<L>,
+}
+
+// 読み進めた字句 (buf を参照しない形で保持する) とその直後の状態
+struct Scanned<L: Lang, S> {
+    kind: L::TokenTag,
+    body: (usize, usize),
+    full: (usize, usize),
+    trivia: Vec<Trivia<L::TokenTag>>,
+    lex_state: S,
+    lexed: LexState,
+}
+
+// 確定した字句の列 (位置は src の先頭から数えたもの)
+pub struct LexWindow<'window, L: Lang> {
+    pub src: &'window str,
+    pub offset: usize,
+    pub tokens: Vec<Token<'window, L::TokenTag>>,
+}
+
+impl<'lexer, L, Lx, R> StreamLexer<'lexer, L, Lx, R>
+where
+    L: Lang,
+    Lx: ContextualLexer<L>,
+    R: Read,
+{
+    pub fn new(lexer: &'lexer Lx, reader: R) -> Self {
+        StreamLexer {
+            lexer,
+            reader,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buf: String::new(),
+            pending: vec![],
+            offset: 0,
+            consumed: 0,
+            lex_state: lexer.start(),
+            lexed: LexState::new(),
+            scanned: vec![],
+            eof: false,
+            finished: false,
+            error: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub fn next_window(&mut self) -> Result<Option<LexWindow<'_, L>>> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        if self.finished {
+            return Ok(None);
+        }
+
+        // 前回確定した範囲を破棄
+        self.buf.drain(..self.consumed);
+        self.offset += self.consumed;
+        Lx::rebase(&mut self.lex_state, self.consumed);
+        self.lexed.rebase(self.consumed);
+        self.consumed = 0;
+
+        // 末尾の字句を除いて 1 つ以上の字句が確定するまで読み込む
+        // (読み進めた字句は保持しておき，読み込みのたびに末尾の字句の直前からのみ読み直す)
+        self.scanned.clear();
+        let count = loop {
+            self.scanned.pop();
+            let (mut lex_state, mut lexed) = match self.scanned.last() {
+                Some(scanned) => (scanned.lex_state.clone(), scanned.lexed.clone()),
+                None => (self.lex_state.clone(), self.lexed.clone()),
+            };
+            while let Some(token) = self.lexer.next_token(&mut lex_state, &self.buf, None) {
+                lexed.advance(&token);
+                self.scanned.push(Scanned {
+                    kind: token.kind,
+                    body: token.body,
+                    full: token.full,
+                    trivia: token.trivia,
+                    lex_state: lex_state.clone(),
+                    lexed: lexed.clone(),
+                });
+            }
+
+            if self.eof {
+                if let Err(err) = self.lexer.check_rest(&self.buf, &lexed) {
+                    self.error = Some(err.shift(self.offset));
+                }
+                self.finished = true;
+                break self.scanned.len();
+            }
+
+            if self.scanned.len() >= 2 {
+                break self.scanned.len() - 1;
+            }
+
+            self.read_chunk()?;
+        };
+
+        // 確定した最後の字句の直後の状態を引き継ぐ
+        if let Some(last) = count.checked_sub(1).map(|idx| &self.scanned[idx]) {
+            self.lex_state = last.lex_state.clone();
+            self.lexed = last.lexed.clone();
+            self.consumed = self.lexed.pos();
+        }
+
+        let src = &self.buf[..];
+        let tokens = self.scanned
+            .drain(..count)
+            .map(|scanned| Token {
+                kind: scanned.kind,
+                src,
+                body: scanned.body,
+                full: scanned.full,
+                trivia: scanned.trivia,
+            })
+            .collect();
+        Ok(Some(LexWindow { src, offset: self.offset, tokens }))
+    }
+
+    pub fn owned_tokens(self) -> OwnedTokens<'lexer, L, Lx, R> {
+        OwnedTokens {
+            stream: self,
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn read_chunk(&mut self) -> Result<()> {
+        // 長い字句が続く場合に読み直しの回数が増えないよう，未確定の入力の長さに応じて読み込む量を増やす
+        let mut chunk = vec![0; self.chunk_size.max(self.buf.len())];
+        let len = self.reader.read(&mut chunk)?;
+        if len == 0 {
+            self.eof = true;
+            if !self.pending.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(());
+        }
+
+        // UTF-8 として不完全な末尾のバイト列は次回に持ち越す
+        self.pending.extend_from_slice(&chunk[..len]);
+        let valid_len = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        let valid = std::str::from_utf8(&self.pending[..valid_len]).unwrap();
+        self.buf.push_str(valid);
+        self.pending.drain(..valid_len);
+
+        Ok(())
+    }
+}
+
+// StreamLexer の結果を所有権を持つ字句として 1 つずつ返すイテレータ
+pub struct OwnedTokens<'lexer, L, Lx, R>
+where
+    L: Lang,
+    Lx: ContextualLexer<L>,
+    R: Read,
+{
+    stream: StreamLexer<'lexer, L, Lx, R>,
+    queue: VecDeque<TokenOwned<L::TokenTag>>,
+}
+
+impl<L, Lx, R> Iterator for OwnedTokens<'_, L, Lx, R>
+where
+    L: Lang,
+    Lx: ContextualLexer<L>,
+    R: Read,
+{
+    type Item = Result<TokenOwned<L::TokenTag>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queue.is_empty() {
+            match self.stream.next_window() {
+                Ok(Some(window)) => {
+                    let tokens = window.tokens
+                        .iter()
+                        .map(|token| TokenOwned::from_token(token, window.offset));
+                    self.queue.extend(tokens);
+                }
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.queue.pop_front().map(Ok)
+    }
+}
+
+fn invalid_utf8() -> Error {
+    let err = io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8");
+    Error::from(err)
+}
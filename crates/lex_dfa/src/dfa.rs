@@ -0,0 +1,248 @@
+use std::cmp::Reverse;
+
+use regex_automata::dfa::{dense, Automaton, StartKind};
+use regex_automata::util::primitives::StateID;
+use regex_automata::util::syntax;
+use regex_automata::{Anchored, Input, MatchKind};
+use serde::{Serialize, Deserialize};
+
+use copager_lang::token::{Token, TokenMatcher, TokenTag, Trivia};
+use copager_lex::pattern::{invalid_pattern, is_synthetic, is_word, is_word_boundary, run_matcher, to_token_pattern};
+use copager_utils::error::Result;
+
+// パターンが表す字句の役割
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Role {
+    PreTrivia,
+    PostTrivia,
+    Body,
+}
+
+// モードごとの DFA (全ての字句のパターンを 1 つの DFA にまとめる)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+pub(crate) struct DfaMode<T: TokenTag> {
+    dfa: SerializedDfa,
+    patterns: Vec<(T, Role, usize)>,  // パターン ID ごとの (字句, 役割, 優先度)
+    #[serde(skip)]
+    matchers: Vec<(TokenMatcher, T, Role, usize)>,
+}
+
+impl<T: TokenTag> DfaMode<T> {
+    pub fn init(tokens: &[T]) -> Result<Self> {
+        let mut regexes = vec![];
+        let mut patterns = vec![];
+        let mut matchers = vec![];
+        for (priority, token, role) in with_roles(tokens) {
+            match token.as_matcher() {
+                Some(matcher) => matchers.push((matcher, token.clone(), role, priority)),
+                None => {
+                    let regex = to_token_pattern(token);
+                    if let Err(err) = syntax::parse(&regex) {
+                        return Err(invalid_pattern(token.as_name(), &regex, err));
+                    }
+                    regexes.push(regex);
+                    patterns.push((token.clone(), role, priority));
+                }
+            }
+        }
+
+        // 同じ位置で終わる全てのパターンを受理状態に残し，探索時に優先度で選ぶ
+        let dfa = dense::Builder::new()
+            .configure(dense::Config::new()
+                .match_kind(MatchKind::All)
+                .start_kind(StartKind::Anchored)
+                .minimize(true))
+            .build_many(&regexes)
+            .map_err(|err| invalid_pattern("", &regexes.join("|"), err))?;
+
+        Ok(DfaMode {
+            dfa: SerializedDfa(dfa),
+            patterns,
+            matchers,
+        })
+    }
+
+    // キャッシュから復元した場合は関数による字句を定義から取り出し直す
+    pub fn restore(mut self, tokens: &[T]) -> Self {
+        self.matchers = with_roles(tokens)
+            .filter_map(|(priority, token, role)| Some((token.as_matcher()?, token.clone(), role, priority)))
+            .collect();
+        self
+    }
+
+    pub fn extract_token<'input>(&self, src: &'input str, begin: usize) -> Option<Token<'input, T>> {
+        self.extract_token_by(src, begin, |_| true)
+    }
+
+    pub fn extract_token_by<'input, F>(&self, src: &'input str, begin: usize, filter: F) -> Option<Token<'input, T>>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let full_begin = begin;
+        let mut trivia = self.pre_trivia(src, full_begin);
+
+        let body_begin = trivia.last().map_or(full_begin, |trivia| trivia.span.1);
+        let (accepted, _, kind) = self.longest_match(src, body_begin, Role::Body, &filter)?;
+        let body_end = body_begin + accepted;
+
+        let post_trivia = self.post_trivia(src, body_end);
+        let full_end = post_trivia.as_ref().map_or(body_end, |trivia| trivia.span.1);
+        trivia.extend(post_trivia);
+
+        Some(Token {
+            kind: kind.clone(),
+            src,
+            body: (body_begin, body_end),
+            full: (full_begin, full_end),
+            trivia,
+        })
+    }
+
+    fn pre_trivia(&self, src: &str, begin: usize) -> Vec<Trivia<T>> {
+        let mut trivia = vec![];
+        let mut pos = begin;
+        while let Some((len, _, kind)) = self.longest_match(src, pos, Role::PreTrivia, &|_| true) {
+            trivia.push(Trivia { kind: kind.clone(), span: (pos, pos + len) });
+            pos += len;
+        }
+        trivia
+    }
+
+    pub fn skip_pre_trivia(&self, src: &str, begin: usize) -> usize {
+        self.pre_trivia(src, begin)
+            .last()
+            .map_or(begin, |trivia| trivia.span.1)
+    }
+
+    // 字句の直後の Trivia (末尾の改行は次の字句の Trivia とする)
+    fn post_trivia(&self, src: &str, begin: usize) -> Option<Trivia<T>> {
+        let (len, _, kind) = self.longest_match(src, begin, Role::PostTrivia, &|_| true)?;
+        let len = match src[begin..begin + len].ends_with('\n') {
+            true => len - 1,
+            false => len,
+        };
+        match len {
+            0 => None,
+            len => Some(Trivia { kind: kind.clone(), span: (begin, begin + len) }),
+        }
+    }
+
+    // filter を満たす字句のうち最長一致 (同じ長さの場合は先に定義された字句を優先する)
+    fn longest_match(&self, src: &str, begin: usize, role: Role, filter: &dyn Fn(&T) -> bool) -> Option<(usize, usize, &T)> {
+        let dfa = &self.dfa.0;
+        let rest = &src[begin..];
+        let bytes = rest.as_bytes();
+
+        // DFA は 1 バイト遅れて受理状態に入るため，pos バイト目を読んだ時点の受理は pos までの一致を表す
+        let input = Input::new(bytes).anchored(Anchored::Yes);
+        let mut state = dfa.start_state_forward(&input).ok()?;
+        let mut dfa_found = None;
+        let mut alive = true;
+        for (pos, &byte) in bytes.iter().enumerate() {
+            state = dfa.next_state(state, byte);
+            if dfa.is_match_state(state) {
+                if let Some((priority, kind)) = self.best_pattern(state, role, filter, src.as_bytes(), begin, begin + pos) {
+                    dfa_found = Some((pos, priority, kind));
+                }
+            } else if dfa.is_dead_state(state) || dfa.is_quit_state(state) {
+                alive = false;
+                break;
+            }
+        }
+        if alive {
+            state = dfa.next_eoi_state(state);
+            if dfa.is_match_state(state)
+                && let Some((priority, kind)) = self.best_pattern(state, role, filter, src.as_bytes(), begin, src.len())
+            {
+                dfa_found = Some((bytes.len(), priority, kind));
+            }
+        }
+
+        let matcher_found = self.matchers
+            .iter()
+            .filter(|(_, kind, matcher_role, _)| *matcher_role == role && filter(kind))
+            .filter_map(|(matcher, kind, _, priority)| Some((run_matcher(*matcher, rest)?, *priority, kind)));
+
+        dfa_found
+            .into_iter()
+            .chain(matcher_found)
+            .filter(|(len, _, _)| *len > 0)
+            .max_by_key(|(len, priority, _)| (*len, Reverse(*priority)))
+    }
+
+    // 受理状態に含まれるパターンのうち，役割が一致し filter を満たす優先度の最も高いもの
+    // (word オプションを持つ字句は，RegexLexer と同様に入力全体 src における一致の先頭 begin と末尾 end が
+    //  Unicode の単語境界である場合のみ受理する)
+    fn best_pattern(
+        &self,
+        state: StateID,
+        role: Role,
+        filter: &dyn Fn(&T) -> bool,
+        src: &[u8],
+        begin: usize,
+        end: usize,
+    ) -> Option<(usize, &T)> {
+        let dfa = &self.dfa.0;
+        let at_word_boundary = || is_word_boundary(src, begin) && is_word_boundary(src, end);
+        (0..dfa.match_len(state))
+            .map(|idx| &self.patterns[dfa.match_pattern(state, idx).as_usize()])
+            .filter(|(kind, pattern_role, _)| *pattern_role == role && filter(kind))
+            .filter(|(kind, _, _)| !is_word(kind) || at_word_boundary())
+            .map(|(kind, _, priority)| (*priority, kind))
+            .min_by_key(|(priority, _)| *priority)
+    }
+}
+
+// 字句解析の対象となる字句とその優先度 (定義順) ・役割
+// (pre_trivia が定義されている場合は trivia を用いない (RegexLexer と同様))
+fn with_roles<T: TokenTag>(tokens: &[T]) -> impl Iterator<Item = (usize, &T, Role)> {
+    let pre_trivia_opt = match tokens.iter().any(|token| token.as_option_list().contains(&"pre_trivia")) {
+        true => "pre_trivia",
+        false => "trivia",
+    };
+    tokens.iter()
+        .enumerate()
+        .filter(|(_, token)| !is_synthetic(*token))
+        .filter_map(move |(priority, token)| {
+            let opts = token.as_option_list();
+            let role = if opts.contains(&pre_trivia_opt) {
+                Role::PreTrivia
+            } else if opts.contains(&"post_trivia") {
+                Role::PostTrivia
+            } else if opts.contains(&"pre_trivia") || opts.contains(&"trivia") {
+                return None;
+            } else {
+                Role::Body
+            };
+            Some((priority, token, role))
+        })
+}
+
+// 直列化された DFA (復元時に内容を検証する)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Vec<u8>", into = "Vec<u8>")]
+struct SerializedDfa(dense::DFA<Vec<u32>>);
+
+impl From<SerializedDfa> for Vec<u8> {
+    fn from(dfa: SerializedDfa) -> Self {
+        // 環境に依存しないようリトルエンディアンとして直列化する (ビッグエンディアンの環境では復元時にエラーとなる)
+        let (bytes, padding) = dfa.0.to_bytes_little_endian();
+        bytes[padding..].to_vec()
+    }
+}
+
+impl TryFrom<Vec<u8>> for SerializedDfa {
+    type Error = String;
+
+    fn try_from(bytes: Vec<u8>) -> std::result::Result<Self, Self::Error> {
+        // DFA の復元には 4 バイト境界に揃えられた領域が必要となる (from_bytes は実行環境のエンディアンで読み込む)
+        let mut buf = vec![0; bytes.len() + 3];
+        let offset = buf.as_ptr().align_offset(4);
+        let aligned = &mut buf[offset..offset + bytes.len()];
+        aligned.copy_from_slice(&bytes);
+
+        let (dfa, _) = dense::DFA::from_bytes(aligned).map_err(|err| err.to_string())?;
+        Ok(SerializedDfa(dfa.to_owned()))
+    }
+}
@@ -0,0 +1,130 @@
+#![feature(gen_blocks)]
+
+mod dfa;
+
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+use copager_lang::token::{Token, TokenSet, TokenTag, DEFAULT_MODE};
+use copager_lang::Lang;
+use copager_lex::mode::{split_by_mode, LexState};
+use copager_lex::pattern::check_rest_from;
+use copager_lex::{BaseLexer, ContextualLexer};
+use copager_utils::cache::Cacheable;
+use copager_utils::error::Result;
+
+use dfa::DfaMode;
+
+// 全ての字句のパターンを 1 つの最小化された DFA にまとめた字句解析器
+// (入力を 1 度走査するだけで最長一致の字句を求める)
+#[derive(Debug)]
+pub struct DfaLexer<L: Lang> {
+    modes: HashMap<&'static str, DfaMode<L::TokenTag>>,
+}
+
+// 事前に構築した DFA (モード名と DFA の組)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+pub struct DfaLexerCache<T: TokenTag> {
+    modes: Vec<(String, DfaMode<T>)>,
+}
+
+impl<L: Lang> BaseLexer<L> for DfaLexer<L> {
+    fn init() -> Result<Self> {
+        let tokens = L::TokenSet::instantiate();
+
+        let modes = split_by_mode(&tokens)?
+            .into_iter()
+            .map(|(mode, tokens)| Ok((mode, DfaMode::init(&tokens)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(DfaLexer { modes })
+    }
+
+    gen fn run<'input>(&self, input: &'input str) -> Token<'input, L::TokenTag> {
+        let mut state = LexState::new();
+        while let Some(token) = self.modes[state.mode()].extract_token(input, state.pos()) {
+            state.advance(&token);
+            yield token;
+        }
+    }
+
+    fn check_rest(&self, input: &str, end: &LexState) -> Result<()> {
+        let begin = self.modes[end.mode()].skip_pre_trivia(input, end.pos());
+        check_rest_from(input, begin)
+    }
+}
+
+impl<L: Lang> ContextualLexer<L> for DfaLexer<L> {
+    type State = LexState;
+
+    fn start(&self) -> Self::State {
+        LexState::new()
+    }
+
+    fn next_token<'input>(
+        &self,
+        state: &mut Self::State,
+        input: &'input str,
+        acceptable: Option<&[L::TokenTag]>,
+    ) -> Option<Token<'input, L::TokenTag>> {
+        let mode = &self.modes[state.mode()];
+        let token = match acceptable {
+            Some(acceptable) => mode.extract_token_by(input, state.pos(), |token| acceptable.contains(token))?,
+            None => mode.extract_token(input, state.pos())?,
+        };
+        state.advance(&token);
+
+        Some(token)
+    }
+
+    fn rebase(state: &mut Self::State, offset: usize) {
+        state.rebase(offset);
+    }
+}
+
+impl<L> Cacheable<()> for DfaLexer<L>
+where
+    L: Lang,
+    L::TokenTag: Serialize + for<'de> Deserialize<'de>,
+{
+    type Cache = DfaLexerCache<L::TokenTag>;
+
+    fn cache(_: ()) -> Result<Self::Cache> {
+        let tokens = L::TokenSet::instantiate();
+
+        let modes = split_by_mode(&tokens)?
+            .into_iter()
+            .map(|(mode, tokens)| Ok((mode.to_string(), DfaMode::init(&tokens)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DfaLexerCache { modes })
+    }
+
+    fn restore(cache: Self::Cache) -> Self {
+        let tokens = L::TokenSet::instantiate().iter().collect::<Vec<_>>();
+
+        // モード名は字句の定義に含まれる文字列を用いる
+        let mode_names = tokens.iter()
+            .flat_map(|token| token.as_mode_list().iter().copied())
+            .chain([DEFAULT_MODE])
+            .collect::<Vec<&'static str>>();
+        let modes = cache.modes
+            .into_iter()
+            .filter_map(|(name, mode)| {
+                let name = mode_names.iter().find(|mode_name| **mode_name == name)?;
+                let mode_tokens = tokens.iter()
+                    .filter(|token| match token.as_mode_list() {
+                        [] => *name == DEFAULT_MODE,
+                        token_modes => token_modes.contains(name),
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Some((*name, mode.restore(&mode_tokens)))
+            })
+            .collect();
+
+        DfaLexer { modes }
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Serialize, Deserialize};
+use serde_cbor::ser::to_vec_packed;
+use serde_cbor::de::from_slice;
+use serde_cbor::value::{from_value, to_value, Value};
+
+use copager_core::{Generator, Processor};
+use copager_utils::cache::CacheError;
+use copager_lang::token::{ModeAction, TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex_dfa::DfaLexer;
+use copager_parse_lr_lr1::LR1;
+use copager_ir_void::Void;
+
+#[derive(Clone, Lang, Serialize, Deserialize)]
+struct ExprLang (
+    #[tokenset] ExprToken,
+    #[ruleset]  ExprRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet, Serialize, Deserialize)]
+enum ExprToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"\*")]
+    Mul,
+    #[token(r"\(", push_mode = "paren")]
+    BracketL,
+    #[token(r"\)", pop_mode, mode = "paren")]
+    BracketR,
+    #[token(r"[1-9][0-9]*", mode = "default", mode = "paren")]
+    Num,
+    #[token(r"-", mode = "paren")]
+    Minus,
+    #[token(r"[ \t\n]+", trivia, mode = "default", mode = "paren")]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet, Serialize, Deserialize)]
+enum ExprRule {
+    #[tokenset(ExprToken)]
+    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= <term>")]
+    Expr,
+    #[rule("<term> ::= <term> Mul <num>")]
+    #[rule("<term> ::= <num>")]
+    Term,
+    #[rule("<num> ::= BracketL Num Minus Num BracketR")]
+    #[rule("<num> ::= Num")]
+    Num,
+}
+
+type MyGenerator<T> = Generator<T, DfaLexer<T>, LR1<T>>;
+type MyProcessor = Processor<MyGenerator<ExprLang>>;
+
+#[test]
+fn prebuild() -> anyhow::Result<()> {
+    // in build.rs
+    let serialized = to_vec_packed(&build_rs()?)?;
+
+    // in main.rs
+    let deserialized: MyProcessor = from_slice(&serialized)?;
+    let processor = deserialized
+        .restore_lexer_by_cache()?
+        .build_parser()?;
+    processor.process::<Void>("1 + (3 - 2) * 4")?;
+    assert!(processor.process::<Void>("1 - 2").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn prebuild_corrupted() -> anyhow::Result<()> {
+    // キャッシュに含まれる DFA のバイト列を途中で切り詰める
+    let mut value = to_value(&build_rs()?)?;
+    if let Value::Map(fields) = &mut value
+        && let Some(Value::Map(cache)) = fields.get_mut(&Value::Text("cache_lex".to_string()))
+    {
+        let body = cache.get_mut(&Value::Text("body".to_string())).unwrap();
+        let mut body_value: Value = from_slice(&from_value::<Vec<u8>>(body.clone())?)?;
+        truncate_dfa(&mut body_value);
+        *body = to_value(to_vec_packed(&body_value)?)?;
+    }
+    let processor: MyProcessor = from_value(value)?;
+
+    let result = processor.restore_lexer_by_cache();
+    assert!(matches!(result, Err(CacheError::Corrupted(_, _))));
+
+    Ok(())
+}
+
+// DFA のバイト列は整数の長い列として直列化される
+fn truncate_dfa(value: &mut Value) {
+    match value {
+        Value::Array(elems) if elems.len() > 64 && elems.iter().all(|elem| matches!(elem, Value::Integer(_))) => {
+            elems.truncate(16);
+        }
+        Value::Array(elems) => elems.iter_mut().for_each(truncate_dfa),
+        Value::Map(map) => map.values_mut().for_each(truncate_dfa),
+        _ => {}
+    }
+}
+
+fn build_rs() -> anyhow::Result<MyProcessor> {
+    Ok(MyProcessor::new().prebuild_lexer()?)
+}
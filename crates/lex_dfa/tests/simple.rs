@@ -0,0 +1,114 @@
+use copager_lang::token::{Token, TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::mode::LexState;
+use copager_lex::BaseLexer;
+use copager_lex_dfa::DfaLexer;
+use copager_lex_regex::RegexLexer;
+use copager_utils::error::Error;
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"if", ignore_case)]
+    If,
+    #[token(r"in", word)]
+    In,
+    #[token(r"\+")]
+    Plus,
+    #[token(r"\+\+")]
+    Incr,
+    #[token(r"<")]
+    Lt,
+    #[token(r"<<=")]
+    ShlAssign,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(unicode_ident)]
+    Ident,
+    #[token(fn = raw_block)]
+    RawBlock,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+    #[token(r"//[^\n]*", trivia, comment)]
+    _Comment,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= Num")]
+    Expr,
+}
+
+// {{ ... }} で囲まれた範囲
+fn raw_block(s: &str) -> Option<usize> {
+    let rest = s.strip_prefix("{{")?;
+    Some(rest.find("}}")? + 4)
+}
+
+const TEST_INPUTS: [&str; 6] = [
+    "if x in 1 + 2",
+    "in inⓐ",  // ⓐ は識別子を構成しないが Unicode の単語文字である
+    "1in +in",
+    "IF iffy ++ +++ << <<= // comment\n inner in1",
+    "変数 {{ raw + text }} _x1 // 末尾",
+    "",
+];
+
+#[test]
+fn same_as_regex_lexer() {
+    let dfa_lexer = <DfaLexer<TestLang> as BaseLexer<TestLang>>::init().unwrap();
+    let regex_lexer = <RegexLexer<TestLang> as BaseLexer<TestLang>>::init().unwrap();
+    for input in TEST_INPUTS {
+        let by_dfa = dfa_lexer.run(input).map(to_tuple).collect::<Vec<_>>();
+        let by_regex = regex_lexer.run(input).map(to_tuple).collect::<Vec<_>>();
+        assert_eq!(by_dfa, by_regex, "input: {:?}", input);
+    }
+}
+
+#[test]
+fn longest_match() {
+    let lexer = <DfaLexer<TestLang> as BaseLexer<TestLang>>::init().unwrap();
+    let kinds = lexer
+        .run("+++ <<= << iffy if")
+        .map(|token| token.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        TestToken::Incr,
+        TestToken::Plus,
+        TestToken::ShlAssign,
+        TestToken::Lt,
+        TestToken::Lt,
+        TestToken::Ident,
+        TestToken::If,
+    ]);
+}
+
+#[test]
+fn word_boundary() {
+    let lexer = <DfaLexer<TestLang> as BaseLexer<TestLang>>::init().unwrap();
+    let kinds = lexer
+        .run("1in +in")
+        .map(|token| token.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![TestToken::Num, TestToken::Ident, TestToken::Plus, TestToken::In]);
+}
+
+#[test]
+fn check_rest() {
+    let lexer = <DfaLexer<TestLang> as BaseLexer<TestLang>>::init().unwrap();
+    let input = "1 + 2 @ 3";
+    let end = LexState::after(lexer.run(input));
+    assert!(matches!(lexer.check_rest(input, &end), Err(Error::Lex { span: (6, 7), .. })));
+}
+
+fn to_tuple(token: Token<'_, TestToken>) -> (TestToken, (usize, usize), (usize, usize), usize) {
+    (token.kind.clone(), token.body, token.full, token.trivia.len())
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use regex::bytes::Regex;
+
+use copager_lang::token::{Token, TokenSet, TokenTag};
+use copager_lang::Lang;
+use copager_lex::mode::{split_by_mode, LexState};
+use copager_lex::BaseLexer;
+use copager_utils::error::{Error, Result};
+
+use crate::RegexMode;
+
+// regex::bytes による字句解析器 (UTF-8 として不正なバイト列を含む入力も扱える)
+// 生のバイトにマッチさせる場合は (?-u:\xFF) のように Unicode モードを無効化したパターンを用いる
+#[derive(Debug)]
+pub struct RegexBytesLexer<L: Lang> {
+    modes: HashMap<&'static str, RegexMode<L::TokenTag, Regex>>,
+}
+
+impl<L: Lang> BaseLexer<L, [u8]> for RegexBytesLexer<L> {
+    fn init() -> Result<Self> {
+        let tokens = L::TokenSet::instantiate();
+
+        let modes = split_by_mode(&tokens)?
+            .into_iter()
+            .map(|(mode, tokens)| Ok((mode, RegexMode::init(&tokens)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(RegexBytesLexer { modes })
+    }
+
+    gen fn run<'input>(&self, input: &'input [u8]) -> Token<'input, L::TokenTag, [u8]> {
+        let mut state = LexState::new();
+        while let Some(token) = self.modes[state.mode()].extract_token(input, state.pos()) {
+            state.advance(&token);
+            yield token;
+        }
+    }
+
+    fn check_rest(&self, input: &[u8], end: &LexState) -> Result<()> {
+        let begin = self.modes[end.mode()].skip_pre_trivia(input, end.pos());
+        let rest = &input[begin..];
+        if rest.is_empty() {
+            return Ok(());
+        }
+
+        // UTF-8 の文字として読めない場合は 1 バイトをエスケープして報告する
+        let len = utf8_char_len(rest);
+        let found = match std::str::from_utf8(&rest[..len]) {
+            Ok(c) => c.to_string(),
+            Err(_) => format!("\\x{:02X}", rest[0]),
+        };
+        Err(Error::Lex { found, span: (begin, begin + len) })
+    }
+}
+
+// 文字列の入力はバイト列として字句解析し，文字の境界で区切られた字句のみを返す
+impl<L: Lang> BaseLexer<L> for RegexBytesLexer<L> {
+    fn init() -> Result<Self> {
+        <Self as BaseLexer<L, [u8]>>::init()
+    }
+
+    gen fn run<'input>(&self, input: &'input str) -> Token<'input, L::TokenTag> {
+        let mut state = LexState::new();
+        loop {
+            match self.modes[state.mode()].extract_token(input.as_bytes(), state.pos()) {
+                Some(token) if is_char_boundary(input, &token) => {
+                    state.advance(&token);
+                    yield Token::new(token.kind, input, token.body, token.full).with_trivia(token.trivia);
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn check_rest(&self, input: &str, end: &LexState) -> Result<()> {
+        <Self as BaseLexer<L, [u8]>>::check_rest(self, input.as_bytes(), end)
+    }
+}
+
+fn is_char_boundary<T: TokenTag>(input: &str, token: &Token<'_, T, [u8]>) -> bool {
+    let (body_begin, body_end) = token.body;
+    let (full_begin, full_end) = token.full;
+    [body_begin, body_end, full_begin, full_end]
+        .into_iter()
+        .all(|pos| input.is_char_boundary(pos))
+}
+
+fn utf8_char_len(s: &[u8]) -> usize {
+    let len = match s[0] {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    };
+    match std::str::from_utf8(&s[..len.min(s.len())]) {
+        Ok(_) => len,
+        Err(_) => 1,
+    }
+}
@@ -0,0 +1,92 @@
+use copager_lang::token::{TokenMatcher, TokenSource};
+use copager_lex::pattern::run_matcher;
+
+// 文字列 (regex) とバイト列 (regex::bytes) の正規表現を同じように扱うための抽象
+pub(crate) trait Engine: Sized {
+    type Haystack: TokenSource + ?Sized;
+    type Set;
+
+    // 関数による字句を扱えるか (関数は文字列を対象とする)
+    const SUPPORTS_MATCHER: bool;
+
+    fn new(pattern: &str) -> Result<Self, regex::Error>;
+    fn new_set(patterns: &[String]) -> Result<Self::Set, regex::Error>;
+    fn matches(set: &Self::Set, haystack: &Self::Haystack) -> Vec<usize>;
+
+    // 一致した部分をバイト列として返す
+    fn find<'h>(&self, haystack: &'h Self::Haystack) -> Option<&'h [u8]>;
+    fn run_matcher(matcher: TokenMatcher, haystack: &Self::Haystack) -> Option<usize>;
+    fn tail(haystack: &Self::Haystack, pos: usize) -> &Self::Haystack;
+    fn as_bytes(haystack: &Self::Haystack) -> &[u8];
+}
+
+impl Engine for regex::Regex {
+    type Haystack = str;
+    type Set = regex::RegexSet;
+
+    const SUPPORTS_MATCHER: bool = true;
+
+    fn new(pattern: &str) -> Result<Self, regex::Error> {
+        regex::Regex::new(pattern)
+    }
+
+    fn new_set(patterns: &[String]) -> Result<Self::Set, regex::Error> {
+        regex::RegexSet::new(patterns)
+    }
+
+    fn matches(set: &Self::Set, haystack: &str) -> Vec<usize> {
+        set.matches(haystack).into_iter().collect()
+    }
+
+    fn find<'h>(&self, haystack: &'h str) -> Option<&'h [u8]> {
+        Some(regex::Regex::find(self, haystack)?.as_str().as_bytes())
+    }
+
+    fn run_matcher(matcher: TokenMatcher, haystack: &str) -> Option<usize> {
+        run_matcher(matcher, haystack)
+    }
+
+    fn tail(haystack: &str, pos: usize) -> &str {
+        &haystack[pos..]
+    }
+
+    fn as_bytes(haystack: &str) -> &[u8] {
+        haystack.as_bytes()
+    }
+}
+
+impl Engine for regex::bytes::Regex {
+    type Haystack = [u8];
+    type Set = regex::bytes::RegexSet;
+
+    const SUPPORTS_MATCHER: bool = false;
+
+    fn new(pattern: &str) -> Result<Self, regex::Error> {
+        regex::bytes::Regex::new(pattern)
+    }
+
+    fn new_set(patterns: &[String]) -> Result<Self::Set, regex::Error> {
+        regex::bytes::RegexSet::new(patterns)
+    }
+
+    fn matches(set: &Self::Set, haystack: &[u8]) -> Vec<usize> {
+        set.matches(haystack).into_iter().collect()
+    }
+
+    fn find<'h>(&self, haystack: &'h [u8]) -> Option<&'h [u8]> {
+        Some(regex::bytes::Regex::find(self, haystack)?.as_bytes())
+    }
+
+    // 関数による字句は初期化時に拒否される
+    fn run_matcher(_: TokenMatcher, _: &[u8]) -> Option<usize> {
+        unreachable!()
+    }
+
+    fn tail(haystack: &[u8], pos: usize) -> &[u8] {
+        &haystack[pos..]
+    }
+
+    fn as_bytes(haystack: &[u8]) -> &[u8] {
+        haystack
+    }
+}
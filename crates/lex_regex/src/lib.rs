@@ -1,143 +1,287 @@
 #![feature(gen_blocks)]
 
-use regex::{Regex, RegexSet};
+mod bytes;
+mod engine;
 
-use copager_lang::token::{Token, TokenSet, TokenTag};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use copager_lang::token::{Token, TokenMatcher, TokenSet, TokenTag, Trivia};
 use copager_lang::Lang;
-use copager_lex::BaseLexer;
+use copager_lex::mode::{split_by_mode, LexState};
+use copager_lex::pattern::{check_rest_from, invalid_pattern, is_synthetic, is_word, is_word_boundary, to_token_pattern};
+use copager_lex::{BaseLexer, ContextualLexer};
+use copager_utils::error::{Error, GrammarError, Result};
+
+pub use bytes::RegexBytesLexer;
+
+use engine::Engine;
 
 #[derive(Debug)]
 pub struct RegexLexer<L: Lang> {
-    regex_pre_trivia: Option<Regex>,
-    regex_post_trivia: Option<Regex>,
-    regex_set: RegexSet,
-    regex_map: Vec<(Regex, L::TokenTag)>,
+    modes: HashMap<&'static str, RegexMode<L::TokenTag, Regex>>,
+}
+
+// モードごとの正規表現と関数 (優先度は字句の定義順)
+// E は文字列に対する regex::Regex またはバイト列に対する regex::bytes::Regex
+#[derive(Debug)]
+struct RegexMode<T: TokenTag, E: Engine> {
+    regex_pre_trivia: Vec<(E, T)>,
+    regex_post_trivia: Vec<(E, T)>,
+    matcher_pre_trivia: Vec<(TokenMatcher, T)>,
+    regex_set: E::Set,
+    regex_map: Vec<(E, T, usize)>,
+    matchers: Vec<(TokenMatcher, T, usize)>,
 }
 
 impl<L: Lang> BaseLexer<L> for RegexLexer<L> {
-    fn init() -> anyhow::Result<Self> {
+    fn init() -> Result<Self> {
         let tokens = L::TokenSet::instantiate();
 
-        // Trivia 用正規表現の準備
-        let regex_pre_trivia = get_regex_by_opts(&tokens, "pre_trivia")?
-            .or(get_regex_by_opts(&tokens, "trivia")?);
-        let regex_post_trivia = get_regex_by_opts(&tokens, "post_trivia")?;
+        let modes = split_by_mode(&tokens)?
+            .into_iter()
+            .map(|(mode, tokens)| Ok((mode, RegexMode::init(&tokens)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
 
-        // トークンに対応する正規表現集合の準備
-        let regex_set = tokens.iter()
+        Ok(RegexLexer { modes })
+    }
+
+    gen fn run<'input>(&self, input: &'input str) -> Token<'input, L::TokenTag> {
+        let mut state = LexState::new();
+        while let Some(token) = self.modes[state.mode()].extract_token(input, state.pos()) {
+            state.advance(&token);
+            yield token;
+        }
+    }
+
+    fn check_rest(&self, input: &str, end: &LexState) -> Result<()> {
+        let begin = self.modes[end.mode()].skip_pre_trivia(input, end.pos());
+        check_rest_from(input, begin)
+    }
+}
+
+impl<L: Lang> ContextualLexer<L> for RegexLexer<L> {
+    type State = LexState;
+
+    fn start(&self) -> Self::State {
+        LexState::new()
+    }
+
+    fn next_token<'input>(
+        &self,
+        state: &mut Self::State,
+        input: &'input str,
+        acceptable: Option<&[L::TokenTag]>,
+    ) -> Option<Token<'input, L::TokenTag>> {
+        let mode = &self.modes[state.mode()];
+        let token = match acceptable {
+            Some(acceptable) => mode.extract_token_by(input, state.pos(), |token| acceptable.contains(token))?,
+            None => mode.extract_token(input, state.pos())?,
+        };
+        state.advance(&token);
+
+        Some(token)
+    }
+
+    fn rebase(state: &mut Self::State, offset: usize) {
+        state.rebase(offset);
+    }
+}
+
+impl<T: TokenTag, E: Engine> RegexMode<T, E> {
+    fn init(tokens: &[T]) -> Result<Self> {
+        // 関数による字句はバイト列を対象とする場合に扱えない
+        if !E::SUPPORTS_MATCHER
+            && let Some(token) = tokens.iter().find(|token| token.as_matcher().is_some())
+        {
+            let err = GrammarError::InvalidPattern {
+                token: token.as_name().to_string(),
+                pattern: "fn".to_string(),
+                message: "custom matchers are not supported for byte input".to_string(),
+            };
+            return Err(Error::from(err));
+        }
+
+        // Trivia 用正規表現の準備 (post_trivia は正規表現のみ対応)
+        let regex_pre_trivia = match get_regex_by_opts(tokens, "pre_trivia")? {
+            regexes if regexes.is_empty() => get_regex_by_opts(tokens, "trivia")?,
+            regexes => regexes,
+        };
+        let regex_post_trivia = get_regex_by_opts(tokens, "post_trivia")?;
+        let matcher_pre_trivia = tokens.iter()
+            .filter(|token| {
+                let opts = token.as_option_list();
+                opts.contains(&"pre_trivia") || opts.contains(&"trivia")
+            })
+            .filter_map(|token| Some((token.as_matcher()?, token.clone())))
+            .collect();
+
+        // Trivia 以外の字句を正規表現によるものと関数によるものに分ける
+        let (regex_tokens, matcher_tokens): (Vec<_>, Vec<_>) = tokens.iter()
             .filter(|token| {
                 let opts = token.as_option_list();
                 !opts.contains(&"pre_trivia") && !opts.contains(&"trivia") && !opts.contains(&"post_trivia")
             })
-            .map(|token| to_or_regex(token.as_str_list()))
+            .filter(|token| !is_synthetic(*token))
+            .enumerate()
+            .partition(|(_, token)| token.as_matcher().is_none());
+
+        // トークンに対応する正規表現集合の準備
+        let regex_set = regex_tokens.iter()
+            .map(|(_, token)| to_token_regex(*token))
             .collect::<Vec<_>>();
-        let regex_set = RegexSet::new(regex_set)?;
+        let regex_set = match E::new_set(&regex_set) {
+            Ok(regex_set) => regex_set,
+            Err(err) => return Err(invalid_pattern("", &regex_set.join("|"), err)),
+        };
 
         // regex_set の結果からの逆引きで使用するためのマップの用意
-        let regex_map = tokens.iter()
-            .map(|token| Ok((Regex::new(&to_or_regex(token.as_str_list()))?, token)))
-            .collect::<anyhow::Result<Vec<_>>>()?;
+        let regex_map = regex_tokens.into_iter()
+            .map(|(priority, token)| {
+                let pattern = to_token_regex(token);
+                match E::new(&pattern) {
+                    Ok(regex) => Ok((regex, token.clone(), priority)),
+                    Err(err) => Err(invalid_pattern(token.as_name(), &pattern, err)),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(RegexLexer {
+        let matchers = matcher_tokens.into_iter()
+            .map(|(priority, token)| (token.as_matcher().unwrap(), token.clone(), priority))
+            .collect();
+
+        Ok(RegexMode {
             regex_pre_trivia,
             regex_post_trivia,
+            matcher_pre_trivia,
             regex_set,
             regex_map,
+            matchers,
         })
     }
 
-    gen fn run<'input>(&self, input: &'input str) -> Token<'input, L::TokenTag> {
-        let mut pos = 0;
-        loop {
-            match self.extract_token(input, pos) {
-                Some(token) => {
-                    pos = token.full.1;
-                    yield token;
-                }
-                None => return,
-            }
-        }
+    fn extract_token<'input>(&self, src: &'input E::Haystack, begin: usize) -> Option<Token<'input, T, E::Haystack>> {
+        self.extract_token_by(src, begin, |_| true)
     }
-}
 
-impl<'input, L: Lang> RegexLexer<L> {
-    fn extract_token(&self, src: &'input str, begin: usize) -> Option<Token<'input, L::TokenTag>> {
+    fn extract_token_by<'input, F>(&self, src: &'input E::Haystack, begin: usize, filter: F) -> Option<Token<'input, T, E::Haystack>>
+    where
+        F: Fn(&T) -> bool,
+    {
         let full_begin = begin;
-        let pre_trivia_end = full_begin + self.pre_trivia_len(&src[full_begin..]);
+        let mut trivia = self.pre_trivia(src, full_begin);
 
-        let body_begin = pre_trivia_end;
-        let (kind, accepted) = self
-            .regex_set
-            .matches(&src[body_begin..])
+        // 最長一致 (同じ長さの場合は先に定義された字句を優先する)
+        let body_begin = trivia.last().map_or(full_begin, |trivia| trivia.span.1);
+        // word オプションを持つ字句は一致の先頭も単語境界である必要がある
+        // (正規表現は字句の先頭から適用するため，直前の文字は入力全体から調べる)
+        let rest = E::tail(src, body_begin);
+        let word_start = is_word_boundary(E::as_bytes(src), body_begin);
+        let regex_candidates = E::matches(&self.regex_set, rest)
             .into_iter()
             .map(|idx| &self.regex_map[idx])
-            .map(|(regex, token)| {
-                let accepted = regex.find(&src[body_begin..]).unwrap().as_str();
-                (token.clone(), accepted)
-            })
-            .next()?;
-        let body_end = body_begin + accepted.len();
+            .filter(|(_, token, _)| filter(token))
+            .filter(|(_, token, _)| !is_word(token) || word_start)
+            .map(|(regex, token, priority)| {
+                let accepted = regex.find(rest).unwrap().len();
+                (accepted, *priority, token)
+            });
+        let matcher_candidates = self
+            .matchers
+            .iter()
+            .filter(|(_, token, _)| filter(token))
+            .filter_map(|(matcher, token, priority)| {
+                let accepted = E::run_matcher(*matcher, rest)?;
+                Some((accepted, *priority, token))
+            });
+        let (accepted, _, kind) = regex_candidates
+            .chain(matcher_candidates)
+            .max_by_key(|(accepted, priority, _)| (*accepted, Reverse(*priority)))?;
+        let body_end = body_begin + accepted;
 
-        let post_trivia_begin = body_end;
-        let full_end = body_end + self.post_trivia_len(&src[post_trivia_begin..]);
+        let post_trivia = self.post_trivia(src, body_end);
+        let full_end = post_trivia.as_ref().map_or(body_end, |trivia| trivia.span.1);
+        trivia.extend(post_trivia);
 
         Some(Token {
-            kind,
+            kind: kind.clone(),
             src,
             body: (body_begin, body_end),
             full: (full_begin, full_end),
+            trivia,
         })
     }
 
-    fn pre_trivia_len(&self, s: &str) -> usize {
-        if self.regex_pre_trivia.is_none() {
-            return 0;
+    // 連続する Trivia を 1 つずつ読み進める (最長一致，同じ長さの場合は先に定義された字句を優先する)
+    fn pre_trivia(&self, src: &E::Haystack, begin: usize) -> Vec<Trivia<T>> {
+        let mut trivia = vec![];
+        let mut pos = begin;
+        loop {
+            let rest = E::tail(src, pos);
+            let regex_found = self.regex_pre_trivia
+                .iter()
+                .filter_map(|(regex, kind)| Some((regex.find(rest)?.len(), kind)));
+            let matcher_found = self.matcher_pre_trivia
+                .iter()
+                .filter_map(|(matcher, kind)| Some((E::run_matcher(*matcher, rest)?, kind)));
+            match regex_found.chain(matcher_found).min_by_key(|(len, _)| Reverse(*len)) {
+                Some((len, kind)) if len > 0 => {
+                    trivia.push(Trivia { kind: kind.clone(), span: (pos, pos + len) });
+                    pos += len;
+                }
+                _ => return trivia,
+            }
         }
-
-        self.regex_pre_trivia
-            .as_ref()
-            .unwrap()
-            .find(s)
-            .and_then(|acc_s| Some(acc_s.as_str()))
-            .unwrap_or("")
-            .len()
     }
 
-    fn post_trivia_len(&self, s: &str) -> usize {
-        if self.regex_post_trivia.is_none() {
-            return 0;
-        }
+    fn skip_pre_trivia(&self, src: &E::Haystack, begin: usize) -> usize {
+        self.pre_trivia(src, begin)
+            .last()
+            .map_or(begin, |trivia| trivia.span.1)
+    }
 
-        let found = self.regex_post_trivia
-            .as_ref()
-            .unwrap()
-            .find(s)
-            .and_then(|acc_s| Some(acc_s.as_str()))
-            .unwrap_or("");
-        match found {
-            "" => 0,
-            s if &s[s.len()-1..] == "\n" => found.len() - 1,
+    // 字句の直後の Trivia (末尾の改行は次の字句の Trivia とする)
+    fn post_trivia(&self, src: &E::Haystack, begin: usize) -> Option<Trivia<T>> {
+        let rest = E::tail(src, begin);
+        let (found, kind) = self.regex_post_trivia
+            .iter()
+            .filter_map(|(regex, kind)| Some((regex.find(rest)?, kind)))
+            .min_by_key(|(found, _)| Reverse(found.len()))?;
+        let len = match found {
+            b"" => 0,
+            s if s.ends_with(b"\n") => found.len() - 1,
             _ => found.len(),
+        };
+        match len {
+            0 => None,
+            len => Some(Trivia { kind: kind.clone(), span: (begin, begin + len) }),
         }
     }
 }
 
-fn to_or_regex<T: AsRef<str>>(str_list: &[T]) -> String {
-    let str_list = str_list.iter()
-        .map(|s| s.as_ref())
-        .collect::<Vec<_>>()
-        .join("|");
-    format!("^({})", str_list)
+// 字句のオプション (ignore_case, word, unicode_ident) を反映した正規表現
+// (word の一致の先頭における単語境界は extract_token_by で調べる)
+fn to_token_regex<T: TokenTag>(token: &T) -> String {
+    let pattern = to_token_pattern(token);
+    match is_word(token) {
+        true => format!("^({}\\b)", pattern),
+        false => format!("^({})", pattern),
+    }
 }
 
-fn get_regex_by_opts<Ts: TokenSet>(tokens: &Ts, opt: &str) -> anyhow::Result<Option<Regex>> {
-    let tokens = tokens.iter()
-        .filter(|token| token.as_option_list().contains(&opt))
-        .map(|token| token.as_str_list().join("|"))
-        .collect::<Vec<_>>();
-    if tokens.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(Regex::new(&to_or_regex(&tokens))?))
-    }
+// Trivia の種類を区別するため，字句ごとに正規表現を用意する
+fn get_regex_by_opts<T: TokenTag, E: Engine>(tokens: &[T], opt: &str) -> Result<Vec<(E, T)>> {
+    tokens.iter()
+        .filter(|token| token.as_option_list().contains(&opt) && token.as_matcher().is_none())
+        .filter(|token| !is_synthetic(*token))
+        .map(|token| {
+            let pattern = to_token_regex(token);
+            match E::new(&pattern) {
+                Ok(regex) => Ok((regex, token.clone())),
+                Err(err) => Err(invalid_pattern(token.as_name(), &pattern, err)),
+            }
+        })
+        .collect()
 }
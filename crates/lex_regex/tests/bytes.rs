@@ -0,0 +1,90 @@
+use copager_lang::token::{Token, TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::mode::LexState;
+use copager_lex::BaseLexer;
+use copager_lex_regex::RegexBytesLexer;
+use copager_utils::error::Error;
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"=")]
+    Assign,
+    #[token(r"[a-z]+")]
+    Key,
+    #[token(r#""(?-u:[^"\n])*""#)]
+    Value,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<entries> ::= <entries> <entry>")]
+    #[rule("<entries> ::= <entry>")]
+    Entries,
+    #[rule("<entry> ::= Key Assign Value")]
+    Entry,
+}
+
+type MyLexer = RegexBytesLexer<TestLang>;
+
+#[test]
+fn latin1_success() {
+    // "caf\xE9" は Latin-1 における "café"
+    let lexer = <MyLexer as BaseLexer<TestLang, [u8]>>::init().unwrap();
+    let lexer = lexer.run(b"name = \"caf\xE9\"\nsize = \"\xFF\xFE\"".as_slice());
+    assert_eq_tokens(lexer, &[
+        b"name", b"=", b"\"caf\xE9\"",
+        b"size", b"=", b"\"\xFF\xFE\"",
+    ]);
+}
+
+#[test]
+fn latin1_failed() {
+    let lexer = <MyLexer as BaseLexer<TestLang, [u8]>>::init().unwrap();
+    let input = b"name = \xE9".as_slice();
+    assert_eq_tokens(lexer.run(input), &[b"name", b"="]);
+
+    let end = LexState::after(lexer.run(input));
+    match lexer.check_rest(input, &end) {
+        Err(Error::Lex { found, span }) => {
+            assert_eq!(found, "\\xE9");
+            assert_eq!(span, (7, 8));
+        }
+        _ => panic!("lex error is not detected"),
+    }
+}
+
+#[test]
+fn str_input() {
+    let lexer = <MyLexer as BaseLexer<TestLang>>::init().unwrap();
+    let tokens = lexer
+        .run("name = \"café\"")
+        .map(|token| token.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, &["name", "=", "\"café\""]);
+}
+
+fn assert_eq_tokens<'a, T, Il>(mut lexer: Il, expected: &[&[u8]])
+where
+    T: TokenTag,
+    Il: Iterator<Item = Token<'a, T, [u8]>>,
+{
+    for expected_elem in expected {
+        let token = lexer.next();
+        match token {
+            Some(token) => assert_eq!(&token.as_bytes(), expected_elem),
+            None => panic!("unexpected eof"),
+        }
+    }
+    assert!(lexer.next().is_none());
+}
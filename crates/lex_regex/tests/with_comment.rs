@@ -0,0 +1,98 @@
+use copager_lang::token::{TokenSet, TokenTag, Trivia};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::BaseLexer;
+use copager_lex_regex::{RegexBytesLexer, RegexLexer};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"\+")]
+    Plus,
+    #[token(r"[1-9][0-9]*")]
+    Num,
+    #[token(r"[ \t\n]+", pre_trivia)]
+    _Whitespace,
+    #[token(r"//[^\n]*", pre_trivia, comment)]
+    _LineComment,
+    #[token(r"/\*([^*]|\*[^/])*\*/", pre_trivia, comment)]
+    _BlockComment,
+    #[token(r"[ \t]*#[^\n]*", post_trivia, comment)]
+    _TrailingComment,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= <expr> Plus Num")]
+    #[rule("<expr> ::= Num")]
+    Expr,
+}
+
+const TEST_INPUT: &str = "// head\n1 /* inline */ + 2 # tail\n";
+
+#[test]
+fn trivia_pieces() {
+    let lexer = <RegexLexer<TestLang> as BaseLexer<TestLang>>::init().unwrap();
+    let tokens = lexer.run(TEST_INPUT).collect::<Vec<_>>();
+    assert_eq!(tokens.len(), 3);
+
+    // 各 Trivia が種類と位置を持つ
+    assert_eq!(tokens[0].trivia, vec![
+        Trivia { kind: TestToken::_LineComment, span: (0, 7) },
+        Trivia { kind: TestToken::_Whitespace, span: (7, 8) },
+    ]);
+    assert_eq!(tokens[1].trivia, vec![
+        Trivia { kind: TestToken::_Whitespace, span: (9, 10) },
+        Trivia { kind: TestToken::_BlockComment, span: (10, 22) },
+        Trivia { kind: TestToken::_Whitespace, span: (22, 23) },
+    ]);
+    assert_eq!(tokens[2].trailing_trivia().collect::<Vec<_>>(), vec![
+        &Trivia { kind: TestToken::_TrailingComment, span: (26, 33) },
+    ]);
+
+    // Trivia の列は full の範囲を隙間なく覆う
+    for token in &tokens {
+        let leading = token.leading_trivia().map(|trivia| token.trivia_slice(trivia)).collect::<String>();
+        let trailing = token.trailing_trivia().map(|trivia| token.trivia_slice(trivia)).collect::<String>();
+        assert_eq!(format!("{}{}{}", leading, token.as_str(), trailing), token.as_full_str());
+    }
+}
+
+#[test]
+fn comment_or_whitespace() {
+    let lexer = <RegexLexer<TestLang> as BaseLexer<TestLang>>::init().unwrap();
+    let comments = lexer
+        .run(TEST_INPUT)
+        .flat_map(|token| {
+            token.trivia
+                .iter()
+                .filter(|trivia| trivia.is_comment())
+                .map(|trivia| token.trivia_slice(trivia))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(comments, vec!["// head", "/* inline */", " # tail"]);
+}
+
+#[test]
+fn trivia_pieces_by_bytes() {
+    let lexer = <RegexBytesLexer<TestLang> as BaseLexer<TestLang, [u8]>>::init().unwrap();
+    let lexed = lexer
+        .run(TEST_INPUT.as_bytes())
+        .map(|token| token.trivia)
+        .collect::<Vec<_>>();
+
+    let expected = <RegexLexer<TestLang> as BaseLexer<TestLang>>::init()
+        .unwrap()
+        .run(TEST_INPUT)
+        .map(|token| token.trivia)
+        .collect::<Vec<_>>();
+    assert_eq!(lexed, expected);
+}
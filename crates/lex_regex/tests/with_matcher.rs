@@ -0,0 +1,128 @@
+use copager_lang::token::{Token, TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::mode::LexState;
+use copager_lex::BaseLexer;
+use copager_lex_regex::{RegexBytesLexer, RegexLexer};
+use copager_utils::error::{Error, GrammarError};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"let")]
+    Let,
+    #[token(r"[a-z]+")]
+    Ident,
+    #[token(fn = matcher::raw_string)]
+    RawString,
+    #[token(r"r")]
+    R,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+    #[token(fn = matcher::block_comment, trivia)]
+    _BlockComment,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<stmt> ::= Let Ident RawString")]
+    Stmt,
+}
+
+mod matcher {
+    // 入れ子になったブロックコメント
+    pub fn block_comment(s: &str) -> Option<usize> {
+        if !s.starts_with("/*") {
+            return None;
+        }
+
+        let mut depth = 0;
+        let mut pos = 0;
+        while pos < s.len() {
+            if s[pos..].starts_with("/*") {
+                depth += 1;
+                pos += 2;
+            } else if s[pos..].starts_with("*/") {
+                depth -= 1;
+                pos += 2;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            } else {
+                pos += s[pos..].chars().next().unwrap().len_utf8();
+            }
+        }
+        None
+    }
+
+    // r#"..."# 形式の文字列 (# の個数は任意)
+    pub fn raw_string(s: &str) -> Option<usize> {
+        let rest = s.strip_prefix('r')?;
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        let body = rest[hashes..].strip_prefix('"')?;
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let end = body.find(&terminator)?;
+        Some(1 + hashes + 1 + end + terminator.len())
+    }
+}
+
+type MyLexer = RegexLexer<TestLang>;
+
+#[test]
+fn with_matcher_success() {
+    let lexer = MyLexer::init().unwrap();
+    let lexer = lexer.run(r###"let /* a /* nested */ comment */ x r##"say "#hi"#"## r"###);
+    assert_eq_tokens(lexer, &["let", "x", r###"r##"say "#hi"#"##"###, "r"]);
+}
+
+#[test]
+fn longest_match() {
+    // 同じ長さであれば先に定義された字句を，そうでなければ最も長く一致した字句を選ぶ
+    let lexer = MyLexer::init().unwrap();
+    let kinds = lexer
+        .run("let letter")
+        .map(|token| token.kind)
+        .collect::<Vec<_>>();
+    assert!(kinds == [TestToken::Let, TestToken::Ident]);
+}
+
+#[test]
+fn with_matcher_failed() {
+    let lexer = MyLexer::init().unwrap();
+    let input = "let /* unterminated";
+    assert_eq_tokens(lexer.run(input), &["let"]);
+    let end = LexState::after(lexer.run(input));
+    assert!(matches!(lexer.check_rest(input, &end), Err(Error::Lex { span: (4, 5), .. })));
+}
+
+#[test]
+fn with_matcher_bytes() {
+    match <RegexBytesLexer<TestLang> as BaseLexer<TestLang, [u8]>>::init() {
+        Err(Error::Grammar(GrammarError::InvalidPattern { token, .. })) => {
+            assert_eq!(token, "RawString");
+        }
+        _ => panic!("custom matcher is accepted for byte input"),
+    }
+}
+
+fn assert_eq_tokens<'a, T, Il>(mut lexer: Il, expected: &[&str])
+where
+    T: TokenTag,
+    Il: Iterator<Item = Token<'a, T>>,
+{
+    for expected_elem in expected {
+        let token = lexer.next();
+        match token {
+            Some(token) => assert_eq!(&token.as_str(), expected_elem),
+            None => panic!("unexpected eof"),
+        }
+    }
+    assert!(lexer.next().is_none());
+}
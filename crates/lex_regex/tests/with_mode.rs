@@ -0,0 +1,124 @@
+use copager_lang::token::{ModeAction, Token, TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::mode::LexState;
+use copager_lex::BaseLexer;
+use copager_lex_regex::RegexLexer;
+use copager_utils::error::{Error, GrammarError};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"<", push_mode = "tag")]
+    TagL,
+    #[token(r"[^<]+")]
+    Text,
+    #[token(r">", mode = "tag", pop_mode)]
+    TagR,
+    #[token(r"=", mode = "tag")]
+    Equal,
+    #[token(r"[a-z]+", mode = "tag")]
+    Name,
+    #[token(r#"""#, mode = "tag", push_mode = "string")]
+    QuoteL,
+    #[token(r#"[^"]+"#, mode = "string")]
+    StrBody,
+    #[token(r#"""#, mode = "string", pop_mode)]
+    QuoteR,
+    #[token(r"[ \t\n]+", mode = "tag", trivia)]
+    _Whitespace,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<tag> ::= TagL Name TagR")]
+    Tag,
+}
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct UndefinedModeLang (
+    #[tokenset] UndefinedModeToken,
+    #[ruleset]  UndefinedModeRule,
+);
+
+#[derive(Clone, Hash, PartialEq, Eq, TokenSet)]
+enum UndefinedModeToken {
+    #[token(r"<", push_mode = "tag")]
+    TagL,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, RuleSet)]
+enum UndefinedModeRule {
+    #[tokenset(UndefinedModeToken)]
+    #[rule("<tag> ::= TagL")]
+    Tag,
+}
+
+type MyLexer = RegexLexer<TestLang>;
+
+#[test]
+fn with_mode_success() {
+    let lexer = MyLexer::init().unwrap();
+    let lexer = lexer.run(r#"hello <a href = "x y"> world <b>"#);
+    assert_eq_tokens(lexer, &[
+        "hello ", "<", "a", "href", "=", "\"", "x y", "\"", ">",
+        " world ", "<", "b", ">",
+    ]);
+}
+
+#[test]
+fn with_mode_failed() {
+    let lexer = MyLexer::init().unwrap();
+    let input = "text <a = !>";
+    assert_eq_tokens(lexer.run(input), &["text ", "<", "a", "="]);
+
+    // モード tag 中の空白は Trivia として読み飛ばされる
+    let end = LexState::after(lexer.run(input));
+    match lexer.check_rest(input, &end) {
+        Err(Error::Lex { found, span }) => {
+            assert_eq!(found, "!");
+            assert_eq!(span, (10, 11));
+        }
+        _ => panic!("lex error is not detected"),
+    }
+}
+
+#[test]
+fn mode_action() {
+    assert_eq!(TestToken::TagL.as_mode_action(), Some(ModeAction::Push("tag")));
+    assert_eq!(TestToken::QuoteR.as_mode_action(), Some(ModeAction::Pop));
+}
+
+#[test]
+fn undefined_mode() {
+    match RegexLexer::<UndefinedModeLang>::init() {
+        Err(Error::Grammar(GrammarError::UndefinedMode { mode, token })) => {
+            assert_eq!(mode, "tag");
+            assert_eq!(token, "TagL");
+        }
+        _ => panic!("undefined mode is not detected"),
+    }
+}
+
+fn assert_eq_tokens<'a, T, Il>(mut lexer: Il, expected: &[&str])
+where
+    T: TokenTag,
+    Il: Iterator<Item = Token<'a, T>>,
+{
+    for expected_elem in expected {
+        let token = lexer.next();
+        match token {
+            Some(token) => assert_eq!(&token.as_str(), expected_elem),
+            None => panic!("unexpected eof"),
+        }
+    }
+    assert!(lexer.next().is_none());
+}
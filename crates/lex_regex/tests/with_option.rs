@@ -0,0 +1,117 @@
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
+use copager_lang::Lang;
+use copager_lex::BaseLexer;
+use copager_lex_regex::{RegexBytesLexer, RegexLexer};
+
+#[allow(dead_code)]
+#[derive(Lang)]
+struct TestLang (
+    #[tokenset] TestToken,
+    #[ruleset]  TestRule,
+);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
+enum TestToken {
+    #[token(r"let", ignore_case)]
+    Let,
+    #[token(r"in", word)]
+    In,
+    #[token(r"[0-9]+")]
+    Num,
+    #[token(unicode_ident)]
+    Ident,
+    #[token(r"=")]
+    Eq,
+    #[token(r"[ \t\n]+", trivia)]
+    _Whitespace,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, RuleSet)]
+enum TestRule {
+    #[tokenset(TestToken)]
+    #[rule("<expr> ::= Let Ident Eq Num In Ident")]
+    Expr,
+}
+
+type MyLexer = RegexLexer<TestLang>;
+
+#[test]
+fn ignore_case() {
+    let lexer = <MyLexer as BaseLexer<TestLang>>::init().unwrap();
+    let tokens = lexer
+        .run("let LET Let lEt")
+        .map(|token| token.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, vec![TestToken::Let; 4]);
+}
+
+#[test]
+fn word_boundary() {
+    let lexer = <MyLexer as BaseLexer<TestLang>>::init().unwrap();
+
+    // "in" の直後が単語の構成文字である場合は In として扱わない
+    let tokens = lexer
+        .run("in in1 in=")
+        .map(|token| (token.kind.clone(), token.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, vec![
+        (TestToken::In, "in"),
+        (TestToken::Ident, "in1"),
+        (TestToken::In, "in"),
+        (TestToken::Eq, "="),
+    ]);
+
+    // "in" の直前が単語の構成文字である場合も In として扱わない
+    let tokens = lexer
+        .run("1in =in")
+        .map(|token| (token.kind.clone(), token.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, vec![
+        (TestToken::Num, "1"),
+        (TestToken::Ident, "in"),
+        (TestToken::Eq, "="),
+        (TestToken::In, "in"),
+    ]);
+}
+
+#[test]
+fn unicode_ident() {
+    let lexer = <MyLexer as BaseLexer<TestLang>>::init().unwrap();
+    let tokens = lexer
+        .run("let 変数 = 1 in _naïve2")
+        .map(|token| (token.kind.clone(), token.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, vec![
+        (TestToken::Let, "let"),
+        (TestToken::Ident, "変数"),
+        (TestToken::Eq, "="),
+        (TestToken::Num, "1"),
+        (TestToken::In, "in"),
+        (TestToken::Ident, "_naïve2"),
+    ]);
+
+    // 数字から始まる識別子は認めない
+    let tokens = lexer
+        .run("1abc")
+        .map(|token| (token.kind.clone(), token.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, vec![(TestToken::Num, "1"), (TestToken::Ident, "abc")]);
+}
+
+#[test]
+fn options_by_bytes() {
+    let lexer = <RegexBytesLexer<TestLang> as BaseLexer<TestLang, [u8]>>::init().unwrap();
+    let tokens = lexer
+        .run("LET 変数 = 1 in x".as_bytes())
+        .map(|token| token.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, vec![
+        TestToken::Let,
+        TestToken::Ident,
+        TestToken::Eq,
+        TestToken::Num,
+        TestToken::In,
+        TestToken::Ident,
+    ]);
+}
@@ -1,31 +1,65 @@
-use copager_lang::token::{TokenTag, Token};
+use copager_lang::token::{TokenTag, TokenSource, Token};
 use copager_lang::rule::RuleTag;
 use copager_lang::Lang;
+use copager_utils::error::{Error, Result};
 
 pub trait BaseParser<L>
 where
     Self: Sized,
     L: Lang,
 {
-    fn init() -> anyhow::Result<Self>;
-    fn run<'input, Il>(&self, lexer: Il)
-        -> impl Iterator<Item = ParseEvent<'input, L::TokenTag, L::RuleTag>>
+    fn init() -> Result<Self>;
+    fn run<'input, S, Il>(&self, lexer: Il)
+        -> impl Iterator<Item = ParseEvent<'input, L::TokenTag, L::RuleTag, S>>
     where
-        Il: Iterator<Item = Token<'input, L::TokenTag>>;
+        S: TokenSource + ?Sized + 'input,
+        Il: Iterator<Item = Token<'input, L::TokenTag, S>>;
 }
 
-pub enum ParseEvent<'input, T, R>
+pub trait IncrementalParser<L>
+where
+    Self: BaseParser<L>,
+    L: Lang,
+{
+    type State<'parser>: Clone
+    where
+        Self: 'parser;
+
+    fn start(&self) -> Self::State<'_>;
+    fn feed<'parser, 'input, S>(state: &mut Self::State<'parser>, token: Option<Token<'input, L::TokenTag, S>>)
+        -> Vec<ParseEvent<'input, L::TokenTag, L::RuleTag, S>>
+    where
+        Self: 'parser,
+        S: TokenSource + ?Sized + 'input;
+    fn accepts_eof<'parser>(state: &Self::State<'parser>) -> bool
+    where
+        Self: 'parser;
+
+    // 現在の状態で kind の字句を読み込めるか (状態は変化させない)
+    fn accepts<'parser>(state: &Self::State<'parser>, kind: &L::TokenTag) -> bool
+    where
+        Self: 'parser;
+
+    // 現在の状態で次に受理可能な字句の一覧
+    fn acceptable<'parser>(state: &Self::State<'parser>) -> Vec<L::TokenTag>
+    where
+        Self: 'parser;
+}
+
+pub enum ParseEvent<'input, T, R, S = str>
 where
     T: TokenTag,
     R: RuleTag<T>,
+    S: TokenSource + ?Sized,
 {
     // Parsing Event
-    Read(Token<'input, T>),
+    Read(Token<'input, T, S>),
     Parse {
         rule: R,
+        alt: usize,  // 還元に用いた規則の rule.as_rules() における位置
         len: usize,
     },
 
     // Control
-    Err(anyhow::Error),
+    Err(Error),
 }
@@ -1,7 +1,9 @@
 mod first;
 mod follow;
 mod director;
+mod check;
 
 pub use first::FirstSet;
 pub use follow::FollowSet;
 pub use director::DirectorSet;
+pub use check::check_undefined;
@@ -0,0 +1,31 @@
+use std::collections::HashSet;
+
+use copager_lang::token::TokenTag;
+use copager_lang::rule::{RuleElem, RuleSetData, RuleTag};
+use copager_utils::error::{GrammarError, Result};
+
+// 右辺で使用されているが左辺に現れない非終端記号を検出する
+pub fn check_undefined<T, R>(ruleset: &RuleSetData<T, R>) -> Result<()>
+where
+    T: TokenTag,
+    R: RuleTag<T>,
+{
+    let defined = ruleset.rules
+        .iter()
+        .map(|rule| &rule.lhs)
+        .collect::<HashSet<_>>();
+
+    for rule in &ruleset.rules {
+        for elem in &rule.rhs {
+            if let RuleElem::NonTerm(symbol) = elem && !defined.contains(elem) {
+                let err = GrammarError::UndefinedSymbol {
+                    symbol: symbol.clone(),
+                    rule: rule.to_string(),
+                };
+                return Err(err.into());
+            }
+        }
+    }
+
+    Ok(())
+}
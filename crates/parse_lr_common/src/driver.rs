@@ -1,10 +1,11 @@
-use copager_lang::token::{TokenTag, Token};
+use copager_lang::token::{TokenTag, TokenSource, Token};
 use copager_lang::rule::{RuleElem, RuleTag};
 use copager_parse::ParseEvent;
 
-use crate::error::LRError;
+use crate::error;
 use crate::table::{LRAction, LRTable};
 
+#[derive(Debug, Clone)]
 pub struct LRDriver<'table, T, R>
 where
     T: TokenTag,
@@ -13,6 +14,7 @@ where
     table: &'table LRTable<T, R>,
     stack: Vec<usize>,
     accepted: bool,
+    last_pos: usize,
 }
 
 impl<'table, T, R> From<&'table LRTable<T, R>> for LRDriver<'table, T, R>
@@ -25,47 +27,59 @@ where
             table,
             stack: vec![0],
             accepted: false,
+            last_pos: 0,
         }
     }
 }
 
-impl<'table, 'input, T, R> LRDriver<'table, T, R>
+impl<'table, T, R> LRDriver<'table, T, R>
 where
     T: TokenTag,
     R: RuleTag<T>,
 {
     pub fn reset(&mut self) {
         self.stack = vec![0];
+        self.last_pos = 0;
     }
 
-    pub gen fn consume(&mut self, token: Option<Token<'input, T>>) -> ParseEvent<'input, T, R> {
+    pub gen fn consume<'input, S>(&mut self, token: Option<Token<'input, T, S>>) -> ParseEvent<'input, T, R, S>
+    where
+        S: TokenSource + ?Sized + 'input,
+    {
         loop {
             let top = self.stack[self.stack.len() - 1];
             let action = self.table.get_action(top, &token);
+
+            // 読み込んだ字句は複製せずにそのまま渡す
+            if let (LRAction::Shift(new_state), Some(_)) = (action, &token) {
+                let token = token.unwrap();
+                self.stack.push(*new_state);
+                self.last_pos = token.body.1;
+                yield ParseEvent::Read(token);
+                break;
+            }
+
             match (action, &token) {
-                (LRAction::Shift(new_state), Some(token)) => {
-                    self.stack.push(*new_state);
-                    yield ParseEvent::Read(token.clone());
-                    break;
-                },
                 (LRAction::Reduce(rule), _) => {
                     let tag = rule.tag.clone().unwrap();
                     let lhs = lhs_as_str(&rule.lhs);
                     let rhs_len = rhs_len(&rule.rhs);
                     self.stack.truncate(self.stack.len() - rhs_len);
                     self.stack.push(self.table.get_goto(self.stack[self.stack.len()-1], lhs).unwrap());
-                    yield ParseEvent::Parse { rule: tag, len: rhs_len };
+                    yield ParseEvent::Parse { rule: tag, alt: rule.alt, len: rhs_len };
                 },
                 (LRAction::Accept, _) => {
                     self.accepted = true;
                     return;
                 }
                 (LRAction::None, Some(token)) => {
-                    yield ParseEvent::Err(LRError::new_unexpected_token(token.clone()).into());
+                    let expected = self.table.get_expected(top);
+                    yield ParseEvent::Err(error::new_unexpected_token(token, expected));
                     return;
                 }
                 (LRAction::None, None) => {
-                    yield ParseEvent::Err(LRError::new_unexpected_eof().into());
+                    let expected = self.table.get_expected(top);
+                    yield ParseEvent::Err(error::new_unexpected_eof(self.last_pos, expected));
                     return;
                 }
                 _ => unreachable!(),
@@ -76,6 +90,56 @@ where
     pub fn accepted(&self) -> bool {
         self.accepted
     }
+
+    // 現時点で入力が終了した場合に受理されるか
+    pub fn accepts_eof(&self) -> bool {
+        self.simulate(None)
+    }
+
+    // kind のトークンを読み込めるか
+    pub fn accepts(&self, kind: &T) -> bool {
+        self.simulate(Some(kind))
+    }
+
+    // 先読み kind (None は EOF) に対する還元を模倣し，読み込み (EOF の場合は受理) に至るかを調べる
+    // (スタックを複製せず，self.stack[..base] と pushed を連結したものを模倣中のスタックとする)
+    fn simulate(&self, kind: Option<&T>) -> bool {
+        let mut base = self.stack.len();
+        let mut pushed: Vec<usize> = vec![];
+        loop {
+            let top = pushed.last().copied().unwrap_or(self.stack[base - 1]);
+            let action = match kind {
+                Some(kind) => self.table.action_table[top].get(kind).unwrap_or(&LRAction::None),
+                None => &self.table.eof_action_table[top],
+            };
+            match action {
+                LRAction::Shift(_) => return kind.is_some(),
+                LRAction::Accept => return kind.is_none(),
+                LRAction::Reduce(rule) => {
+                    let rhs_len = rhs_len(&rule.rhs);
+                    let popped = rhs_len.min(pushed.len());
+                    pushed.truncate(pushed.len() - popped);
+                    base -= rhs_len - popped;
+
+                    let top = pushed.last().copied().unwrap_or(self.stack[base - 1]);
+                    match self.table.get_goto(top, lhs_as_str(&rule.lhs)) {
+                        Some(next) => pushed.push(next),
+                        None => return false,
+                    }
+                }
+                LRAction::None => return false,
+            }
+        }
+    }
+
+    // 次に受理可能なトークンの一覧
+    pub fn acceptable(&self) -> Vec<T> {
+        self.table.get_acceptable(self.stack[self.stack.len() - 1])
+    }
+
+    pub fn stack(&self) -> &[usize] {
+        &self.stack
+    }
 }
 
 fn lhs_as_str<T: TokenTag>(lhs: &RuleElem<T>) -> &str {
@@ -1,50 +1,44 @@
-use std::fmt::Debug;
-
-use thiserror::Error;
-
-use copager_lang::token::{TokenTag, Token};
+use copager_lang::token::{TokenTag, TokenSource, Token};
 use copager_lang::rule::RuleTag;
-use copager_utils::error::PrettyError;
+use copager_utils::error::{Error, GrammarError, TokenInfo};
 
 use crate::table::LRAction;
 
-#[derive(Debug, Error)]
-pub enum LRError {
-    #[error("Conflict occured at {action_a} and {action_b}")]
-    Conflilct {
-        action_a: String,
-        action_b: String,
-    },
-    #[error("Unexpected token {actual:?} found")]
-    UnexpectedToken {
-        actual: String,
-    },
-    #[error("Unexpected EOF")]
-    UnexpectedEOF,
+pub fn new_conflict<T, R>(
+    state: usize,
+    token: &Option<T>,
+    action_a: &LRAction<T, R>,
+    action_b: &LRAction<T, R>,
+) -> Error
+where
+    T: TokenTag,
+    R: RuleTag<T>,
+{
+    let lookahead = match token {
+        Some(token) => token.as_name().to_string(),
+        None => "EOF".to_string(),
+    };
+    let action_a = format!("{}", action_a);
+    let action_b = format!("{}", action_b);
+    Error::from(GrammarError::Conflict { state, lookahead, action_a, action_b })
 }
 
-impl LRError {
-    pub fn new_conflict<T, R>(action_a: &LRAction<T, R>, action_b: &LRAction<T, R>) -> PrettyError
-    where
-        T: TokenTag,
-        R: RuleTag<T>,
-    {
-        let action_a = format!("{}", action_a);
-        let action_b = format!("{}", action_b);
-        PrettyError::from(LRError::Conflilct{ action_a, action_b })
-    }
-
-    pub fn new_unexpected_token<T>(expected: Token<T>) -> PrettyError
-    where
-        T: TokenTag,
-    {
-        let err = LRError::UnexpectedToken {
-            actual: format!("{:?}", "TODO") // TODO: expected.kind),
-        };
-        PrettyError::from(err).with(expected)
+pub fn new_unexpected_token<T, S>(actual: &Token<T, S>, expected: Vec<String>) -> Error
+where
+    T: TokenTag,
+    S: TokenSource + ?Sized,
+{
+    Error::Syntax {
+        found: Some(TokenInfo::from(actual)),
+        expected,
+        span: actual.body,
     }
+}
 
-    pub fn new_unexpected_eof() -> PrettyError {
-        PrettyError::from(LRError::UnexpectedEOF)
+pub fn new_unexpected_eof(pos: usize, expected: Vec<String>) -> Error {
+    Error::Syntax {
+        found: None,
+        expected,
+        span: (pos, pos),
     }
 }
@@ -0,0 +1,47 @@
+// 構文解析表を table に持つ LR 構文解析器に IncrementalParser を実装する
+#[macro_export]
+macro_rules! impl_incremental_parser {
+    ($parser:ident) => {
+        impl<L: $crate::__private::Lang> $crate::__private::IncrementalParser<L> for $parser<L> {
+            type State<'parser> = $crate::LRDriver<'parser, L::TokenTag, L::RuleTag>
+            where
+                L: 'parser;
+
+            fn start(&self) -> Self::State<'_> {
+                $crate::LRDriver::from(&self.table)
+            }
+
+            fn feed<'parser, 'input, S>(
+                state: &mut Self::State<'parser>,
+                token: Option<$crate::__private::Token<'input, L::TokenTag, S>>,
+            ) -> Vec<$crate::__private::ParseEvent<'input, L::TokenTag, L::RuleTag, S>>
+            where
+                Self: 'parser,
+                S: $crate::__private::TokenSource + ?Sized + 'input,
+            {
+                state.consume(token).collect()
+            }
+
+            fn accepts_eof<'parser>(state: &Self::State<'parser>) -> bool
+            where
+                Self: 'parser,
+            {
+                state.accepts_eof()
+            }
+
+            fn accepts<'parser>(state: &Self::State<'parser>, kind: &L::TokenTag) -> bool
+            where
+                Self: 'parser,
+            {
+                state.accepts(kind)
+            }
+
+            fn acceptable<'parser>(state: &Self::State<'parser>) -> Vec<L::TokenTag>
+            where
+                Self: 'parser,
+            {
+                state.acceptable()
+            }
+        }
+    };
+}
@@ -4,6 +4,7 @@ mod automaton;
 mod error;
 mod driver;
 mod table;
+mod incremental;
 
 // LR 共通部品
 pub use table::{LRAction, LRTable, LRTableBuilder};
@@ -13,3 +14,11 @@ pub use driver::LRDriver;
 pub use automaton::lr0;
 pub use automaton::lr1;
 pub use automaton::lalr1;
+
+// impl_incremental_parser! の展開先から参照する
+#[doc(hidden)]
+pub mod __private {
+    pub use copager_lang::token::{Token, TokenSource};
+    pub use copager_lang::Lang;
+    pub use copager_parse::{IncrementalParser, ParseEvent};
+}
@@ -3,11 +3,12 @@ use std::fmt::Display;
 
 use serde::{Serialize, Deserialize};
 
-use copager_lang::token::{Token, TokenTag};
+use copager_lang::token::{Token, TokenSource, TokenTag};
 use copager_lang::rule::{Rule, RuleElem, RuleTag};
+use copager_utils::error::Result;
 
 use crate::automaton::Automaton;
-use crate::error::LRError;
+use crate::error;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LRAction<T, R>
@@ -52,7 +53,10 @@ where
     T: TokenTag,
     R: RuleTag<T>,
 {
-    pub fn get_action(&self, state: usize, token: &Option<Token<T>>) -> &LRAction<T, R> {
+    pub fn get_action<S>(&self, state: usize, token: &Option<Token<T, S>>) -> &LRAction<T, R>
+    where
+        S: TokenSource + ?Sized,
+    {
         if let Some(token) = token {
             return &self.action_table[state].get(&token.kind).unwrap_or(&LRAction::None)
         } else {
@@ -63,6 +67,29 @@ where
     pub fn get_goto(&self, state: usize, nonterm: &str) -> Option<usize> {
         self.goto_table[state].get(nonterm).copied()
     }
+
+    // 状態 state において受理可能なトークン (EOF を含まない) の一覧
+    pub fn get_acceptable(&self, state: usize) -> Vec<T> {
+        self.action_table[state]
+            .iter()
+            .filter(|(_, action)| **action != LRAction::None)
+            .map(|(token, _)| token.clone())
+            .collect()
+    }
+
+    // 状態 state において受理可能なトークン (EOF を含む) の一覧
+    pub fn get_expected(&self, state: usize) -> Vec<String> {
+        let mut expected = self.action_table[state]
+            .iter()
+            .filter(|(_, action)| **action != LRAction::None)
+            .map(|(token, _)| token.as_name().to_string())
+            .collect::<Vec<_>>();
+        expected.sort();
+        if self.eof_action_table[state] != LRAction::None {
+            expected.push("EOF".to_string());
+        }
+        expected
+    }
 }
 
 #[derive(Debug)]
@@ -122,16 +149,16 @@ where
         }
     }
 
-    pub fn try_set(&mut self, state: usize, token: Option<T>, action: LRAction<T, R>) -> anyhow::Result<()>{
+    pub fn try_set(&mut self, state: usize, token: Option<T>, action: LRAction<T, R>) -> Result<()>{
         if let Some(token) = token {
             if let Some(registered) = self.action_table[state].get(&token) {
-                return Err(LRError::new_conflict(registered, &action).into());
+                return Err(error::new_conflict(state, &Some(token), registered, &action));
             }
             self.action_table[state].insert(token, action);
         } else {
             if self.eof_action_table[state] != LRAction::None {
                 let registered = &self.eof_action_table[state];
-                return Err(LRError::new_conflict(registered, &action).into());
+                return Err(error::new_conflict(state, &None, registered, &action));
             }
             self.eof_action_table[state] = action;
         }
@@ -4,31 +4,33 @@ use std::marker::PhantomData;
 
 use serde::{Serialize, Deserialize};
 
-use copager_lang::token::{Token, TokenTag};
+use copager_lang::token::{Token, TokenSource, TokenTag};
 use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
 use copager_lang::Lang;
 use copager_parse::{BaseParser, ParseEvent};
-use copager_parse_common::rule::FirstSet;
+use copager_parse_common::rule::{check_undefined, FirstSet};
 use copager_parse_lr_common::lr1::LR1DFA;
 use copager_parse_lr_common::lalr1::item::LALR1Item;
 use copager_parse_lr_common::lalr1::LALR1DFA;
-use copager_parse_lr_common::{LRDriver, LRAction, LRTable, LRTableBuilder};
+use copager_parse_lr_common::{impl_incremental_parser, LRDriver, LRAction, LRTable, LRTableBuilder};
 use copager_utils::cache::Cacheable;
+use copager_utils::error::Result;
 
 pub struct LALR1<L: Lang> {
     table: LRTable<L::TokenTag, L::RuleTag>,
 }
 
 impl<L: Lang> BaseParser<L> for LALR1<L> {
-    fn init() -> anyhow::Result<Self> {
+    fn init() -> Result<Self> {
         Ok(LALR1 {
             table: LALR1Table::<L>::init()?,
         })
     }
 
-    gen fn run<'input, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag>
+    gen fn run<'input, S, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag, S>
     where
-        Il: Iterator<Item = Token<'input, L::TokenTag>>,
+        S: TokenSource + ?Sized + 'input,
+        Il: Iterator<Item = Token<'input, L::TokenTag, S>>,
     {
         let mut driver = LRDriver::from(&self.table);
         while !driver.accepted() {
@@ -39,6 +41,8 @@ impl<L: Lang> BaseParser<L> for LALR1<L> {
     }
 }
 
+impl_incremental_parser!(LALR1);
+
 impl<L> Cacheable<()> for LALR1<L>
 where
     L: Lang,
@@ -47,7 +51,7 @@ where
 {
     type Cache = LRTable<L::TokenTag, L::RuleTag>;
 
-    fn cache(_: ()) -> anyhow::Result<Self::Cache> {
+    fn cache(_: ()) -> Result<Self::Cache> {
         Ok(LALR1Table::<L>::init()?)
     }
 
@@ -61,7 +65,7 @@ pub struct LALR1Table<L: Lang> {
 }
 
 impl<L: Lang> LALR1Table<L> {
-    pub fn init() -> anyhow::Result<LRTable<L::TokenTag, L::RuleTag>> {
+    pub fn init() -> Result<LRTable<L::TokenTag, L::RuleTag>> {
         // Rules 準備
         let ruleset = L::RuleSet::instantiate();
 
@@ -73,6 +77,7 @@ impl<L: Lang> LALR1Table<L> {
             vec![RuleElem::new_nonterm(&ruleset.top)],
         );
         ruleset.update_top(top_dummy.clone());
+        check_undefined(&ruleset)?;
 
         // First 集合作成
         let first_set = FirstSet::from(&ruleset);
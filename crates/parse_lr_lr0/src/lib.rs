@@ -4,29 +4,32 @@ use std::marker::PhantomData;
 
 use serde::{Serialize, Deserialize};
 
-use copager_lang::token::{Token, TokenSet, TokenTag};
+use copager_lang::token::{Token, TokenSet, TokenSource, TokenTag};
 use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
 use copager_lang::Lang;
 use copager_parse::{BaseParser, ParseEvent};
+use copager_parse_common::rule::check_undefined;
 use copager_parse_lr_common::lr0::item::LR0Item;
 use copager_parse_lr_common::lr0::LR0DFA;
-use copager_parse_lr_common::{LRDriver, LRAction, LRTable, LRTableBuilder};
+use copager_parse_lr_common::{impl_incremental_parser, LRDriver, LRAction, LRTable, LRTableBuilder};
 use copager_utils::cache::Cacheable;
+use copager_utils::error::Result;
 
 pub struct LR0<L: Lang> {
     table: LRTable<L::TokenTag, L::RuleTag>,
 }
 
 impl<L: Lang> BaseParser<L> for LR0<L>{
-    fn init() -> anyhow::Result<Self> {
+    fn init() -> Result<Self> {
         Ok(LR0 {
             table: LR0Table::<L>::init()?,
         })
     }
 
-    gen fn run<'input, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag>
+    gen fn run<'input, S, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag, S>
     where
-        Il: Iterator<Item = Token<'input, L::TokenTag>>,
+        S: TokenSource + ?Sized + 'input,
+        Il: Iterator<Item = Token<'input, L::TokenTag, S>>,
     {
         let mut driver = LRDriver::from(&self.table);
         while !driver.accepted() {
@@ -37,6 +40,8 @@ impl<L: Lang> BaseParser<L> for LR0<L>{
     }
 }
 
+impl_incremental_parser!(LR0);
+
 impl<L> Cacheable<()> for LR0<L>
 where
     L: Lang,
@@ -45,7 +50,7 @@ where
 {
     type Cache = LRTable<L::TokenTag, L::RuleTag>;
 
-    fn cache(_: ()) -> anyhow::Result<Self::Cache> {
+    fn cache(_: ()) -> Result<Self::Cache> {
         Ok(LR0Table::<L>::init()?)
     }
 
@@ -60,7 +65,7 @@ pub struct LR0Table<L: Lang> {
 }
 
 impl<L: Lang> LR0Table<L> {
-    pub fn init() -> anyhow::Result<LRTable<L::TokenTag, L::RuleTag>> {
+    pub fn init() -> Result<LRTable<L::TokenTag, L::RuleTag>> {
         // Toks 準備
         let tokenset = L::TokenSet::instantiate();
         let ruleset = L::RuleSet::instantiate();
@@ -73,6 +78,7 @@ impl<L: Lang> LR0Table<L> {
             vec![RuleElem::new_nonterm(&ruleset.top)],
         );
         ruleset.update_top(top_dummy.clone());
+        check_undefined(&ruleset)?;
 
         // LR(0) オートマトン作成
         let dfa = LR0DFA::from(&ruleset);
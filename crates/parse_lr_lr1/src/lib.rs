@@ -4,30 +4,32 @@ use std::marker::PhantomData;
 
 use serde::{Serialize, Deserialize};
 
-use copager_lang::token::{Token, TokenTag};
+use copager_lang::token::{Token, TokenSource, TokenTag};
 use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
 use copager_lang::Lang;
 use copager_parse::{BaseParser, ParseEvent};
-use copager_parse_common::rule::FirstSet;
+use copager_parse_common::rule::{check_undefined, FirstSet};
 use copager_parse_lr_common::lr1::item::LR1Item;
 use copager_parse_lr_common::lr1::LR1DFA;
-use copager_parse_lr_common::{LRDriver, LRAction, LRTable, LRTableBuilder};
+use copager_parse_lr_common::{impl_incremental_parser, LRDriver, LRAction, LRTable, LRTableBuilder};
 use copager_utils::cache::Cacheable;
+use copager_utils::error::Result;
 
 pub struct LR1<L: Lang> {
     table: LRTable<L::TokenTag, L::RuleTag>,
 }
 
 impl<L: Lang> BaseParser<L> for LR1<L> {
-    fn init() -> anyhow::Result<Self> {
+    fn init() -> Result<Self> {
         Ok(LR1 {
             table: LR1Table::<L>::init()?,
         })
     }
 
-    gen fn run<'input, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag>
+    gen fn run<'input, S, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag, S>
     where
-        Il: Iterator<Item = Token<'input, L::TokenTag>>,
+        S: TokenSource + ?Sized + 'input,
+        Il: Iterator<Item = Token<'input, L::TokenTag, S>>,
     {
         let mut driver = LRDriver::from(&self.table);
         while !driver.accepted() {
@@ -38,6 +40,8 @@ impl<L: Lang> BaseParser<L> for LR1<L> {
     }
 }
 
+impl_incremental_parser!(LR1);
+
 impl<L> Cacheable<()> for LR1<L>
 where
     L: Lang,
@@ -46,7 +50,7 @@ where
 {
     type Cache = LRTable<L::TokenTag, L::RuleTag>;
 
-    fn cache(_: ()) -> anyhow::Result<Self::Cache> {
+    fn cache(_: ()) -> Result<Self::Cache> {
         Ok(LR1Table::<L>::init()?)
     }
 
@@ -60,7 +64,7 @@ pub struct LR1Table<L: Lang> {
 }
 
 impl<L: Lang> LR1Table<L> {
-    pub fn init() -> anyhow::Result<LRTable<L::TokenTag, L::RuleTag>> {
+    pub fn init() -> Result<LRTable<L::TokenTag, L::RuleTag>> {
         // Rules 準備
         let ruleset = L::RuleSet::instantiate();
 
@@ -72,6 +76,7 @@ impl<L: Lang> LR1Table<L> {
             vec![RuleElem::new_nonterm(&ruleset.top)],
         );
         ruleset.update_top(top_dummy.clone());
+        check_undefined(&ruleset)?;
 
         // First 集合作成
         let first_set = FirstSet::from(&ruleset);
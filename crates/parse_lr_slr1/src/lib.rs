@@ -4,29 +4,31 @@ use std::marker::PhantomData;
 
 use serde::{Serialize, Deserialize};
 
-use copager_lang::token::{Token, TokenTag};
+use copager_lang::token::{Token, TokenSource, TokenTag};
 use copager_lang::rule::{Rule, RuleElem, RuleSet, RuleTag};
 use copager_lang::Lang;
 use copager_parse::{BaseParser, ParseEvent};
-use copager_parse_common::rule::FollowSet;
+use copager_parse_common::rule::{check_undefined, FollowSet};
 use copager_parse_lr_common::lr0::item::LR0Item;
 use copager_parse_lr_common::lr0::LR0DFA;
-use copager_parse_lr_common::{LRDriver, LRAction, LRTable, LRTableBuilder};
+use copager_parse_lr_common::{impl_incremental_parser, LRDriver, LRAction, LRTable, LRTableBuilder};
 use copager_utils::cache::Cacheable;
+use copager_utils::error::Result;
 
 pub struct SLR1<L: Lang> {
     table: LRTable<L::TokenTag, L::RuleTag>,
 }
 
 impl<L: Lang> BaseParser<L> for SLR1<L> {
-    fn init() -> anyhow::Result<Self> {
+    fn init() -> Result<Self> {
         let table = SLR1Table::<L>::init()?;
         Ok(SLR1 { table })
     }
 
-    gen fn run<'input, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag>
+    gen fn run<'input, S, Il>(&self, mut lexer: Il) -> ParseEvent<'input, L::TokenTag, L::RuleTag, S>
     where
-        Il: Iterator<Item = Token<'input, L::TokenTag>>,
+        S: TokenSource + ?Sized + 'input,
+        Il: Iterator<Item = Token<'input, L::TokenTag, S>>,
     {
         let mut driver = LRDriver::from(&self.table);
         while !driver.accepted() {
@@ -37,6 +39,8 @@ impl<L: Lang> BaseParser<L> for SLR1<L> {
     }
 }
 
+impl_incremental_parser!(SLR1);
+
 impl<L> Cacheable<()> for SLR1<L>
 where
     L: Lang,
@@ -45,7 +49,7 @@ where
 {
     type Cache = LRTable<L::TokenTag, L::RuleTag>;
 
-    fn cache(_: ()) -> anyhow::Result<Self::Cache> {
+    fn cache(_: ()) -> Result<Self::Cache> {
         Ok(SLR1Table::<L>::init()?)
     }
 
@@ -59,7 +63,7 @@ pub struct SLR1Table<L: Lang> {
 }
 
 impl<L: Lang> SLR1Table<L> {
-    pub fn init() -> anyhow::Result<LRTable<L::TokenTag, L::RuleTag>> {
+    pub fn init() -> Result<LRTable<L::TokenTag, L::RuleTag>> {
         // Rules 準備
         let ruleset = L::RuleSet::instantiate();
 
@@ -71,6 +75,7 @@ impl<L: Lang> SLR1Table<L> {
             vec![RuleElem::new_nonterm(&ruleset.top)],
         );
         ruleset.update_top(top_dummy.clone());
+        check_undefined(&ruleset)?;
 
         // Follow 集合作成
         let follow_set = FollowSet::from(&ruleset);
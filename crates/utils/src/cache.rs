@@ -1,4 +1,13 @@
+use std::hash::{Hash, Hasher};
+
 use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use copager_lang::Lang;
+use copager_lang::token::{TokenSet, TokenTag};
+use copager_lang::rule::RuleSet;
+
+use crate::error::Result;
 
 pub trait Cacheable<F>
 where
@@ -6,6 +15,85 @@ where
 {
     type Cache: Serialize + for<'de> Deserialize<'de>;
 
-    fn cache(from: F) -> anyhow::Result<Self::Cache>;
+    fn cache(from: F) -> Result<Self::Cache>;
     fn restore(cache: Self::Cache) -> Self;
 }
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("Cache of the {0} is not found")]
+    NotFound(&'static str),
+    #[error("Cache of the {0} cannot be serialized: {1}")]
+    Unserializable(&'static str, String),
+    #[error("Cache of the {0} is corrupted: {1}")]
+    Corrupted(&'static str, String),
+    #[error("Cache of the {target} was built from a different grammar (expected {expected:016x}, found {found:016x})")]
+    GrammarMismatch {
+        target: &'static str,
+        expected: u64,
+        found: u64,
+    },
+}
+
+// 言語定義 (トークン・規則) から算出される安定なハッシュ値
+pub fn fingerprint<L: Lang>() -> u64 {
+    let mut hasher = Fnv1aHasher::default();
+
+    for token in L::TokenSet::instantiate().iter() {
+        token.as_name().hash(&mut hasher);
+        token.as_str_list().hash(&mut hasher);
+        token.as_option_list().hash(&mut hasher);
+        token.as_mode_list().hash(&mut hasher);
+        token.as_mode_action().hash(&mut hasher);
+    }
+
+    for rule in L::RuleSet::instantiate().into_ruleset().rules {
+        rule.id.hash(&mut hasher);
+        rule.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+// 実行ごとに結果が変化しないよう FNV-1a を用いる
+// (整数はポインタ幅やエンディアンに依存しないよう 64 bit 以上のリトルエンディアンとして書き込む)
+struct Fnv1aHasher(u64);
+
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Fnv1aHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
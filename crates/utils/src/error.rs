@@ -1,50 +1,182 @@
-use std::cmp::{max, min};
+mod diagnostic;
+
 use std::error::Error as StdError;
 use std::fmt::Display;
 
 use thiserror::Error;
 
-use copager_lang::token::{TokenTag, Token};
+use copager_lang::token::{TokenTag, TokenSource, Token};
+
+use crate::cache::CacheError;
+
+pub use diagnostic::{Diagnostic, Label, Severity};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unexpected character {found:?} found")]
+    Lex {
+        found: String,
+        span: (usize, usize),
+    },
+    #[error("Unindent does not match any outer indentation level")]
+    Indent {
+        span: (usize, usize),
+    },
+    #[error("{}", syntax_message(.found, .expected))]
+    Syntax {
+        found: Option<TokenInfo>,
+        expected: Vec<String>,
+        span: (usize, usize),
+    },
+    #[error(transparent)]
+    Grammar(#[from] GrammarError),
+    #[error(transparent)]
+    Cache(#[from] CacheError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Token filters cannot be applied in {method}")]
+    UnsupportedFilter {
+        method: &'static str,
+    },
+    #[error("{message}")]
+    IR {
+        message: String,
+        span: Option<(usize, usize)>,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum GrammarError {
+    #[error("Conflict occured at {action_a} and {action_b} (state {state}, lookahead {lookahead})")]
+    Conflict {
+        state: usize,
+        lookahead: String,
+        action_a: String,
+        action_b: String,
+    },
+    #[error("Undefined symbol <{symbol}> is used in {rule}")]
+    UndefinedSymbol {
+        symbol: String,
+        rule: String,
+    },
+    #[error("Invalid pattern {pattern:?} of token {token}: {message}")]
+    InvalidPattern {
+        token: String,
+        pattern: String,
+        message: String,
+    },
+    #[error("Token with option \"{option}\" is not defined")]
+    MissingToken {
+        option: String,
+    },
+    #[error("Undefined mode \"{mode}\" is used in {token}")]
+    UndefinedMode {
+        mode: String,
+        token: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub kind: &'static str,
+    pub text: String,
+    pub span: (usize, usize),
+}
+
+impl<T, S> From<&Token<'_, T, S>> for TokenInfo
+where
+    T: TokenTag,
+    S: TokenSource + ?Sized,
+{
+    fn from(token: &Token<'_, T, S>) -> Self {
+        TokenInfo {
+            kind: token.kind.as_name(),
+            text: token.as_text().into_owned(),
+            span: token.body,
+        }
+    }
+}
+
+impl Error {
+    pub fn span(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Lex { span, .. } => Some(*span),
+            Error::Indent { span } => Some(*span),
+            Error::Syntax { span, .. } => Some(*span),
+            Error::IR { span, .. } => *span,
+            _ => None,
+        }
+    }
+
+    // 入力の一部を解析した際のエラー位置を入力全体における位置に変換する
+    pub fn shift(self, offset: usize) -> Self {
+        let shift = |(l, r): (usize, usize)| (l + offset, r + offset);
+        match self {
+            Error::Lex { found, span } => Error::Lex { found, span: shift(span) },
+            Error::Indent { span } => Error::Indent { span: shift(span) },
+            Error::Syntax { found, expected, span } => {
+                let found = found.map(|token| TokenInfo { span: shift(token.span), ..token });
+                Error::Syntax { found, expected, span: shift(span) }
+            }
+            Error::IR { message, span } => Error::IR { message, span: span.map(shift) },
+            err => err,
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        match self {
+            Error::Lex { span, .. } => {
+                diagnostic.with_label(Label::primary(*span, "unexpected character"))
+            }
+            Error::Indent { span } => {
+                diagnostic.with_label(Label::primary(*span, "inconsistent indentation"))
+            }
+            Error::Syntax { found, span, .. } => {
+                let message = match found {
+                    Some(_) => "unexpected token",
+                    None => "unexpected end of input",
+                };
+                diagnostic.with_label(Label::primary(*span, message))
+            }
+            Error::IR { span: Some(span), .. } => diagnostic.with_label(Label::primary(*span, "")),
+            _ => diagnostic,
+        }
+    }
+
+    pub fn pretty(self, src: &str) -> PrettyError {
+        let diagnostic = self.to_diagnostic();
+        PrettyError::from(self).with_diagnostic(src, |_| diagnostic)
+    }
+}
+
+fn syntax_message(found: &Option<TokenInfo>, expected: &[String]) -> String {
+    let found = match found {
+        Some(token) => format!("Unexpected token {}({:?}) found", token.kind, token.text),
+        None => "Unexpected EOF".to_string(),
+    };
+    match expected {
+        [] => found,
+        [expected] => format!("{}, expected {}", found, expected),
+        _ => format!("{}, expected one of {}", found, expected.join(", ")),
+    }
+}
 
 #[derive(Debug, Error)]
 pub struct PrettyError {
     err: Box<dyn StdError + Send + Sync>,
     src: Option<String>,
-    pos: Option<(usize, usize)>,
+    diagnostic: Diagnostic,
 }
 
 impl Display for PrettyError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fn pretty_print(
-            f: &mut std::fmt::Formatter<'_>,
-            input: &str,
-            pos: (usize, usize)
-        ) -> std::fmt::Result {
-            writeln!(f, "-----")?;
-
-            let (row, col) = (pos.0 as i32 - 1, pos.1 as i32 - 1);
-            let lines = input.split('\n');
-            let neighbor_lines = lines
-                .skip(max(0, row - 2) as usize)
-                .take(min(row + 1, 3) as usize);
-
-            for (idx, line) in neighbor_lines.enumerate() {
-                let row = max(1, row - 1) + (idx as i32);
-                writeln!(f, "{:2}: {}", row, line)?;
-            }
-
-            writeln!(f, "    {}^ here", " ".repeat(col as usize))?;
-            writeln!(f, "Found at line {}, column {}.", row + 1, col + 1)?;
-            writeln!(f, "-----")
-        }
-
-        writeln!(f, "{}", self.err)?;
-        match (&self.src, self.pos) {
-            (Some(src), Some(pos)) => pretty_print(f, &src, pos)?,
-            _ => {},
+        match &self.src {
+            Some(src) => write!(f, "{}", self.diagnostic.render(src)),
+            None => writeln!(f, "{}", self.err),
         }
-
-        Ok(())
     }
 }
 
@@ -53,37 +185,32 @@ impl PrettyError {
     where
         E: StdError + Send + Sync + 'static,
     {
+        let diagnostic = Diagnostic::error(err.to_string());
         PrettyError {
             err: Box::new(err),
             src: None,
-            pos: None,
+            diagnostic,
         }
     }
 
     pub fn with<'input, T: TokenTag>(self, token: Token<'input, T>) -> PrettyError {
-        let mut sum = 0;
-        let (mut rows, mut cols) = (1, 1);
-        for c in token.src.chars() {
-            if token.body.0 <= sum {
-                break;
-            }
-            sum += c.len_utf8();
-
-            match c {
-                '\n' => {
-                    rows += 1;
-                    cols = 1;
-                }
-                _ => {
-                    cols += 1;
-                }
-            }
-        }
+        let label = Label::primary(token.body, "");
+        self.with_diagnostic(token.src, |diagnostic| diagnostic.with_label(label))
+    }
+
+    pub fn at(self, src: &str, pos: usize) -> PrettyError {
+        let label = Label::primary((pos, pos), "");
+        self.with_diagnostic(src, |diagnostic| diagnostic.with_label(label))
+    }
 
+    pub fn with_diagnostic<F>(self, src: &str, f: F) -> PrettyError
+    where
+        F: FnOnce(Diagnostic) -> Diagnostic,
+    {
         PrettyError {
             err: self.err,
-            src: Some(token.src.to_string()),
-            pos: Some((rows, cols)),
+            src: Some(src.to_string()),
+            diagnostic: f(self.diagnostic),
         }
     }
 }
@@ -0,0 +1,279 @@
+use std::fmt::Write;
+
+use unicode_width::UnicodeWidthChar;
+
+const TAB_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: (usize, usize),
+    pub message: String,
+    pub primary: bool,
+}
+
+impl Label {
+    pub fn primary<S: Into<String>>(span: (usize, usize), message: S) -> Self {
+        Label { span, message: message.into(), primary: true }
+    }
+
+    pub fn secondary<S: Into<String>>(span: (usize, usize), message: S) -> Self {
+        Label { span, message: message.into(), primary: false }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file_name: Option<String>,
+    pub source_id: Option<usize>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub helps: Vec<String>,
+    pub color: bool,
+}
+
+impl Diagnostic {
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn warning<S: Into<String>>(message: S) -> Self {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    pub fn new<S: Into<String>>(severity: Severity, message: S) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            file_name: None,
+            source_id: None,
+            labels: vec![],
+            notes: vec![],
+            helps: vec![],
+            color: false,
+        }
+    }
+
+    pub fn with_file<S: Into<String>>(mut self, file_name: S) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    pub fn with_source_id(mut self, source_id: usize) -> Self {
+        self.source_id = Some(source_id);
+        self
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note<S: Into<String>>(mut self, note: S) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help<S: Into<String>>(mut self, help: S) -> Self {
+        self.helps.push(help.into());
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, src: &str) -> String {
+        let mut out = String::new();
+        self.render_to(&mut out, src).unwrap();
+        out
+    }
+
+    fn render_to(&self, out: &mut String, src: &str) -> std::fmt::Result {
+        let (severity, severity_color) = match self.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+        writeln!(
+            out,
+            "{}{}",
+            self.paint(severity, severity_color),
+            self.paint(&format!(": {}", self.message), BOLD),
+        )?;
+
+        // 各ラベルの位置を (行, 行頭, 行末) に変換し，行順に並べる
+        let mut labels = self.labels
+            .iter()
+            .map(|label| (locate(src, label.span.0), label))
+            .collect::<Vec<_>>();
+        labels.sort_by_key(|(loc, label)| (loc.row, label.span.0));
+
+        let gutter = labels
+            .iter()
+            .map(|(loc, _)| loc.row.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(gutter);
+        let bar = self.paint("|", BLUE);
+
+        // 位置情報
+        let primary = self.labels
+            .iter()
+            .find(|label| label.primary)
+            .or(self.labels.first());
+        if let Some(primary) = primary {
+            let loc = locate(src, primary.span.0);
+            let name = match (&self.file_name, self.source_id) {
+                (Some(name), _) => name.clone(),
+                (None, Some(id)) => format!("<source #{}>", id),
+                (None, None) => "<input>".to_string(),
+            };
+            writeln!(out, "{}{} {}:{}:{}", pad, self.paint("-->", BLUE), name, loc.row, loc.col)?;
+        }
+
+        // ソースコードと下線
+        if !labels.is_empty() {
+            writeln!(out, "{} {}", pad, bar)?;
+        }
+        let mut prev_row: Option<usize> = None;
+        for (loc, label) in &labels {
+            if prev_row != Some(loc.row) {
+                if matches!(prev_row, Some(prev) if prev + 1 < loc.row) {
+                    writeln!(out, "{}", self.paint("...", BLUE))?;
+                }
+                let line = &src[loc.line_begin..loc.line_end];
+                let row = self.paint(&format!("{:>width$}", loc.row, width = gutter), BLUE);
+                writeln!(out, "{} {} {}", row, bar, expand_tabs(line))?;
+                prev_row = Some(loc.row);
+            }
+
+            let begin = label.span.0.clamp(loc.line_begin, loc.line_end);
+            let end = label.span.1.clamp(begin, loc.line_end);
+            let offset = display_width(&src[loc.line_begin..begin]);
+            let length = display_width(&src[begin..end]).max(1);
+            let (mark, color) = if label.primary { ("^", severity_color) } else { ("-", BLUE) };
+            let underline = format!("{} {}", mark.repeat(length), label.message);
+            writeln!(
+                out,
+                "{} {} {}{}",
+                pad,
+                bar,
+                " ".repeat(offset),
+                self.paint(underline.trim_end(), color),
+            )?;
+        }
+
+        // 補足情報
+        if !labels.is_empty() && !(self.notes.is_empty() && self.helps.is_empty()) {
+            writeln!(out, "{} {}", pad, bar)?;
+        }
+        for note in &self.notes {
+            writeln!(out, "{} {} {}", pad, self.paint("= note:", BOLD), note)?;
+        }
+        for help in &self.helps {
+            writeln!(out, "{} {} {}", pad, self.paint("= help:", BOLD), help)?;
+        }
+
+        Ok(())
+    }
+
+    fn paint(&self, text: &str, code: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const BLUE: &str = "1;34";
+const BOLD: &str = "1";
+
+struct Location {
+    row: usize,
+    col: usize,
+    line_begin: usize,
+    line_end: usize,
+}
+
+fn locate(src: &str, pos: usize) -> Location {
+    let mut pos = pos.min(src.len());
+    while !src.is_char_boundary(pos) {
+        pos -= 1;
+    }
+
+    let line_begin = src[..pos].rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let line_end = src[pos..].find('\n').map(|idx| pos + idx).unwrap_or(src.len());
+    let line_end = if src[..line_end].ends_with('\r') { line_end - 1 } else { line_end };
+    let row = src[..line_begin].matches('\n').count() + 1;
+    let col = src[line_begin..pos].chars().count() + 1;
+
+    Location { row, col, line_begin, line_end: line_end.max(pos) }
+}
+
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c {
+            '\t' => TAB_WIDTH,
+            c => c.width().unwrap_or(0),
+        })
+        .sum()
+}
+
+fn expand_tabs(s: &str) -> String {
+    s.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Label};
+
+    #[test]
+    fn render_labels() {
+        let src = "let x = 1;\nlet y = x + z;\n";
+        let diagnostic = Diagnostic::error("Undefined variable z")
+            .with_file("main.src")
+            .with_label(Label::secondary((4, 5), "x is defined here"))
+            .with_label(Label::primary((23, 24), "not found"))
+            .with_help("define z before use");
+
+        let expected = concat!(
+            "error: Undefined variable z\n",
+            " --> main.src:2:13\n",
+            "  |\n",
+            "1 | let x = 1;\n",
+            "  |     - x is defined here\n",
+            "2 | let y = x + z;\n",
+            "  |             ^ not found\n",
+            "  |\n",
+            "  = help: define z before use\n",
+        );
+        assert_eq!(diagnostic.render(src), expected);
+    }
+
+    #[test]
+    fn render_wide_chars() {
+        let src = "// 日本語のコメント\nfoo bar";
+        let diagnostic = Diagnostic::error("Unexpected token")
+            .with_label(Label::primary((3, 12), "comment"));
+
+        let expected = concat!(
+            "error: Unexpected token\n",
+            " --> <input>:1:4\n",
+            "  |\n",
+            "1 | // 日本語のコメント\n",
+            "  |    ^^^^^^ comment\n",
+        );
+        assert_eq!(diagnostic.render(src), expected);
+    }
+}
@@ -58,7 +58,8 @@ fn main() -> anyhow::Result<()> {
 
     let sexp = MyProcessor::new()
         .build()?
-        .process::<SExp<_>>(&input)?;
+        .process::<SExp<_>>(&input)
+        .map_err(|err| err.pretty(&input))?;
     println!("Success: {}", sexp);
 
     Ok(())
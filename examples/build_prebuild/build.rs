@@ -1,14 +1,26 @@
-use copager::template::LALR1;
+use copager::template::{LALR1, LR1};
 use copager::Processor;
 
 use language::Arithmetic;
 
-type Config = LALR1<Arithmetic>;
-type MyProcessor = Processor<Config>;
+type LALR1Processor = Processor<LALR1<Arithmetic>>;
+type LR1Processor = Processor<LR1<Arithmetic>>;
 
-#[copager::prebuild]
-fn main() -> MyProcessor {
-    MyProcessor::new()
+#[copager::prebuild(name = "arithmetic_lalr1")]
+fn prebuild_lalr1() -> LALR1Processor {
+    LALR1Processor::new()
         .prebuild_parser()
         .unwrap()
 }
+
+#[copager::prebuild(name = "arithmetic_lr1")]
+fn prebuild_lr1() -> LR1Processor {
+    LR1Processor::new()
+        .prebuild_parser()
+        .unwrap()
+}
+
+fn main() {
+    prebuild_lalr1();
+    prebuild_lr1();
+}
@@ -1,24 +1,44 @@
 use std::io::{stdin, Read};
 
-use copager::template::LALR1;
+use copager::template::{LALR1, LR1};
 use copager::ir::SExp;
 use copager::Processor;
 
 use language::Arithmetic;
 
-type Config = LALR1<Arithmetic>;
-type MyProcessor = Processor<Config>;
+type LALR1Processor = Processor<LALR1<Arithmetic>>;
+type LR1Processor = Processor<LR1<Arithmetic>>;
 
-#[copager::load]
-fn main(processor: MyProcessor) -> anyhow::Result<()> {
-    let mut input = String::new();
-    stdin().read_to_string(&mut input)?;
+#[copager::load(name = "arithmetic_lalr1")]
+fn parse_by_lalr1(processor: LALR1Processor, input: &str) -> anyhow::Result<()> {
+    let sexp = processor
+        .build_lexer()?
+        .restore_parser_by_cache()?
+        .process::<SExp<_>>(input)
+        .map_err(|err| err.pretty(input))?;
+    println!("Success (LALR1): {}", sexp);
+
+    Ok(())
+}
 
+#[copager::load(name = "arithmetic_lr1")]
+fn parse_by_lr1(processor: LR1Processor, input: &str) -> anyhow::Result<()> {
     let sexp = processor
         .build_lexer()?
-        .restore_parser_by_cache()
-        .process::<SExp<_>>(&input)?;
-    println!("Success: {}", sexp);
+        .restore_parser_by_cache()?
+        .process::<SExp<_>>(input)
+        .map_err(|err| err.pretty(input))?;
+    println!("Success (LR1): {}", sexp);
+
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut input = String::new();
+    stdin().read_to_string(&mut input)?;
+
+    parse_by_lalr1(&input)?;
+    parse_by_lr1(&input)?;
 
     Ok(())
 }
@@ -0,0 +1,52 @@
+use copager::ir::{Exportable, Exporter};
+use copager::lang::Lang;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Json,
+    Xml,
+}
+
+// 外部のツール向けに木を出力する指定 (--dump=json|xml，--trivia を指定すると空白とコメントも含める)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DumpArgs {
+    pub format: DumpFormat,
+    pub trivia: bool,
+}
+
+impl DumpArgs {
+    // プログラム名を除いた引数から読む (--dump が指定されていなければ None，知らない引数はエラー)
+    pub fn parse<I, S>(args: I) -> anyhow::Result<Option<Self>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut format = None;
+        let mut trivia = false;
+        for arg in args {
+            match arg.as_ref() {
+                "--trivia" => trivia = true,
+                "--dump=json" => format = Some(DumpFormat::Json),
+                "--dump=xml" => format = Some(DumpFormat::Xml),
+                arg => match arg.strip_prefix("--dump=") {
+                    Some(format) => anyhow::bail!("Unknown dump format: {}", format),
+                    None => anyhow::bail!("Unknown argument: {}", arg),
+                },
+            }
+        }
+        Ok(format.map(|format| DumpArgs { format, trivia }))
+    }
+
+    // 指定された形式で出力する (末尾は改行で終わる)
+    pub fn dump<L, T>(&self, source: &str, tree: &T) -> String
+    where
+        L: Lang,
+        T: Exportable<L>,
+    {
+        let exporter = Exporter::new().source(source).trivia(self.trivia);
+        match self.format {
+            DumpFormat::Json => exporter.to_json(tree) + "\n",
+            DumpFormat::Xml => exporter.to_xml(tree),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use example_common::{DumpArgs, DumpFormat};
+
+#[test]
+fn parse_dump_args() -> anyhow::Result<()> {
+    assert_eq!(DumpArgs::parse(Vec::<String>::new())?, None);
+    assert_eq!(DumpArgs::parse(["--trivia"])?, None);
+    assert_eq!(
+        DumpArgs::parse(["--dump=json", "--trivia"])?,
+        Some(DumpArgs { format: DumpFormat::Json, trivia: true }),
+    );
+    assert_eq!(
+        DumpArgs::parse(["--dump=xml"])?,
+        Some(DumpArgs { format: DumpFormat::Xml, trivia: false }),
+    );
+
+    // 知らない形式や引数はエラーとする
+    assert!(DumpArgs::parse(["--dump=yaml"]).is_err());
+    assert!(DumpArgs::parse(["--dump=json", "--trivial"]).is_err());
+
+    Ok(())
+}
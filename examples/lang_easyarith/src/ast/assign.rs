@@ -1,23 +1,18 @@
-use copager::ir::r#ref::CSTreeWalker;
+use copager::ir::r#ref::FromCst;
 
 use crate::ast::Expr;
 use crate::eval::{Env, Eval};
-use crate::syntax::EasyArith;
+use crate::syntax::{EARule, EAToken};
 
-#[derive(Debug)]
+#[derive(Debug, FromCst)]
+#[cst(rule = EARule::Assign)]
 pub struct Assign<'input> {
+    #[cst(label = "name", token = EAToken::Id)]
     name: &'input str,
+    #[cst(label = "value")]
     expr: Expr<'input>,
 }
 
-impl<'input> From<CSTreeWalker<'input, EasyArith>> for Assign<'input> {
-    fn from(mut walker: CSTreeWalker<'input, EasyArith>) -> Self {
-        let name = walker.expect_leaf().1;
-        let expr = walker.expect_node();
-        Assign { name, expr }
-    }
-}
-
 impl<'input> Eval<'input> for Assign<'input> {
     fn eval(&self, env: &mut Env<'input>) -> i32 {
         match env.vars.get(&self.name) {
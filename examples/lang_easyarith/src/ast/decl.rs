@@ -1,20 +1,15 @@
-use copager::ir::r#ref::CSTreeWalker;
+use copager::ir::r#ref::FromCst;
 
 use crate::eval::{Env, Eval};
-use crate::syntax::EasyArith;
+use crate::syntax::{EARule, EAToken};
 
-#[derive(Debug)]
+#[derive(Debug, FromCst)]
+#[cst(rule = EARule::Decl)]
 pub struct Decl<'input> {
+    #[cst(token = EAToken::Id)]
     name: &'input str,
 }
 
-impl<'input> From<CSTreeWalker<'input, EasyArith>> for Decl<'input> {
-    fn from(mut walker: CSTreeWalker<'input, EasyArith>) -> Self {
-        let name = walker.expect_leaf().1;
-        Decl { name }
-    }
-}
-
 impl<'input> Eval<'input> for Decl<'input> {
     fn eval(&self, env: &mut Env<'input>) -> i32 {
         env.vars.insert(self.name, 0);
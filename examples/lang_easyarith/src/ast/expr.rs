@@ -1,35 +1,21 @@
-use copager::ir::r#ref::CSTreeWalker;
+use copager::ir::r#ref::FromCst;
 
 use crate::ast::Term;
 use crate::eval::{Env, Eval};
-use crate::syntax::{EasyArith, EARule};
+use crate::syntax::EARule;
 
-#[derive(Debug)]
+#[derive(Debug, FromCst)]
+#[cst(rule = EARule::Expr)]
 pub enum Expr<'input> {
     Plus {
+        #[cst(label = "lhs")]
         lhs: Box<Expr<'input>>,
+        #[cst(label = "rhs")]
         rhs: Term<'input>,
     },
     Term(Term<'input>),
 }
 
-impl<'input> From<CSTreeWalker<'input, EasyArith>> for Expr<'input> {
-    fn from(mut walker: CSTreeWalker<'input, EasyArith>) -> Self {
-        match walker.peek().1.unwrap() {
-            EARule::Expr => {
-                let lhs = Box::new(walker.expect_node());
-                let rhs = walker.expect_node();
-                Expr::Plus { lhs, rhs }
-            }
-            EARule::Term => {
-                let term = walker.expect_node();
-                Expr::Term(term)
-            }
-            _ => unreachable!(),
-        }
-    }
-}
-
 impl<'input> Eval<'input> for Expr<'input> {
     fn eval(&self, env: &mut Env<'input>) -> i32 {
         match self {
@@ -1,39 +1,26 @@
-use copager::ir::r#ref::CSTreeWalker;
+use std::num::ParseIntError;
+
+use copager::ir::r#ref::FromCst;
 
 use crate::ast::Expr;
 use crate::eval::{Env, Eval};
-use crate::syntax::{EasyArith, EAToken};
+use crate::syntax::{EARule, EAToken};
 
-#[derive(Debug)]
+#[derive(Debug, FromCst)]
+#[cst(rule = EARule::Fact)]
 pub enum Fact<'input> {
-    Num(i32),
-    Var(&'input str),
+    Num(#[cst(token = EAToken::Num, with = parse_num)] i32),
+    Var(#[cst(token = EAToken::Id)] &'input str),
     Expr(Box<Expr<'input>>),
 }
 
-impl<'input> From<CSTreeWalker<'input, EasyArith>> for Fact<'input> {
-    fn from(mut walker: CSTreeWalker<'input, EasyArith>) -> Self {
-        match walker.peek().0 {
-            Some(EAToken::Num) => {
-                let s = walker.expect_leaf().1;
-                let num = match s {
-                    "0" => Ok(0),
-                    _ if s.starts_with("0b") => i32::from_str_radix(&s[2..], 2),
-                    _ if s.starts_with("0x") => i32::from_str_radix(&s[2..], 16),
-                    _ if s.starts_with("0") => i32::from_str_radix(&s[1..], 8),
-                    _ => s.parse::<i32>(),
-                }.unwrap();
-                Fact::Num(num)
-            }
-            Some(EAToken::Id) => {
-                let var = walker.expect_leaf().1;
-                Fact::Var(var)
-            }
-            _ => {
-                let expr = walker.expect_node();
-                Fact::Expr(Box::new(expr))
-            }
-        }
+fn parse_num(s: &str) -> Result<i32, ParseIntError> {
+    match s {
+        "0" => Ok(0),
+        _ if s.starts_with("0b") => i32::from_str_radix(&s[2..], 2),
+        _ if s.starts_with("0x") => i32::from_str_radix(&s[2..], 16),
+        _ if s.starts_with("0") => i32::from_str_radix(&s[1..], 8),
+        _ => s.parse::<i32>(),
     }
 }
 
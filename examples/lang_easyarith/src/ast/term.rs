@@ -1,35 +1,21 @@
-use copager::ir::r#ref::CSTreeWalker;
+use copager::ir::r#ref::FromCst;
 
 use crate::ast::Fact;
 use crate::eval::{Env, Eval};
-use crate::syntax::{EasyArith, EARule};
+use crate::syntax::EARule;
 
-#[derive(Debug)]
+#[derive(Debug, FromCst)]
+#[cst(rule = EARule::Term)]
 pub enum Term<'input> {
     Mul {
+        #[cst(label = "lhs")]
         lhs: Box<Term<'input>>,
+        #[cst(label = "rhs")]
         rhs: Fact<'input>,
     },
     Fact(Fact<'input>),
 }
 
-impl<'input> From<CSTreeWalker<'input, EasyArith>> for Term<'input> {
-    fn from(mut walker: CSTreeWalker<'input, EasyArith>) -> Self {
-        match walker.peek().1.unwrap() {
-            EARule::Term => {
-                let lhs = Box::new(walker.expect_node());
-                let rhs = walker.expect_node();
-                Term::Mul { lhs, rhs }
-            }
-            EARule::Fact => {
-                let fact = walker.expect_node();
-                Term::Fact(fact)
-            }
-            _ => unreachable!(),
-        }
-    }
-}
-
 impl<'input> Eval<'input> for Term<'input> {
     fn eval(&self, env: &mut Env<'input>) -> i32 {
         match self {
@@ -1,22 +1,15 @@
-use copager::ir::r#ref::CSTreeWalker;
+use copager::ir::r#ref::FromCst;
 
 use crate::ast::Stmt;
 use crate::eval::{Env, Eval};
-use crate::syntax::EasyArith;
+use crate::syntax::EARule;
 
-#[derive(Debug)]
+#[derive(Debug, FromCst)]
+#[cst(rule = EARule::Top)]
 pub struct Top<'input> {
     pub stmts: Vec<Stmt<'input>>,
 }
 
-impl<'input> From<CSTreeWalker<'input, EasyArith>> for Top<'input> {
-    fn from(mut walker: CSTreeWalker<'input, EasyArith>) -> Self {
-        Top {
-            stmts: walker.expect_nodes()
-        }
-    }
-}
-
 impl<'input> Eval<'input> for Top<'input> {
     fn eval(&self, env: &mut Env<'input>) -> i32 {
         for stmt in &self.stmts {
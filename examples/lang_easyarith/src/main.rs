@@ -1,9 +1,11 @@
+use std::env::args;
 use std::io::{stdin, Read};
 
 use copager::template::LALR1;
-use copager::ir::r#ref::{CSTree, CSTreeWalker};
+use copager::ir::r#ref::{CSTree, FromCst};
 use copager::Processor;
 
+use example_common::DumpArgs;
 use example_lang_easyarith::ast::Top;
 use example_lang_easyarith::eval::eval;
 use example_lang_easyarith::syntax::EasyArith;
@@ -15,11 +17,21 @@ fn main() -> anyhow::Result<()> {
     let mut input = String::new();
     stdin().read_to_string(&mut input)?;
 
-    let cst = MyProcessor::new()
-        .build()?
-        .process::<CSTree<_>>(&input)?;
-    let cst_walker = CSTreeWalker::from(cst);
-    let ast = Top::from(cst_walker);
+    let processor = MyProcessor::new().build()?;
+
+    // 外部のツール向けに木を出力する (--dump=json|xml，--trivia を指定すると空白とコメントも含める)
+    if let Some(dump) = DumpArgs::parse(args().skip(1))? {
+        let cst = processor
+            .process::<CSTree<_>>(&input)
+            .map_err(|err| err.pretty(&input))?;
+        print!("{}", dump.dump(&input, &cst));
+        return Ok(());
+    }
+
+    let cst = processor
+        .process::<CSTree<_>>(&input)
+        .map_err(|err| err.pretty(&input))?;
+    let ast = Top::from_cst(&cst)?;
     eval(&ast);
 
     Ok(())
@@ -62,16 +62,16 @@ pub enum EARule {
     Stmt,
     #[rule("<decl> ::= Var Id Semi")]
     Decl,
-    #[rule("<assign> ::= Id Eql <expr> Semi")]
+    #[rule("<assign> ::= name:Id Eql <value:expr> Semi")]
     Assign,
     #[rule("<print> ::= Print <expr> Semi")]
     Print,
 
     // 式
-    #[rule("<expr> ::= <expr> Plus <term>")]
+    #[rule("<expr> ::= <lhs:expr> Plus <rhs:term>")]
     #[rule("<expr> ::= <term>")]
     Expr,
-    #[rule("<term> ::= <term> Mul <fact>")]
+    #[rule("<term> ::= <lhs:term> Mul <rhs:fact>")]
     #[rule("<term> ::= <fact>")]
     Term,
     #[rule("<fact> ::= Num")]
@@ -0,0 +1,138 @@
+use thiserror::Error;
+
+use copager::ir::r#ref::CSTreeWalker;
+use copager::ir::WalkError;
+
+use crate::syntax::{Json, JsonRule, JsonToken};
+
+#[derive(Debug, Error)]
+pub enum AstError {
+    #[error(transparent)]
+    Walk(#[from] WalkError),
+    #[error("Invalid number {0:?}")]
+    Number(String),
+}
+
+// トップレベル要素
+#[derive(Debug)]
+pub struct Document<'input> {
+    pub items: Vec<Value<'input>>,
+}
+
+impl<'input> TryFrom<CSTreeWalker<'input, Json>> for Document<'input> {
+    type Error = AstError;
+
+    fn try_from(mut walker: CSTreeWalker<'input, Json>) -> Result<Self, Self::Error> {
+        // <json> ::= <json> <item> | <item>
+        match walker.len() {
+            2 => {
+                let mut document = walker.try_expect_node_of::<Document>(&JsonRule::Json)?;
+                document.items.push(walker.try_expect_node_of(&JsonRule::Item)?);
+                Ok(document)
+            }
+            _ => {
+                let item = walker.try_expect_node_of(&JsonRule::Item)?;
+                Ok(Document { items: vec![item] })
+            }
+        }
+    }
+}
+
+// 値
+#[derive(Debug, PartialEq)]
+pub enum Value<'input> {
+    Object(Vec<(&'input str, Value<'input>)>),
+    Array(Vec<Value<'input>>),
+    String(&'input str),
+    Number(u64),
+    Bool(bool),
+    Null,
+}
+
+impl<'input> TryFrom<CSTreeWalker<'input, Json>> for Value<'input> {
+    type Error = AstError;
+
+    fn try_from(mut walker: CSTreeWalker<'input, Json>) -> Result<Self, Self::Error> {
+        match walker.peek() {
+            (Some(JsonToken::String), _) => {
+                let s = walker.try_expect_leaf()?.1;
+                Ok(Value::String(s.trim_matches('"')))
+            }
+            (Some(JsonToken::Number), _) => {
+                let s = walker.try_expect_leaf()?.1;
+                s.parse()
+                    .map(Value::Number)
+                    .map_err(|_| AstError::Number(s.to_string()))
+            }
+            (Some(JsonToken::True), _) => {
+                walker.try_expect_leaf()?;
+                Ok(Value::Bool(true))
+            }
+            (Some(JsonToken::False), _) => {
+                walker.try_expect_leaf()?;
+                Ok(Value::Bool(false))
+            }
+            (Some(JsonToken::Null), _) => {
+                walker.try_expect_leaf()?;
+                Ok(Value::Null)
+            }
+            (_, Some(JsonRule::Object)) => {
+                let object = walker.try_expect_node_of::<Object>(&JsonRule::Object)?;
+                Ok(Value::Object(object.0))
+            }
+            _ => {
+                let array = walker.try_expect_node_of::<Array>(&JsonRule::Array)?;
+                Ok(Value::Array(array.0))
+            }
+        }
+    }
+}
+
+// オブジェクト
+struct Object<'input>(Vec<(&'input str, Value<'input>)>);
+
+impl<'input> TryFrom<CSTreeWalker<'input, Json>> for Object<'input> {
+    type Error = AstError;
+
+    fn try_from(mut walker: CSTreeWalker<'input, Json>) -> Result<Self, Self::Error> {
+        let key_values = walker.try_expect_nodes::<KeyValue>()?;
+        Ok(Object(key_values.into_iter().map(|kv| (kv.key, kv.value)).collect()))
+    }
+}
+
+struct KeyValue<'input> {
+    key: &'input str,
+    value: Value<'input>,
+}
+
+impl<'input> TryFrom<CSTreeWalker<'input, Json>> for KeyValue<'input> {
+    type Error = AstError;
+
+    fn try_from(mut walker: CSTreeWalker<'input, Json>) -> Result<Self, Self::Error> {
+        let key = walker.try_expect_node_of::<Key>(&JsonRule::Key)?;
+        let value = walker.try_expect_node_of(&JsonRule::Value)?;
+        Ok(KeyValue { key: key.0, value })
+    }
+}
+
+struct Key<'input>(&'input str);
+
+impl<'input> TryFrom<CSTreeWalker<'input, Json>> for Key<'input> {
+    type Error = AstError;
+
+    fn try_from(mut walker: CSTreeWalker<'input, Json>) -> Result<Self, Self::Error> {
+        let s = walker.try_expect_leaf_of(&JsonToken::String)?.1;
+        Ok(Key(s.trim_matches('"')))
+    }
+}
+
+// 配列
+struct Array<'input>(Vec<Value<'input>>);
+
+impl<'input> TryFrom<CSTreeWalker<'input, Json>> for Array<'input> {
+    type Error = AstError;
+
+    fn try_from(mut walker: CSTreeWalker<'input, Json>) -> Result<Self, Self::Error> {
+        Ok(Array(walker.try_expect_nodes()?))
+    }
+}
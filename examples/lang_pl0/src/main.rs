@@ -1,9 +1,12 @@
+use std::env::args;
 use std::io::{stdin, Read};
 
 use copager::template::LALR1;
 use copager::ir::SExp;
+use copager::ir::r#ref::CSTree;
 use copager::Processor;
 
+use example_common::DumpArgs;
 use example_lang_pl0::syntax::Pl0;
 
 type Config = LALR1<Pl0>;
@@ -13,10 +16,21 @@ fn main() -> anyhow::Result<()> {
     let mut input = String::new();
     stdin().read_to_string(&mut input)?;
 
-    let sexp = MyProcessor::new()
-        .build()?
-        .process::<SExp<_>>(&input)?;
-    println!("Success: {}", sexp);
+    let processor = MyProcessor::new().build()?;
+
+    // 外部のツール向けに木を出力する (--dump=json|xml，--trivia を指定すると空白とコメントも含める)
+    if let Some(dump) = DumpArgs::parse(args().skip(1))? {
+        let cst = processor
+            .process::<CSTree<_>>(&input)
+            .map_err(|err| err.pretty(&input))?;
+        print!("{}", dump.dump(&input, &cst));
+        return Ok(());
+    }
+
+    let sexp = processor
+        .process::<SExp<_>>(&input)
+        .map_err(|err| err.pretty(&input))?;
+    println!("Success:\n{}", sexp.pretty(80));
 
     Ok(())
 }
@@ -11,31 +11,31 @@ pub struct Pl0 (
 #[derive(Debug, Clone, PartialEq, Eq, Hash, TokenSet)]
 pub enum Pl0Token {
     // キーワード
-    #[token(r"const", r"CONST", ir_omit)]
+    #[token(r"const", ignore_case, ir_omit)]
     Const,
-    #[token(r"var", r"VAR", ir_omit)]
+    #[token(r"var", ignore_case, ir_omit)]
     Var,
-    #[token(r"procedure", r"PROCEDURE", ir_omit)]
+    #[token(r"procedure", ignore_case, ir_omit)]
     Procedure,
-    #[token(r"call", r"CALL", ir_omit)]
+    #[token(r"call", ignore_case, ir_omit)]
     Call,
-    #[token(r"begin", r"BEGIN", ir_omit)]
+    #[token(r"begin", ignore_case, ir_omit)]
     Begin,
-    #[token(r"end", r"END", ir_omit)]
+    #[token(r"end", ignore_case, ir_omit)]
     End,
-    #[token(r"if", r"IF", ir_omit)]
+    #[token(r"if", ignore_case, ir_omit)]
     If,
-    #[token(r"then", r"THEN", ir_omit)]
+    #[token(r"then", ignore_case, ir_omit)]
     Then,
-    #[token(r"while", r"WHILE", ir_omit)]
+    #[token(r"while", ignore_case, ir_omit)]
     While,
-    #[token(r"do", r"DO", ir_omit)]
+    #[token(r"do", ignore_case, ir_omit)]
     Do,
-    #[token(r"odd", r"ODD", ir_omit)]
+    #[token(r"odd", ignore_case, ir_omit)]
     Odd,
-    #[token(r"write", r"WRITE", ir_omit)]
+    #[token(r"write", ignore_case, ir_omit)]
     Write,
-    #[token(r"read", r"READ", ir_omit)]
+    #[token(r"read", ignore_case, ir_omit)]
     Read,
 
     // 識別子と数値
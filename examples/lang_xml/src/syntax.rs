@@ -10,26 +10,30 @@ pub struct Xml (
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, TokenSet)]
 pub enum XmlToken {
-    // 記号
-    #[token(r"<", ir_omit)]
+    // タグの外側
+    #[token(r"<", ir_omit, push_mode = "tag")]
     TagL,
-    #[token(r">", ir_omit)]
+    #[token(r"[^<\s]([^<]*[^<\s])?")]
+    Text,
+
+    // タグの内側 (記号)
+    #[token(r">", ir_omit, mode = "tag", pop_mode)]
     TagR,
-    #[token(r"/", ir_omit)]
+    #[token(r"/", ir_omit, mode = "tag")]
     Slash,
-    #[token(r"=", ir_omit)]
+    #[token(r"=", ir_omit, mode = "tag")]
     Equal,
 
-    // 文字列 & 識別子
-    #[token(r"[a-zA-Z_][a-zA-Z0-9_]*")]
+    // タグの内側 (文字列 & 識別子)
+    #[token(r"[a-zA-Z_][a-zA-Z0-9_]*", mode = "tag")]
     String,
-    #[token(r"'[a-zA-Z_][a-zA-Z0-9_]*'")]
+    #[token(r"'[a-zA-Z_][a-zA-Z0-9_]*'", mode = "tag")]
     QuotedString,
-    #[token(r#""[a-zA-Z_][a-zA-Z0-9_]*""#)]
+    #[token(r#""[a-zA-Z_][a-zA-Z0-9_]*""#, mode = "tag")]
     WQuotedString,
 
     // 空白文字
-    #[token(r"[ \t\n]+", trivia)]
+    #[token(r"[ \t\n]+", mode = "default", mode = "tag", trivia)]
     _Whitespace,
 }
 
@@ -78,6 +82,6 @@ pub enum XmlRule {
     ValueList,
 
     #[rule("<value> ::= <tag>")]
-    #[rule("<value> ::= String")]
+    #[rule("<value> ::= Text")]
     Value,
 }
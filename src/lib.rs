@@ -10,6 +10,8 @@ pub mod lex {
     pub use copager_lex::*;
     #[cfg(feature = "regexlex")]
     pub use copager_lex_regex::*;
+    #[cfg(feature = "dfalex")]
+    pub use copager_lex_dfa::*;
 }
 
 pub mod parse {
@@ -32,11 +34,13 @@ pub mod ir {
     pub use copager_ir_sexp::*;
     #[cfg(feature = "tree")]
     pub use copager_ir_tree::*;
+    #[cfg(feature = "action")]
+    pub use copager_ir_action::*;
 }
 
 pub mod prelude {
     pub use copager_lang::rule::{Rule, RuleElem, RuleTag};
-    pub use copager_lang::token::TokenTag;
+    pub use copager_lang::token::{ModeAction, TokenTag};
 }
 
 #[cfg(feature = "prebuild")]
@@ -44,8 +48,15 @@ pub use copager_core_macros::*;
 
 #[cfg(feature = "prebuild")]
 pub mod prebuild {
+    use copager_core::CacheError;
+
     pub use serde_json::to_string as __serialize;
     pub use serde_json::from_str as __deserialize;
+
+    // #[copager::load] が読み込んだキャッシュを復元できない場合のエラー
+    pub fn __corrupted(name: &'static str, err: serde_json::Error) -> CacheError {
+        CacheError::Corrupted(name, err.to_string())
+    }
 }
 
 #[cfg(feature = "template")]
@@ -61,6 +72,22 @@ pub mod template {
     pub type SLR1<T> = Generator<T, RegexLexer<T>, copager_parse_lr_slr1::SLR1<T>>;
     #[cfg(feature = "lalr1")]
     pub type LALR1<T> = Generator<T, RegexLexer<T>, copager_parse_lr_lalr1::LALR1<T>>;
+
+    // 字句解析器に DfaLexer を用いる構成
+    #[cfg(feature = "dfalex")]
+    pub mod dfa {
+        use copager_core::Generator;
+        use copager_lex_dfa::DfaLexer;
+
+        #[cfg(feature = "lr0")]
+        pub type LR0<T> = Generator<T, DfaLexer<T>, copager_parse_lr_lr0::LR0<T>>;
+        #[cfg(feature = "lr1")]
+        pub type LR1<T> = Generator<T, DfaLexer<T>, copager_parse_lr_lr1::LR1<T>>;
+        #[cfg(feature = "slr1")]
+        pub type SLR1<T> = Generator<T, DfaLexer<T>, copager_parse_lr_slr1::SLR1<T>>;
+        #[cfg(feature = "lalr1")]
+        pub type LALR1<T> = Generator<T, DfaLexer<T>, copager_parse_lr_lalr1::LALR1<T>>;
+    }
 }
 
 #[cfg(feature = "dev")]
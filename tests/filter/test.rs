@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+
+use copager::lang::Lang;
+use copager::lex::{BaseLexer, RegexLexer, TokenFilter};
+use copager::lex::{SemicolonInsertion, RetagToken, SplitToken, DropToken};
+use copager::template::LALR1;
+use copager::ir::Void;
+use copager::{Error, Processor};
+
+use example_lang_easyarith::syntax::{EasyArith, EAToken};
+use example_lang_pl0::syntax::{Pl0, Pl0Token};
+
+#[test]
+fn semicolon_insertion() {
+    let input = "var a\na = 1 + 2\nprint a * (3 + 4)\n";
+    let kinds = apply::<EasyArith, _>(input, &easyarith_asi())
+        .into_iter()
+        .filter(|(kind, _)| *kind == EAToken::Semi)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        (EAToken::Semi, (5, 5)),
+        (EAToken::Semi, (15, 15)),
+        (EAToken::Semi, (33, 33)),
+    ]);
+
+    Processor::<LALR1<EasyArith>>::new()
+        .with_filter(easyarith_asi())
+        .build()
+        .unwrap()
+        .process::<Void>(input)
+        .unwrap();
+}
+
+#[test]
+fn semicolon_insertion_unless_before() {
+    let input = "var a;\na = 1;\n";
+    let semis = apply::<EasyArith, _>(input, &easyarith_asi())
+        .into_iter()
+        .filter(|(kind, _)| *kind == EAToken::Semi)
+        .count();
+    assert_eq!(semis, 2);
+}
+
+#[test]
+fn retag_keyword() {
+    let input = "var x;\nprocedure read;\n  x := 1;\ncall read.";
+    let retag = || {
+        RetagToken::<Pl0>::new([Pl0Token::Read, Pl0Token::Write], Pl0Token::Ident)
+            .after([Pl0Token::Var, Pl0Token::Comma, Pl0Token::Procedure, Pl0Token::Call])
+    };
+
+    let kinds = apply::<Pl0, _>(input, &retag())
+        .into_iter()
+        .map(|(kind, _)| kind)
+        .collect::<Vec<_>>();
+    assert!(!kinds.contains(&Pl0Token::Read));
+
+    let processor = Processor::<LALR1<Pl0>>::new().build().unwrap();
+    assert!(processor.process::<Void>(input).is_err());
+
+    let processor = Processor::<LALR1<Pl0>>::new()
+        .with_filter(retag())
+        .build()
+        .unwrap();
+    assert!(processor.process::<Void>(input).is_ok());
+}
+
+#[test]
+fn split_token() {
+    let filter = SplitToken::<Pl0>::new(Pl0Token::Geq, [(Pl0Token::Gtr, 1), (Pl0Token::Eql, 1)]);
+    let tokens = apply::<Pl0, _>("x >= 1", &filter);
+    assert_eq!(tokens, vec![
+        (Pl0Token::Ident, (0, 1)),
+        (Pl0Token::Gtr, (2, 3)),
+        (Pl0Token::Eql, (3, 4)),
+        (Pl0Token::Number, (5, 6)),
+    ]);
+
+    // 長さが一致しない場合は分割しない
+    let filter = SplitToken::<Pl0>::new(Pl0Token::Geq, [(Pl0Token::Gtr, 1)]);
+    let tokens = apply::<Pl0, _>("x >= 1", &filter);
+    assert_eq!(tokens[1], (Pl0Token::Geq, (2, 4)));
+}
+
+#[test]
+fn drop_and_record() {
+    let record = Arc::new(Mutex::new(vec![]));
+    let filter = DropToken::<EasyArith>::new([EAToken::Semi]).record_to(Arc::clone(&record));
+    let tokens = apply::<EasyArith, _>("var a;\nprint a;", &filter);
+    assert!(tokens.iter().all(|(kind, _)| *kind != EAToken::Semi));
+
+    let record = record.lock().unwrap();
+    let spans = record.iter().map(|token| token.body).collect::<Vec<_>>();
+    assert_eq!(spans, vec![(5, 6), (14, 15)]);
+    assert!(record.iter().all(|token| token.as_str() == ";"));
+}
+
+#[test]
+fn filter_chain() {
+    // 既存の区切り記号を取り除いた後に改行位置へ挿入し直す
+    let record = Arc::new(Mutex::new(vec![]));
+    let processor = Processor::<LALR1<EasyArith>>::new()
+        .with_filter(DropToken::new([EAToken::Semi]).record_to(Arc::clone(&record)))
+        .with_filter(easyarith_asi())
+        .build()
+        .unwrap();
+
+    assert!(processor.process::<Void>("var a;\na = 1;\nprint a;\n").is_ok());
+    assert_eq!(record.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn filter_unsupported() {
+    // 字句列全体を扱えない解析方法ではフィルタを無視せずエラーとする
+    let processor = Processor::<LALR1<EasyArith>>::new()
+        .with_filter(easyarith_asi())
+        .build()
+        .unwrap();
+
+    let input = "var a\na = 1\n";
+    let err = processor.process_reader::<Void, _>(input.as_bytes()).unwrap_err();
+    assert!(matches!(err, Error::UnsupportedFilter { method: "process_reader" }));
+    let err = processor.process_contextual::<Void>(input).unwrap_err();
+    assert!(matches!(err, Error::UnsupportedFilter { method: "process_contextual" }));
+}
+
+fn easyarith_asi() -> SemicolonInsertion<EasyArith> {
+    SemicolonInsertion::new(EAToken::Semi)
+        .after([EAToken::Num, EAToken::Id, EAToken::RPar])
+        .unless_before([EAToken::Semi])
+}
+
+fn apply<L, F>(input: &str, filter: &F) -> Vec<(L::TokenTag, (usize, usize))>
+where
+    L: Lang,
+    F: TokenFilter<L>,
+{
+    let lexer = <RegexLexer<L> as BaseLexer<L>>::init().unwrap();
+    let tokens = BaseLexer::<L>::run(&lexer, input);
+    filter
+        .apply(Box::new(tokens))
+        .map(|token| (token.kind, token.body))
+        .collect()
+}
@@ -1,6 +1,7 @@
 mod utils;
 
 use copager::template::LALR1;
+use copager::template::dfa::LALR1 as DfaLALR1;
 use copager::ir::Void;
 use copager::Processor;
 
@@ -18,12 +19,34 @@ fn fail() {
     test_dir("tests/pl0/fail", Expect::Err, &parse);
 }
 
+#[test]
+fn success_by_dfa() {
+    test_dir("tests/pl0/success", Expect::Ok, &parse_by_dfa);
+}
+
+#[test]
+fn fail_by_dfa() {
+    test_dir("tests/pl0/fail", Expect::Err, &parse_by_dfa);
+}
+
 fn parse(input: &str) -> anyhow::Result<()> {
     type Config = LALR1<Pl0>;
     type MyProcessor = Processor<Config>;
 
     MyProcessor::new()
         .build()?
-        .process::<Void>(input)
-        .and_then(|_| Ok(()))
+        .process::<Void>(input)?;
+
+    Ok(())
+}
+
+fn parse_by_dfa(input: &str) -> anyhow::Result<()> {
+    type Config = DfaLALR1<Pl0>;
+    type MyProcessor = Processor<Config>;
+
+    MyProcessor::new()
+        .build()?
+        .process::<Void>(input)?;
+
+    Ok(())
 }
@@ -1,6 +1,8 @@
 mod utils;
 
 use copager::template::LALR1;
+use copager::template::dfa::LALR1 as DfaLALR1;
+use copager::lex::{BaseLexer, RegexLexer, StreamLexer};
 use copager::ir::Void;
 use copager::Processor;
 
@@ -18,12 +20,70 @@ fn fail() {
     test_dir("tests/xml/fail", Expect::Err, &parse);
 }
 
+#[test]
+fn success_by_dfa() {
+    test_dir("tests/xml/success", Expect::Ok, &parse_by_dfa);
+}
+
+#[test]
+fn fail_by_dfa() {
+    test_dir("tests/xml/fail", Expect::Err, &parse_by_dfa);
+}
+
+#[test]
+fn success_by_stream() {
+    test_dir("tests/xml/success", Expect::Ok, &lex_by_stream);
+}
+
+#[test]
+fn large_input_by_reader() -> anyhow::Result<()> {
+    type Config = LALR1<Xml>;
+    type MyProcessor = Processor<Config>;
+
+    // 読み込みの単位の境界をタグの内側 (モード tag) が何度もまたぐ入力
+    let input = format!("<root>{}</root>", "<a x=\"cc\">text</a>".repeat(20000));
+    MyProcessor::new()
+        .build()?
+        .process_reader::<Void, _>(input.as_bytes())?;
+
+    Ok(())
+}
+
 fn parse(input: &str) -> anyhow::Result<()> {
     type Config = LALR1<Xml>;
     type MyProcessor = Processor<Config>;
 
     MyProcessor::new()
         .build()?
-        .process::<Void>(input)
-        .and_then(|_| Ok(()))
+        .process::<Void>(input)?;
+
+    Ok(())
+}
+
+fn parse_by_dfa(input: &str) -> anyhow::Result<()> {
+    type Config = DfaLALR1<Xml>;
+    type MyProcessor = Processor<Config>;
+
+    MyProcessor::new()
+        .build()?
+        .process::<Void>(input)?;
+
+    Ok(())
+}
+
+// 少しずつ読み込んだ場合も入力全体を字句解析した場合と同じ字句が得られる
+fn lex_by_stream(input: &str) -> anyhow::Result<()> {
+    let lexer = RegexLexer::<Xml>::init()?;
+    let expected = lexer
+        .run(input)
+        .map(|token| (token.kind, token.body, token.full))
+        .collect::<Vec<_>>();
+    let tokens = StreamLexer::new(&lexer, input.as_bytes())
+        .with_chunk_size(7)
+        .owned_tokens()
+        .map(|token| token.map(|token| (token.kind, token.body, token.full)))
+        .collect::<Result<Vec<_>, _>>()?;
+    anyhow::ensure!(tokens == expected, "tokens differ from RegexLexer::run");
+
+    Ok(())
 }